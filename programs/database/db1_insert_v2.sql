@@ -1,10 +1,16 @@
-INSERT INTO Lists (title) VALUES
-    ("Empty List"),
-    ("Set Only List"),
-    ("To Do Only List"),
-    ("Flat Mixed List"),
-    ("Nested To Dos Only List"),
-    ("Nested and Flat To Dos List");
+-- Seeds a database the server has migrated, see migrations/.
+
+-- A demo account owning every seeded list. Its password is "password".
+INSERT INTO Users (username, password_hash) VALUES
+    ("demo", "$argon2id$v=19$m=19456,t=2,p=1$FWJ7R09YUA4eK0/A+btEFw$F7J1qlx6RepOXCWIc+nXBDiyoCZI1kY4+xVLeOBEfA8");
+
+INSERT INTO Lists (owner_id, title) VALUES
+    (1, "Empty List"),
+    (1, "Set Only List"),
+    (1, "To Do Only List"),
+    (1, "Flat Mixed List"),
+    (1, "Nested To Dos Only List"),
+    (1, "Nested and Flat To Dos List");
 
 -- Sets for "Set Only List" (list_id = 2)
 INSERT INTO Sets (list_id, title) VALUES
@@ -454,3 +460,23 @@ INSERT INTO Todos (list_id, set_id, title, complete, due_date) VALUES
     (6, NULL, "Completed Scheduled Todo 4", 1, "2025-09-24T16:00:00Z"),
     (6, NULL, "Completed Scheduled Todo 5", 1, "2025-09-25T11:30:00Z"),
     (6, NULL, "Completed Scheduled Todo 6", 1, "2025-09-26T15:00:00Z");
+
+-- Child sets for "Nested To Dos Only List" (list_id = 5)
+INSERT INTO Sets (list_id, parent_id, title) VALUES
+    (5, 13, "Child Set 1"),
+    (5, 13, "Child Set 2"),
+    (5, 14, "Child Set 3");
+
+-- Grandchild set of "Child Set 1" (set_id = 31)
+INSERT INTO Sets (list_id, parent_id, title) VALUES
+    (5, 31, "Grandchild Set 1");
+
+-- Todos for the child sets (set_id = 31 to 34)
+INSERT INTO Todos (list_id, set_id, title, complete, due_date) VALUES
+    (5, 31, "Incompleted Unscheduled Todo 1", 0, NULL),
+    (5, 32, "Incompleted Scheduled Todo 1", 0, "2026-01-05T10:00:00Z"),
+    (5, 33, "Completed Unscheduled Todo 1", 1, NULL),
+    (5, 34, "Completed Scheduled Todo 1", 1, "2025-12-31T09:00:00Z");
+
+-- The seed doesn't say when its completed to dos were completed, so use their due dates.
+UPDATE Todos SET completed_at = COALESCE(due_date, created_at) WHERE complete = 1;
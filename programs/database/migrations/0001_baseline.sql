@@ -0,0 +1,32 @@
+-- The schema tables_v2.sql set up before the schema was kept as migrations. Databases made from
+-- that file already hold these tables, so they're only made where they're missing, and every
+-- migration after this one applies to both kinds of database.
+--
+-- Migrations run with foreign keys off, so tables can be rebuilt the way SQLite recommends:
+-- make the new table, copy the rows over, drop the old one and rename the new one into place.
+-- Dropping a table would otherwise delete the rows referring to it.
+
+CREATE TABLE IF NOT EXISTS Lists (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS Sets (
+    id INTEGER PRIMARY KEY,
+    list_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS Todos (
+    id INTEGER PRIMARY KEY,
+    list_id INTEGER NOT NULL,
+    set_id INTEGER,
+    title TEXT NOT NULL,
+    complete BOOLEAN NOT NULL DEFAULT 0,
+    due_date DATETIME,
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE,
+    FOREIGN KEY (set_id) REFERENCES sets (id) ON DELETE CASCADE
+);
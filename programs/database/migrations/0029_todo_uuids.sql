@@ -0,0 +1,4 @@
+-- The Taskwarrior UUID of each to do, so tasks keep their identity through export and import.
+ALTER TABLE Todos ADD COLUMN uuid TEXT;
+
+CREATE UNIQUE INDEX todos_by_uuid ON Todos (uuid);
@@ -0,0 +1,9 @@
+-- User-defined order of sets within their list and of to dos within their set. Rows that are
+-- already there all start at 0, which keeps them in the order of their ids.
+ALTER TABLE Sets ADD COLUMN position REAL NOT NULL DEFAULT 0;
+
+CREATE INDEX sets_by_position ON Sets (list_id, position);
+
+ALTER TABLE Todos ADD COLUMN position REAL NOT NULL DEFAULT 0;
+
+CREATE INDEX todos_by_position ON Todos (list_id, set_id, position);
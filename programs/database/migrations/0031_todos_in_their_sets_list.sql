@@ -0,0 +1,51 @@
+-- A to do's set has to belong to the to do's list. To dos that disagree with their set are moved
+-- to the set's list, since the set is the more specific of the two.
+UPDATE Todos SET list_id = (SELECT list_id FROM Sets WHERE Sets.id = Todos.set_id)
+WHERE set_id IS NOT NULL;
+
+CREATE TABLE NewSets (
+    id INTEGER PRIMARY KEY,
+    list_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    position REAL NOT NULL DEFAULT 0,
+
+    -- Lets to dos reference a set together with its list.
+    UNIQUE (id, list_id),
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE
+);
+
+INSERT INTO NewSets (id, list_id, title, position)
+SELECT id, list_id, title, position FROM Sets;
+
+DROP TABLE Sets;
+
+ALTER TABLE NewSets RENAME TO Sets;
+
+CREATE INDEX sets_by_position ON Sets (list_id, position);
+
+CREATE TABLE NewTodos (
+    id INTEGER PRIMARY KEY,
+    list_id INTEGER NOT NULL,
+    set_id INTEGER,
+    title TEXT NOT NULL,
+    complete BOOLEAN NOT NULL DEFAULT 0,
+    due_date DATETIME,
+    uuid TEXT UNIQUE,
+    position REAL NOT NULL DEFAULT 0,
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE,
+    -- A to do's set always belongs to its list, and moving a set moves its to dos with it.
+    -- A to do without a set (NULL set_id) is only checked against its list.
+    FOREIGN KEY (set_id, list_id) REFERENCES sets (id, list_id)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+INSERT INTO NewTodos (id, list_id, set_id, title, complete, due_date, uuid, position)
+SELECT id, list_id, set_id, title, complete, due_date, uuid, position FROM Todos;
+
+DROP TABLE Todos;
+
+ALTER TABLE NewTodos RENAME TO Todos;
+
+CREATE INDEX todos_by_position ON Todos (list_id, set_id, position);
@@ -0,0 +1,28 @@
+-- Sets can be nested inside other sets of their list. Sets that are already there stay directly
+-- in their list.
+CREATE TABLE NewSets (
+    id INTEGER PRIMARY KEY,
+    list_id INTEGER NOT NULL,
+    parent_id INTEGER, -- NULL means the set sits directly in its list
+    title TEXT NOT NULL,
+    position REAL NOT NULL DEFAULT 0,
+
+    -- Lets to dos and child sets reference a set together with its list.
+    UNIQUE (id, list_id),
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE,
+    -- A child set lives in its parent's list, and moves and is deleted along with it.
+    FOREIGN KEY (parent_id, list_id) REFERENCES sets (id, list_id)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+INSERT INTO NewSets (id, list_id, title, position)
+SELECT id, list_id, title, position FROM Sets;
+
+DROP TABLE Sets;
+
+ALTER TABLE NewSets RENAME TO Sets;
+
+CREATE INDEX sets_by_position ON Sets (list_id, position);
+
+CREATE INDEX sets_by_parent ON Sets (parent_id);
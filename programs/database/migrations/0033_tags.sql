@@ -0,0 +1,17 @@
+CREATE TABLE Tags (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+-- Tags cut across lists and sets, so a to do can carry any number of them.
+CREATE TABLE TodoTags (
+    todo_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+
+    PRIMARY KEY (todo_id, tag_id),
+
+    FOREIGN KEY (todo_id) REFERENCES todos (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE INDEX todo_tags_by_tag ON TodoTags (tag_id);
@@ -0,0 +1,79 @@
+-- Timestamps are stored as RFC 3339 text in UTC, like the API reads and writes them. SQLite can't
+-- add columns defaulting to the current time, so the tables are rebuilt, and the rows already
+-- there are stamped with the time of the migration.
+
+CREATE TABLE NewLists (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+INSERT INTO NewLists (id, title) SELECT id, title FROM Lists;
+
+DROP TABLE Lists;
+
+ALTER TABLE NewLists RENAME TO Lists;
+
+CREATE TABLE NewSets (
+    id INTEGER PRIMARY KEY,
+    list_id INTEGER NOT NULL,
+    parent_id INTEGER, -- NULL means the set sits directly in its list
+    title TEXT NOT NULL,
+    position REAL NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+    -- Lets to dos and child sets reference a set together with its list.
+    UNIQUE (id, list_id),
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE,
+    -- A child set lives in its parent's list, and moves and is deleted along with it.
+    FOREIGN KEY (parent_id, list_id) REFERENCES sets (id, list_id)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+INSERT INTO NewSets (id, list_id, parent_id, title, position)
+SELECT id, list_id, parent_id, title, position FROM Sets;
+
+DROP TABLE Sets;
+
+ALTER TABLE NewSets RENAME TO Sets;
+
+CREATE INDEX sets_by_position ON Sets (list_id, position);
+
+CREATE INDEX sets_by_parent ON Sets (parent_id);
+
+CREATE TABLE NewTodos (
+    id INTEGER PRIMARY KEY,
+    list_id INTEGER NOT NULL,
+    set_id INTEGER,
+    title TEXT NOT NULL,
+    complete BOOLEAN NOT NULL DEFAULT 0,
+    due_date DATETIME,
+    uuid TEXT UNIQUE,
+    position REAL NOT NULL DEFAULT 0,
+    notes TEXT, -- Markdown
+    priority INTEGER NOT NULL DEFAULT 0, -- 0 (none) to 3 (high)
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    completed_at DATETIME, -- NULL while the to do isn't complete
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE,
+    -- A to do's set always belongs to its list, and moving a set moves its to dos with it.
+    -- A to do without a set (NULL set_id) is only checked against its list.
+    FOREIGN KEY (set_id, list_id) REFERENCES sets (id, list_id)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+-- To dos that are already complete count as completed now, since when they were isn't known.
+INSERT INTO NewTodos (id, list_id, set_id, title, complete, due_date, uuid, position, completed_at)
+SELECT id, list_id, set_id, title, complete, due_date, uuid, position,
+    CASE WHEN complete THEN strftime('%Y-%m-%dT%H:%M:%fZ', 'now') END
+FROM Todos;
+
+DROP TABLE Todos;
+
+ALTER TABLE NewTodos RENAME TO Todos;
+
+CREATE INDEX todos_by_position ON Todos (list_id, set_id, position);
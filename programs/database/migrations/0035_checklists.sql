@@ -0,0 +1,14 @@
+ALTER TABLE Todos ADD COLUMN auto_complete BOOLEAN NOT NULL DEFAULT 0; -- complete once every checklist item is done
+
+-- Short checklists inside a to do, for steps that don't deserve a set of their own.
+CREATE TABLE ChecklistItems (
+    id INTEGER PRIMARY KEY,
+    todo_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    done BOOLEAN NOT NULL DEFAULT 0,
+    position REAL NOT NULL DEFAULT 0,
+
+    FOREIGN KEY (todo_id) REFERENCES todos (id) ON DELETE CASCADE
+);
+
+CREATE INDEX checklist_items_by_position ON ChecklistItems (todo_id, position);
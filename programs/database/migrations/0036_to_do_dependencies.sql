@@ -0,0 +1,14 @@
+-- A to do is blocked by each of its blockers until they're complete. Blockers may live in any list
+-- or set, and the server keeps the graph free of cycles.
+CREATE TABLE TodoDependencies (
+    todo_id INTEGER NOT NULL,
+    blocker_id INTEGER NOT NULL,
+
+    PRIMARY KEY (todo_id, blocker_id),
+    CHECK (todo_id != blocker_id),
+
+    FOREIGN KEY (todo_id) REFERENCES todos (id) ON DELETE CASCADE,
+    FOREIGN KEY (blocker_id) REFERENCES todos (id) ON DELETE CASCADE
+);
+
+CREATE INDEX todo_dependencies_by_blocker ON TodoDependencies (blocker_id);
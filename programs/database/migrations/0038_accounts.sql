@@ -0,0 +1,59 @@
+CREATE TABLE Users (
+    id INTEGER PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL, -- argon2, in PHC string format
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE Sessions (
+    token TEXT PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    expires_at DATETIME NOT NULL,
+
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+-- Lists and tags made before there were accounts go to an `admin` account. Its password hash is
+-- left empty, which no password matches, so nobody can log in as it until an administrator
+-- writes a real hash into its row.
+INSERT INTO Users (username, password_hash)
+SELECT 'admin', '' WHERE EXISTS (SELECT 1 FROM Lists) OR EXISTS (SELECT 1 FROM Tags);
+
+CREATE TABLE NewLists (
+    id INTEGER PRIMARY KEY,
+    owner_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+    FOREIGN KEY (owner_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+INSERT INTO NewLists (id, owner_id, title, created_at, updated_at)
+SELECT id, (SELECT id FROM Users WHERE username = 'admin'), title, created_at, updated_at
+FROM Lists;
+
+DROP TABLE Lists;
+
+ALTER TABLE NewLists RENAME TO Lists;
+
+CREATE INDEX lists_by_owner ON Lists (owner_id);
+
+CREATE TABLE NewTags (
+    id INTEGER PRIMARY KEY,
+    owner_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+
+    -- Every user has their own tags.
+    UNIQUE (owner_id, name),
+
+    FOREIGN KEY (owner_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+INSERT INTO NewTags (id, owner_id, name)
+SELECT id, (SELECT id FROM Users WHERE username = 'admin'), name FROM Tags;
+
+DROP TABLE Tags;
+
+ALTER TABLE NewTags RENAME TO Tags;
@@ -0,0 +1,14 @@
+-- Users a list is shared with. Viewers can read the list, editors can also change its sets, to
+-- dos and checklist items. Renaming, deleting and sharing the list stay with its owner.
+CREATE TABLE ListMembers (
+    list_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('viewer', 'editor')),
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+    PRIMARY KEY (list_id, user_id),
+    FOREIGN KEY (list_id) REFERENCES Lists (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX list_members_by_user ON ListMembers (user_id);
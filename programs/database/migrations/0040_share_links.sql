@@ -0,0 +1,13 @@
+-- Links that let anyone holding the token read a list without an account. Deleting the row
+-- revokes the link.
+CREATE TABLE ShareLinks (
+    id INTEGER PRIMARY KEY,
+    list_id INTEGER NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    expires_at DATETIME, -- NULL means the link never expires
+
+    FOREIGN KEY (list_id) REFERENCES Lists (id) ON DELETE CASCADE
+);
+
+CREATE INDEX share_links_by_list ON ShareLinks (list_id);
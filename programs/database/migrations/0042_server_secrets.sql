@@ -0,0 +1,6 @@
+-- Values made once per database, like the key public ids are scrambled with. They stay on the
+-- server, so ids handed out keep meaning the same rows for as long as the database lives.
+CREATE TABLE ServerSecrets (
+    name TEXT PRIMARY KEY,
+    value BLOB NOT NULL
+);
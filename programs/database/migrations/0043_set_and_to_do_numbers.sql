@@ -0,0 +1,45 @@
+ALTER TABLE Sets ADD COLUMN sid INTEGER; -- numbered within the list, see number_sets below
+
+ALTER TABLE Todos ADD COLUMN tdid INTEGER; -- numbered within the list, see number_todos below
+
+-- Sets and to dos that are already there are numbered in the order they were made.
+UPDATE Sets SET sid = (SELECT COUNT(*) FROM Sets s WHERE s.list_id = Sets.list_id AND s.id <= Sets.id);
+
+UPDATE Todos SET tdid = (SELECT COUNT(*) FROM Todos t WHERE t.list_id = Todos.list_id AND t.id <= Todos.id);
+
+CREATE INDEX sets_by_sid ON Sets (list_id, sid);
+
+CREATE INDEX todos_by_tdid ON Todos (list_id, tdid);
+
+-- Sets are addressed by (list, sid) as well as by id. A set gets the next free sid of its list when
+-- it's made and again when it moves to another list, along with the sets nested inside it. This
+-- is left to triggers because moves cascade, and isn't a UNIQUE constraint because a moved set
+-- briefly shares its old sid with a set of its new list.
+CREATE TRIGGER number_sets AFTER INSERT ON Sets WHEN NEW.sid IS NULL
+BEGIN
+    UPDATE Sets SET sid = (SELECT COALESCE(MAX(sid), 0) + 1 FROM Sets WHERE list_id = NEW.list_id)
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER renumber_moved_sets AFTER UPDATE OF list_id ON Sets
+WHEN NEW.list_id != OLD.list_id
+BEGIN
+    UPDATE Sets SET sid = (SELECT COALESCE(MAX(sid), 0) + 1 FROM Sets WHERE list_id = NEW.list_id)
+    WHERE id = NEW.id;
+END;
+
+-- To dos are addressed by (list, set, tdid) as well as by id. Like sids, tdids are handed out per
+-- list, so they're also unique within each set and a to do keeps its tdid when it moves between
+-- sets of its list.
+CREATE TRIGGER number_todos AFTER INSERT ON Todos WHEN NEW.tdid IS NULL
+BEGIN
+    UPDATE Todos SET tdid = (SELECT COALESCE(MAX(tdid), 0) + 1 FROM Todos WHERE list_id = NEW.list_id)
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER renumber_moved_todos AFTER UPDATE OF list_id ON Todos
+WHEN NEW.list_id != OLD.list_id
+BEGIN
+    UPDATE Todos SET tdid = (SELECT COALESCE(MAX(tdid), 0) + 1 FROM Todos WHERE list_id = NEW.list_id)
+    WHERE id = NEW.id;
+END;
@@ -0,0 +1,163 @@
+-- A change counter for each table the read routes serve, bumped by the triggers below whenever a
+-- row is inserted, updated or deleted, directly or through a cascade. Reads are tagged with the
+-- counters of the tables they draw on, so clients can ask whether anything changed since they
+-- last read it without being sent it again.
+CREATE TABLE Changes (
+    name TEXT PRIMARY KEY, -- the table counted
+    version INTEGER NOT NULL DEFAULT 0,
+    changed_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+INSERT INTO Changes (name) VALUES
+    ('Lists'),
+    ('ListMembers'),
+    ('Sets'),
+    ('Todos'),
+    ('ChecklistItems'),
+    ('Tags'),
+    ('TodoTags'),
+    ('TodoDependencies');
+
+CREATE TRIGGER count_inserts_of_lists AFTER INSERT ON Lists
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Lists';
+END;
+
+CREATE TRIGGER count_updates_of_lists AFTER UPDATE ON Lists
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Lists';
+END;
+
+CREATE TRIGGER count_deletes_of_lists AFTER DELETE ON Lists
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Lists';
+END;
+
+CREATE TRIGGER count_inserts_of_listmembers AFTER INSERT ON ListMembers
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ListMembers';
+END;
+
+CREATE TRIGGER count_updates_of_listmembers AFTER UPDATE ON ListMembers
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ListMembers';
+END;
+
+CREATE TRIGGER count_deletes_of_listmembers AFTER DELETE ON ListMembers
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ListMembers';
+END;
+
+CREATE TRIGGER count_inserts_of_sets AFTER INSERT ON Sets
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Sets';
+END;
+
+CREATE TRIGGER count_updates_of_sets AFTER UPDATE ON Sets
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Sets';
+END;
+
+CREATE TRIGGER count_deletes_of_sets AFTER DELETE ON Sets
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Sets';
+END;
+
+CREATE TRIGGER count_inserts_of_todos AFTER INSERT ON Todos
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Todos';
+END;
+
+CREATE TRIGGER count_updates_of_todos AFTER UPDATE ON Todos
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Todos';
+END;
+
+CREATE TRIGGER count_deletes_of_todos AFTER DELETE ON Todos
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Todos';
+END;
+
+CREATE TRIGGER count_inserts_of_checklistitems AFTER INSERT ON ChecklistItems
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ChecklistItems';
+END;
+
+CREATE TRIGGER count_updates_of_checklistitems AFTER UPDATE ON ChecklistItems
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ChecklistItems';
+END;
+
+CREATE TRIGGER count_deletes_of_checklistitems AFTER DELETE ON ChecklistItems
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ChecklistItems';
+END;
+
+CREATE TRIGGER count_inserts_of_tags AFTER INSERT ON Tags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Tags';
+END;
+
+CREATE TRIGGER count_updates_of_tags AFTER UPDATE ON Tags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Tags';
+END;
+
+CREATE TRIGGER count_deletes_of_tags AFTER DELETE ON Tags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Tags';
+END;
+
+CREATE TRIGGER count_inserts_of_todotags AFTER INSERT ON TodoTags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'TodoTags';
+END;
+
+CREATE TRIGGER count_updates_of_todotags AFTER UPDATE ON TodoTags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'TodoTags';
+END;
+
+CREATE TRIGGER count_deletes_of_todotags AFTER DELETE ON TodoTags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'TodoTags';
+END;
+
+CREATE TRIGGER count_inserts_of_tododependencies AFTER INSERT ON TodoDependencies
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'TodoDependencies';
+END;
+
+CREATE TRIGGER count_updates_of_tododependencies AFTER UPDATE ON TodoDependencies
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'TodoDependencies';
+END;
+
+CREATE TRIGGER count_deletes_of_tododependencies AFTER DELETE ON TodoDependencies
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'TodoDependencies';
+END;
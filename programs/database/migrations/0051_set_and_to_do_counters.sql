@@ -0,0 +1,64 @@
+-- Each list keeps the next sid and tdid it hands out, so a number stays retired once the set or
+-- to do that had it is deleted or moves away, instead of going to the next one made.
+ALTER TABLE Lists ADD COLUMN next_sid INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE Lists ADD COLUMN next_tdid INTEGER NOT NULL DEFAULT 1;
+
+UPDATE Lists SET
+    next_sid = (SELECT COALESCE(MAX(sid), 0) + 1 FROM Sets WHERE list_id = Lists.id),
+    next_tdid = (SELECT COALESCE(MAX(tdid), 0) + 1 FROM Todos WHERE list_id = Lists.id);
+
+-- Handing out numbers only changes the counters, which isn't a change of the list.
+DROP TRIGGER count_updates_of_lists;
+
+CREATE TRIGGER count_updates_of_lists AFTER UPDATE OF id, owner_id, title, created_at, updated_at ON Lists
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Lists';
+END;
+
+DROP INDEX sets_by_sid;
+
+DROP INDEX todos_by_tdid;
+
+CREATE UNIQUE INDEX sets_by_sid ON Sets (list_id, sid);
+
+CREATE UNIQUE INDEX todos_by_tdid ON Todos (list_id, tdid);
+
+-- A set or to do gets the next number of its list when it's made, and again when it moves to
+-- another list. The sets and to dos moved along with a set by the cascades of its foreign keys
+-- keep their old numbers until their own trigger runs, which would break the UNIQUE indexes above,
+-- so whatever moves them clears their numbers first. NULLs don't collide.
+DROP TRIGGER number_sets;
+
+DROP TRIGGER renumber_moved_sets;
+
+DROP TRIGGER number_todos;
+
+DROP TRIGGER renumber_moved_todos;
+
+CREATE TRIGGER number_sets AFTER INSERT ON Sets WHEN NEW.sid IS NULL
+BEGIN
+    UPDATE Sets SET sid = (SELECT next_sid FROM Lists WHERE id = NEW.list_id) WHERE id = NEW.id;
+    UPDATE Lists SET next_sid = next_sid + 1 WHERE id = NEW.list_id;
+END;
+
+CREATE TRIGGER renumber_moved_sets AFTER UPDATE OF list_id ON Sets
+WHEN NEW.list_id != OLD.list_id
+BEGIN
+    UPDATE Sets SET sid = (SELECT next_sid FROM Lists WHERE id = NEW.list_id) WHERE id = NEW.id;
+    UPDATE Lists SET next_sid = next_sid + 1 WHERE id = NEW.list_id;
+END;
+
+CREATE TRIGGER number_todos AFTER INSERT ON Todos WHEN NEW.tdid IS NULL
+BEGIN
+    UPDATE Todos SET tdid = (SELECT next_tdid FROM Lists WHERE id = NEW.list_id) WHERE id = NEW.id;
+    UPDATE Lists SET next_tdid = next_tdid + 1 WHERE id = NEW.list_id;
+END;
+
+CREATE TRIGGER renumber_moved_todos AFTER UPDATE OF list_id ON Todos
+WHEN NEW.list_id != OLD.list_id
+BEGIN
+    UPDATE Todos SET tdid = (SELECT next_tdid FROM Lists WHERE id = NEW.list_id) WHERE id = NEW.id;
+    UPDATE Lists SET next_tdid = next_tdid + 1 WHERE id = NEW.list_id;
+END;
@@ -0,0 +1,294 @@
+-- The ids clients see are made from these keys, so a key that comes back after its row was deleted
+-- would make an id the client still holds name another row. AUTOINCREMENT keeps keys from being
+-- reused, which takes rebuilding each table with a public id. The keys already used by rows that
+-- were deleted before this migration can't be told apart, only the ones of the rows left are kept.
+
+-- The triggers numbering sets and to dos refer to Lists, which is rebuilt first. They're made
+-- again at the end, along with the triggers of the rebuilt tables.
+DROP TRIGGER number_sets;
+
+DROP TRIGGER renumber_moved_sets;
+
+DROP TRIGGER number_todos;
+
+DROP TRIGGER renumber_moved_todos;
+
+CREATE TABLE NewUsers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL, -- argon2, in PHC string format
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+INSERT INTO NewUsers (id, username, password_hash, created_at)
+SELECT id, username, password_hash, created_at FROM Users;
+
+DROP TABLE Users;
+
+ALTER TABLE NewUsers RENAME TO Users;
+
+CREATE TABLE NewLists (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    owner_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    next_sid INTEGER NOT NULL DEFAULT 1, -- the sid the next set of the list gets
+    next_tdid INTEGER NOT NULL DEFAULT 1, -- the tdid the next to do of the list gets
+
+    FOREIGN KEY (owner_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+INSERT INTO NewLists (id, owner_id, title, created_at, updated_at, next_sid, next_tdid)
+SELECT id, owner_id, title, created_at, updated_at, next_sid, next_tdid FROM Lists;
+
+DROP TABLE Lists;
+
+ALTER TABLE NewLists RENAME TO Lists;
+
+CREATE INDEX lists_by_owner ON Lists (owner_id);
+
+CREATE TABLE NewSets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    list_id INTEGER NOT NULL,
+    parent_id INTEGER, -- NULL means the set sits directly in its list
+    title TEXT NOT NULL,
+    position REAL NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    sid INTEGER, -- numbered within the list, see number_sets below
+
+    -- Lets to dos and child sets reference a set together with its list.
+    UNIQUE (id, list_id),
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE,
+    -- A child set lives in its parent's list, and moves and is deleted along with it.
+    FOREIGN KEY (parent_id, list_id) REFERENCES sets (id, list_id)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+INSERT INTO NewSets (id, list_id, parent_id, title, position, created_at, updated_at, sid)
+SELECT id, list_id, parent_id, title, position, created_at, updated_at, sid FROM Sets;
+
+DROP TABLE Sets;
+
+ALTER TABLE NewSets RENAME TO Sets;
+
+CREATE INDEX sets_by_position ON Sets (list_id, position);
+
+CREATE INDEX sets_by_parent ON Sets (parent_id);
+
+CREATE UNIQUE INDEX sets_by_sid ON Sets (list_id, sid);
+
+CREATE TABLE NewTodos (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    list_id INTEGER NOT NULL,
+    set_id INTEGER,
+    title TEXT NOT NULL,
+    complete BOOLEAN NOT NULL DEFAULT 0,
+    due_date DATETIME,
+    uuid TEXT UNIQUE,
+    position REAL NOT NULL DEFAULT 0,
+    notes TEXT, -- Markdown
+    priority INTEGER NOT NULL DEFAULT 0, -- 0 (none) to 3 (high)
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    completed_at DATETIME, -- NULL while the to do isn't complete
+    auto_complete BOOLEAN NOT NULL DEFAULT 0,
+    tdid INTEGER, -- numbered within the list, see number_todos below
+
+    FOREIGN KEY (list_id) REFERENCES lists (id) ON DELETE CASCADE,
+    -- A to do's set always belongs to its list, and moving a set moves its to dos with it.
+    -- A to do without a set (NULL set_id) is only checked against its list.
+    FOREIGN KEY (set_id, list_id) REFERENCES sets (id, list_id)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+INSERT INTO NewTodos (
+    id, list_id, set_id, title, complete, due_date, uuid, position, notes, priority, created_at,
+    updated_at, completed_at, auto_complete, tdid
+)
+SELECT id, list_id, set_id, title, complete, due_date, uuid, position, notes, priority, created_at,
+    updated_at, completed_at, auto_complete, tdid
+FROM Todos;
+
+DROP TABLE Todos;
+
+ALTER TABLE NewTodos RENAME TO Todos;
+
+CREATE INDEX todos_by_position ON Todos (list_id, set_id, position);
+
+CREATE UNIQUE INDEX todos_by_tdid ON Todos (list_id, tdid);
+
+CREATE TABLE NewTags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    owner_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+
+    -- Every user has their own tags.
+    UNIQUE (owner_id, name),
+
+    FOREIGN KEY (owner_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+INSERT INTO NewTags (id, owner_id, name) SELECT id, owner_id, name FROM Tags;
+
+DROP TABLE Tags;
+
+ALTER TABLE NewTags RENAME TO Tags;
+
+CREATE TABLE NewChecklistItems (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    todo_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    done BOOLEAN NOT NULL DEFAULT 0,
+    position REAL NOT NULL DEFAULT 0,
+
+    FOREIGN KEY (todo_id) REFERENCES todos (id) ON DELETE CASCADE
+);
+
+INSERT INTO NewChecklistItems (id, todo_id, title, done, position)
+SELECT id, todo_id, title, done, position FROM ChecklistItems;
+
+DROP TABLE ChecklistItems;
+
+ALTER TABLE NewChecklistItems RENAME TO ChecklistItems;
+
+CREATE INDEX checklist_items_by_position ON ChecklistItems (todo_id, position);
+
+CREATE TABLE NewShareLinks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    list_id INTEGER NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    expires_at DATETIME, -- NULL means the link never expires
+
+    FOREIGN KEY (list_id) REFERENCES Lists (id) ON DELETE CASCADE
+);
+
+INSERT INTO NewShareLinks (id, list_id, token, created_at, expires_at)
+SELECT id, list_id, token, created_at, expires_at FROM ShareLinks;
+
+DROP TABLE ShareLinks;
+
+ALTER TABLE NewShareLinks RENAME TO ShareLinks;
+
+CREATE INDEX share_links_by_list ON ShareLinks (list_id);
+
+-- The triggers of 0043_set_and_to_do_numbers and 0051_set_and_to_do_counters.
+CREATE TRIGGER number_sets AFTER INSERT ON Sets WHEN NEW.sid IS NULL
+BEGIN
+    UPDATE Sets SET sid = (SELECT next_sid FROM Lists WHERE id = NEW.list_id) WHERE id = NEW.id;
+    UPDATE Lists SET next_sid = next_sid + 1 WHERE id = NEW.list_id;
+END;
+
+CREATE TRIGGER renumber_moved_sets AFTER UPDATE OF list_id ON Sets
+WHEN NEW.list_id != OLD.list_id
+BEGIN
+    UPDATE Sets SET sid = (SELECT next_sid FROM Lists WHERE id = NEW.list_id) WHERE id = NEW.id;
+    UPDATE Lists SET next_sid = next_sid + 1 WHERE id = NEW.list_id;
+END;
+
+CREATE TRIGGER number_todos AFTER INSERT ON Todos WHEN NEW.tdid IS NULL
+BEGIN
+    UPDATE Todos SET tdid = (SELECT next_tdid FROM Lists WHERE id = NEW.list_id) WHERE id = NEW.id;
+    UPDATE Lists SET next_tdid = next_tdid + 1 WHERE id = NEW.list_id;
+END;
+
+CREATE TRIGGER renumber_moved_todos AFTER UPDATE OF list_id ON Todos
+WHEN NEW.list_id != OLD.list_id
+BEGIN
+    UPDATE Todos SET tdid = (SELECT next_tdid FROM Lists WHERE id = NEW.list_id) WHERE id = NEW.id;
+    UPDATE Lists SET next_tdid = next_tdid + 1 WHERE id = NEW.list_id;
+END;
+
+-- The triggers of 0050_change_counters and 0051_set_and_to_do_counters.
+CREATE TRIGGER count_inserts_of_lists AFTER INSERT ON Lists
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Lists';
+END;
+
+CREATE TRIGGER count_updates_of_lists AFTER UPDATE OF id, owner_id, title, created_at, updated_at ON Lists
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Lists';
+END;
+
+CREATE TRIGGER count_deletes_of_lists AFTER DELETE ON Lists
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Lists';
+END;
+
+CREATE TRIGGER count_inserts_of_sets AFTER INSERT ON Sets
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Sets';
+END;
+
+CREATE TRIGGER count_updates_of_sets AFTER UPDATE ON Sets
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Sets';
+END;
+
+CREATE TRIGGER count_deletes_of_sets AFTER DELETE ON Sets
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Sets';
+END;
+
+CREATE TRIGGER count_inserts_of_todos AFTER INSERT ON Todos
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Todos';
+END;
+
+CREATE TRIGGER count_updates_of_todos AFTER UPDATE ON Todos
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Todos';
+END;
+
+CREATE TRIGGER count_deletes_of_todos AFTER DELETE ON Todos
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Todos';
+END;
+
+CREATE TRIGGER count_inserts_of_checklistitems AFTER INSERT ON ChecklistItems
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ChecklistItems';
+END;
+
+CREATE TRIGGER count_updates_of_checklistitems AFTER UPDATE ON ChecklistItems
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ChecklistItems';
+END;
+
+CREATE TRIGGER count_deletes_of_checklistitems AFTER DELETE ON ChecklistItems
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'ChecklistItems';
+END;
+
+CREATE TRIGGER count_inserts_of_tags AFTER INSERT ON Tags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Tags';
+END;
+
+CREATE TRIGGER count_updates_of_tags AFTER UPDATE ON Tags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Tags';
+END;
+
+CREATE TRIGGER count_deletes_of_tags AFTER DELETE ON Tags
+BEGIN
+    UPDATE Changes SET version = version + 1, changed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    WHERE name = 'Tags';
+END;
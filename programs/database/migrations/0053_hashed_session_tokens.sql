@@ -0,0 +1,15 @@
+-- Sessions are looked up by the SHA-256 of their token, so a copy of the database can't be used to
+-- log in. The tokens of existing sessions can't be hashed in SQL, so they end and everyone logs in
+-- again.
+CREATE TABLE NewSessions (
+    token_hash BLOB PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    expires_at DATETIME NOT NULL,
+
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+DROP TABLE Sessions;
+
+ALTER TABLE NewSessions RENAME TO Sessions;
@@ -0,0 +1,18 @@
+-- Every to do gets its Taskwarrior UUID when it's made, instead of on its first export, so
+-- exporting only reads. The UUIDs are random version 4 ones, like those made by the server.
+UPDATE Todos SET uuid = lower(
+    hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)), 2) || '-'
+    || substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-'
+    || hex(randomblob(6))
+)
+WHERE uuid IS NULL;
+
+CREATE TRIGGER give_todos_uuids AFTER INSERT ON Todos WHEN NEW.uuid IS NULL
+BEGIN
+    UPDATE Todos SET uuid = lower(
+        hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)), 2)
+        || '-' || substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-'
+        || hex(randomblob(6))
+    )
+    WHERE id = NEW.id;
+END;
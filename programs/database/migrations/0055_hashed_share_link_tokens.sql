@@ -0,0 +1,18 @@
+-- Share links are looked up by the SHA-256 of their token, so a copy of the database can't be used
+-- to read shared lists. The tokens of existing links can't be hashed in SQL, so those links stop
+-- working and have to be shared anew.
+CREATE TABLE NewShareLinks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    list_id INTEGER NOT NULL,
+    token_hash BLOB NOT NULL UNIQUE,
+    created_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    expires_at DATETIME, -- NULL means the link never expires
+
+    FOREIGN KEY (list_id) REFERENCES Lists (id) ON DELETE CASCADE
+);
+
+DROP TABLE ShareLinks;
+
+ALTER TABLE NewShareLinks RENAME TO ShareLinks;
+
+CREATE INDEX share_links_by_list ON ShareLinks (list_id);
@@ -0,0 +1,15 @@
+fn main() -> std::io::Result<()> {
+    // The vendored compiler saves contributors from installing protoc themselves.
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("No protoc for this platform");
+    let include = protoc_bin_vendored::include_path().expect("No protoc includes");
+    // SAFETY: build scripts are single threaded.
+    unsafe { std::env::set_var("PROTOC", protoc) };
+
+    // `sqlx::migrate!` embeds the migrations, so adding one has to rebuild the server.
+    println!("cargo:rerun-if-changed=../database/migrations");
+    println!("cargo:rerun-if-changed=../schema.proto");
+    prost_build::compile_protos(
+        &["../schema.proto"],
+        &[std::path::Path::new(".."), &include],
+    )
+}
@@ -0,0 +1,152 @@
+use actix_web::{
+    HttpRequest, HttpResponse,
+    cookie::{Cookie, SameSite, time::OffsetDateTime},
+    delete, get, post,
+    web::Data,
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{
+        types::{Body, CurrentUser, JsonError, MaybeBody, RateLimiter, SESSION_COOKIE},
+        utils::{map_input_err, map_query_err},
+    },
+    db::sqlx::{delete_session, insert_session, insert_user, query_user},
+    types::{CreateSession, CreateUser, Session, User},
+};
+
+pub type CreateUserRequest = CreateUser;
+pub type CreateSessionRequest = CreateSession;
+
+pub type CreateUserResponse = User;
+pub type CreateSessionResponse = Session;
+pub type ReadUserResponse = User;
+
+#[utoipa::path(
+    tag = "accounts",
+    request_body = CreateUser,
+    security(()),
+    responses((status = OK, body = User), JsonError)
+)]
+#[post("/api/users")]
+pub async fn create_user(
+    req: MaybeBody<CreateUserRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+) -> Result<Body<CreateUserResponse>, JsonError> {
+    let entry = match req {
+        MaybeBody::Valid(entry) => entry,
+        MaybeBody::Empty => {
+            return Err(JsonError::BadRequest(
+                "Empty request not allowed".to_string(),
+            ));
+        }
+        MaybeBody::Invalid(err) => return map_input_err(err),
+    };
+
+    match insert_user(db_conn_pool, entry).await {
+        Ok(user) => Ok(Body(user)),
+        Err(err) => map_query_err(err),
+    }
+}
+
+/// Logs in. The session token is returned in the body and also set as a `Secure`, `HttpOnly`
+/// cookie, so browsers don't have to handle it themselves. Wrong passwords count against the
+/// address's budget of failures, like unknown session tokens do.
+#[utoipa::path(
+    tag = "accounts",
+    request_body = CreateSession,
+    security(()),
+    responses(
+        (status = OK, body = Session, headers(("Set-Cookie" = String, description = "The session cookie"))),
+        JsonError
+    )
+)]
+#[post("/api/sessions")]
+pub async fn create_session(
+    http_req: HttpRequest,
+    req: MaybeBody<CreateSessionRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    limiter: Option<Data<RateLimiter>>,
+) -> Result<HttpResponse, JsonError> {
+    if let Some(limiter) = &limiter {
+        limiter.check_failures(&http_req)?;
+    }
+    let Body(session) = match log_in(req, db_conn_pool).await {
+        Err(err @ JsonError::Unauthorized(_)) => {
+            if let Some(limiter) = &limiter {
+                limiter.fail(&http_req);
+            }
+            return Err(err);
+        }
+        session => session?,
+    };
+
+    let expires_at = OffsetDateTime::from_unix_timestamp(session.expires_at.timestamp())
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH);
+    let cookie = Cookie::build(SESSION_COOKIE, session.token.clone())
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .expires(expires_at)
+        .finish();
+
+    Ok(HttpResponse::Ok().cookie(cookie).json(session))
+}
+
+/// Logs out, ending the session the request was made with.
+#[utoipa::path(
+    tag = "accounts",
+    responses((status = NO_CONTENT), JsonError)
+)]
+#[delete("/api/sessions")]
+pub async fn delete_current_session(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<HttpResponse, JsonError> {
+    if let Err(err) = delete_session(db_conn_pool, user.token).await {
+        map_query_err::<()>(err)?;
+    }
+
+    let mut cookie = Cookie::build(SESSION_COOKIE, "").path("/").finish();
+    cookie.make_removal();
+    Ok(HttpResponse::NoContent().cookie(cookie).finish())
+}
+
+#[utoipa::path(
+    tag = "accounts",
+    responses((status = OK, body = User), JsonError)
+)]
+#[get("/api/users/me")]
+pub async fn read_current_user(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ReadUserResponse>, JsonError> {
+    match query_user(db_conn_pool, user.id).await {
+        Ok(user) => Ok(Body(user)),
+        Err(err) => map_query_err(err),
+    }
+}
+
+async fn log_in(
+    req: MaybeBody<CreateSessionRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+) -> Result<Body<CreateSessionResponse>, JsonError> {
+    let entry = match req {
+        MaybeBody::Valid(entry) => entry,
+        MaybeBody::Empty => {
+            return Err(JsonError::BadRequest(
+                "Empty request not allowed".to_string(),
+            ));
+        }
+        MaybeBody::Invalid(err) => return map_input_err(err),
+    };
+
+    match insert_session(db_conn_pool, entry).await {
+        Ok(Some(session)) => Ok(Body(session)),
+        Ok(None) => Err(JsonError::Unauthorized(
+            "Wrong username or password".to_string(),
+        )),
+        Err(err) => map_query_err(err),
+    }
+}
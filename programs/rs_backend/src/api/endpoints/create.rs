@@ -1,48 +1,113 @@
 use std::collections::HashSet;
 
-use actix_web::{
-    post,
-    web::{Data, Json},
-};
+use actix_web::{post, web::Data};
 use sqlx::{Pool, Sqlite};
 
 use crate::{
     api::{
-        types::{JsonError, MaybeJson},
+        types::{Body, CurrentUser, JsonError, MaybeBody, MaybeMessage, Message},
         utils::query_some,
     },
-    db::sqlx::{insert_lists, insert_sets, insert_todos},
-    types::{CreateList, CreateSet, CreateToDo, List, Set, ToDo},
+    db::sqlx::{insert_checklist_items, insert_lists, insert_sets, insert_tags, insert_todos},
+    types::{
+        ChecklistItem, CreateChecklistItem, CreateList, CreateSet, CreateTag, CreateToDo, List,
+        Set, Tag, ToDo, protobuf,
+    },
 };
 
 pub type CreateListsRequest = Vec<CreateList>;
 pub type CreateSetsRequest = Vec<CreateSet>;
 pub type CreateToDosRequest = Vec<CreateToDo>;
+pub type CreateTagsRequest = Vec<CreateTag>;
+pub type CreateChecklistItemsRequest = Vec<CreateChecklistItem>;
 
 pub type CreateListsResponse = HashSet<List>;
 pub type CreateSetsResponse = HashSet<Set>;
 pub type CreateToDosResponse = HashSet<ToDo>;
+pub type CreateTagsResponse = HashSet<Tag>;
+pub type CreateChecklistItemsResponse = HashSet<ChecklistItem>;
 
+#[utoipa::path(
+    tag = "lists",
+    request_body = Vec<CreateList>,
+    responses((status = OK, body = HashSet<List>), JsonError)
+)]
 #[post("/api/lists")]
 pub async fn create_lists(
-    req: MaybeJson<CreateListsRequest>,
+    req: MaybeMessage<CreateListsRequest, protobuf::CreateListsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<CreateListsResponse>, JsonError> {
-    query_some(req, db_conn_pool, insert_lists).await
+    user: CurrentUser,
+) -> Result<Message<CreateListsResponse, protobuf::CreateListsResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        insert_lists(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
 }
 
+#[utoipa::path(
+    tag = "sets",
+    request_body = Vec<CreateSet>,
+    responses((status = OK, body = HashSet<Set>), JsonError)
+)]
 #[post("/api/sets")]
 pub async fn create_sets(
-    req: MaybeJson<CreateSetsRequest>,
+    req: MaybeMessage<CreateSetsRequest, protobuf::CreateSetsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<CreateSetsResponse>, JsonError> {
-    query_some(req, db_conn_pool, insert_sets).await
+    user: CurrentUser,
+) -> Result<Message<CreateSetsResponse, protobuf::CreateSetsResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        insert_sets(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
 }
 
+#[utoipa::path(
+    tag = "to dos",
+    request_body = Vec<CreateToDo>,
+    responses((status = OK, body = HashSet<ToDo>), JsonError)
+)]
 #[post("/api/to_dos")]
 pub async fn create_to_dos(
-    req: MaybeJson<CreateToDosRequest>,
+    req: MaybeMessage<CreateToDosRequest, protobuf::CreateToDosRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Message<CreateToDosResponse, protobuf::CreateToDosResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        insert_todos(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
+}
+
+#[utoipa::path(
+    tag = "tags",
+    request_body = Vec<CreateTag>,
+    responses((status = OK, body = HashSet<Tag>), JsonError)
+)]
+#[post("/api/tags")]
+pub async fn create_tags(
+    req: MaybeBody<CreateTagsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<CreateTagsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| insert_tags(db, user.id, adds)).await
+}
+
+#[utoipa::path(
+    tag = "checklist items",
+    request_body = Vec<CreateChecklistItem>,
+    responses((status = OK, body = HashSet<ChecklistItem>), JsonError)
+)]
+#[post("/api/checklist_items")]
+pub async fn create_checklist_items(
+    req: MaybeBody<CreateChecklistItemsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<CreateToDosResponse>, JsonError> {
-    query_some(req, db_conn_pool, insert_todos).await
+    user: CurrentUser,
+) -> Result<Body<CreateChecklistItemsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        insert_checklist_items(db, user.id, adds)
+    })
+    .await
 }
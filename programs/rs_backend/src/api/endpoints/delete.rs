@@ -1,51 +1,119 @@
 use std::collections::HashSet;
 
-use actix_web::{
-    delete,
-    web::{Data, Json},
-};
+use actix_web::{delete, web::Data};
 use sqlx::{Pool, Sqlite};
 
 use crate::{
     api::{
-        types::{JsonError, MaybeJson},
+        types::{Body, CurrentUser, JsonError, MaybeBody, MaybeMessage, Message},
         utils::query_some,
     },
     db::sqlx::{
-        delete_lists as db_delete_lists, delete_sets as db_delete_sets,
+        delete_checklist_items as db_delete_checklist_items, delete_lists as db_delete_lists,
+        delete_sets as db_delete_sets, delete_tags as db_delete_tags,
         delete_todos as db_delete_todos,
     },
-    types::{ListID, SetID, SetQueryTarget, ToDoID, ToDoQueryTarget},
+    types::{
+        ChecklistItemID, ListID, SetID, SetQueryTarget, TagID, ToDoID, ToDoQueryTarget, protobuf,
+    },
 };
 
 pub type DeleteListsRequest = HashSet<ListID>;
 pub type DeleteSetsRequest = HashSet<SetQueryTarget>;
 pub type DeleteToDosRequest = HashSet<ToDoQueryTarget>;
+pub type DeleteTagsRequest = HashSet<TagID>;
+pub type DeleteChecklistItemsRequest = HashSet<ChecklistItemID>;
 
 pub type DeleteListsResponse = HashSet<ListID>;
 pub type DeleteSetsResponse = HashSet<SetID>;
 pub type DeleteToDosResponse = HashSet<ToDoID>;
+pub type DeleteTagsResponse = HashSet<TagID>;
+pub type DeleteChecklistItemsResponse = HashSet<ChecklistItemID>;
 
+#[utoipa::path(
+    tag = "lists",
+    request_body = HashSet<ListID>,
+    responses((status = OK, body = HashSet<ListID>), JsonError)
+)]
 #[delete("/api/lists")]
 pub async fn delete_lists(
-    req: MaybeJson<DeleteListsRequest>,
+    req: MaybeMessage<DeleteListsRequest, protobuf::DeleteListsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<DeleteListsResponse>, JsonError> {
-    query_some(req, db_conn_pool, db_delete_lists).await
+    user: CurrentUser,
+) -> Result<Message<DeleteListsResponse, protobuf::DeleteListsResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        db_delete_lists(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
 }
 
+#[utoipa::path(
+    tag = "sets",
+    request_body = HashSet<SetQueryTarget>,
+    responses((status = OK, body = HashSet<SetID>), JsonError)
+)]
 #[delete("/api/sets")]
 pub async fn delete_sets(
-    req: MaybeJson<DeleteSetsRequest>,
+    req: MaybeMessage<DeleteSetsRequest, protobuf::DeleteSetsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<DeleteSetsResponse>, JsonError> {
-    query_some(req, db_conn_pool, db_delete_sets).await
+    user: CurrentUser,
+) -> Result<Message<DeleteSetsResponse, protobuf::DeleteSetsResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        db_delete_sets(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
 }
 
+#[utoipa::path(
+    tag = "to dos",
+    request_body = HashSet<ToDoQueryTarget>,
+    responses((status = OK, body = HashSet<ToDoID>), JsonError)
+)]
 #[delete("/api/to_dos")]
 pub async fn delete_to_dos(
-    req: MaybeJson<DeleteToDosRequest>,
+    req: MaybeMessage<DeleteToDosRequest, protobuf::DeleteToDosRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Message<DeleteToDosResponse, protobuf::DeleteToDosResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        db_delete_todos(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
+}
+
+#[utoipa::path(
+    tag = "tags",
+    request_body = HashSet<TagID>,
+    responses((status = OK, body = HashSet<TagID>), JsonError)
+)]
+#[delete("/api/tags")]
+pub async fn delete_tags(
+    req: MaybeBody<DeleteTagsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<DeleteTagsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_delete_tags(db, user.id, adds)
+    })
+    .await
+}
+
+#[utoipa::path(
+    tag = "checklist items",
+    request_body = HashSet<ChecklistItemID>,
+    responses((status = OK, body = HashSet<ChecklistItemID>), JsonError)
+)]
+#[delete("/api/checklist_items")]
+pub async fn delete_checklist_items(
+    req: MaybeBody<DeleteChecklistItemsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<DeleteToDosResponse>, JsonError> {
-    query_some(req, db_conn_pool, db_delete_todos).await
+    user: CurrentUser,
+) -> Result<Body<DeleteChecklistItemsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_delete_checklist_items(db, user.id, adds)
+    })
+    .await
 }
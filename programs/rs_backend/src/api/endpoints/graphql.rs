@@ -0,0 +1,52 @@
+use actix_web::{HttpRequest, HttpResponse, get, post, web::Data};
+use async_graphql::http::GraphiQLSource;
+use sqlx::{Pool, Sqlite};
+
+use crate::api::{
+    graphql::{Store, ToDoSchema, is_mutation},
+    types::{Body, CurrentUser, JsonError, MaybeBody, RateLimiter},
+    utils::map_input_err,
+};
+
+pub type GraphQLRequest = async_graphql::Request;
+pub type GraphQLResponse = async_graphql::Response;
+
+/// Runs a GraphQL query or mutation as the logged in user. Errors in the query itself are
+/// reported in the response's `errors`, next to whatever data could still be read. Queries count
+/// against the rate limit as reads, and mutations as writes too.
+#[post("/graphql")]
+pub async fn graphql(
+    http_req: HttpRequest,
+    req: MaybeBody<GraphQLRequest>,
+    schema: Data<ToDoSchema>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    limiter: Option<Data<RateLimiter>>,
+    user: CurrentUser,
+) -> Result<Body<GraphQLResponse>, JsonError> {
+    let mut request = match req {
+        MaybeBody::Valid(request) => request,
+        MaybeBody::Empty => {
+            return Err(JsonError::BadRequest(
+                "Empty request not allowed".to_string(),
+            ));
+        }
+        MaybeBody::Invalid(err) => return map_input_err(err),
+    };
+
+    if let Some(limiter) = limiter
+        && is_mutation(&mut request)
+    {
+        limiter.charge_write(&http_req)?;
+    }
+
+    let request = request.data(Store::loader(db_conn_pool, user.id));
+    Ok(Body(schema.execute(request).await))
+}
+
+/// GraphiQL, for exploring the schema. It's only served by debug builds.
+#[get("/graphql")]
+pub async fn graphql_playground() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(GraphiQLSource::build().endpoint("/graphql").finish())
+}
@@ -0,0 +1,87 @@
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, get, mime, post, web::Data};
+use chrono::Utc;
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{
+        ReadListsRequest,
+        types::{Body, CurrentUser, JsonError, MaybeBody},
+        utils::{map_query_err, query_all_or_some},
+    },
+    db::sqlx::{import_markdown, query_all_markdown, query_markdown},
+    types::{ListID, MarkdownDocument, MarkdownImportSummary},
+};
+
+pub type ExportMarkdownRequest = ReadListsRequest;
+pub type ImportMarkdownRequest = MarkdownDocument;
+
+pub type ExportMarkdownResponse = MarkdownDocument;
+pub type ImportMarkdownResponse = MarkdownImportSummary;
+
+#[utoipa::path(
+    tag = "transfer",
+    request_body = Option<HashSet<ListID>>,
+    responses((status = OK, body = String, content_type = "text/markdown"), JsonError)
+)]
+#[get("/api/markdown")]
+pub async fn export_markdown(
+    req: MaybeBody<ExportMarkdownRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<HttpResponse, JsonError> {
+    let Body(document) = query_all_or_some(
+        req,
+        db_conn_pool,
+        |db| query_all_markdown(db, user.id),
+        |db, adds| query_markdown(db, user.id, adds),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/markdown; charset=utf-8")
+        .body(document.render()))
+}
+
+#[utoipa::path(
+    tag = "transfer",
+    request_body(content = String, content_type = "text/markdown"),
+    responses((status = OK, body = MarkdownImportSummary), JsonError)
+)]
+#[post("/api/markdown")]
+pub async fn import_markdown_lists(
+    req: HttpRequest,
+    body: String,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ImportMarkdownResponse>, JsonError> {
+    match req.mime_type() {
+        Ok(Some(mime))
+            if mime.type_() == mime::TEXT
+                && (mime.subtype() == "markdown" || mime.subtype() == mime::PLAIN) => {}
+        _ => {
+            return Err(JsonError::UnsupportedMediaType(
+                "Expected a 'Content-Type' header of text/markdown or text/plain".to_string(),
+            ));
+        }
+    }
+
+    let document = match MarkdownDocument::parse(&body, Utc::now().date_naive()) {
+        Ok(document) => document,
+        Err(err) => {
+            return Err(JsonError::BadRequest(format!(
+                "Error reading your Markdown: {}",
+                err
+            )));
+        }
+    };
+    if document.lists.is_empty() {
+        return Err(JsonError::BadRequest(
+            "Empty request not allowed".to_string(),
+        ));
+    }
+
+    match import_markdown(db_conn_pool, user.id, document).await {
+        Ok(summary) => Ok(Body(summary)),
+        Err(err) => map_query_err(err),
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::{BTreeSet, HashSet};
+
+use actix_web::{delete, get, post, put, web::Data};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{
+        types::{Body, CurrentUser, JsonError, MaybeBody},
+        utils::{query_all, query_all_or_some, query_some},
+    },
+    db::sqlx::{
+        delete_memberships as db_delete_memberships, insert_memberships, query_all_memberships,
+        query_memberships, query_shared_lists, update_memberships as db_update_memberships,
+    },
+    types::{CreateMembership, ListID, Membership, MembershipID, SharedList, UpdateMembership},
+};
+
+pub type CreateMembershipsRequest = Vec<CreateMembership>;
+pub type ReadMembershipsRequest = HashSet<ListID>;
+pub type UpdateMembershipsRequest = Vec<UpdateMembership>;
+pub type DeleteMembershipsRequest = HashSet<MembershipID>;
+
+pub type CreateMembershipsResponse = BTreeSet<Membership>;
+pub type ReadMembershipsResponse = BTreeSet<Membership>;
+pub type UpdateMembershipsResponse = BTreeSet<Membership>;
+pub type DeleteMembershipsResponse = HashSet<MembershipID>;
+pub type ReadSharedListsResponse = Vec<SharedList>;
+
+#[utoipa::path(
+    tag = "memberships",
+    request_body = Vec<CreateMembership>,
+    responses((status = OK, body = BTreeSet<Membership>), JsonError)
+)]
+#[post("/api/memberships")]
+pub async fn create_memberships(
+    req: MaybeBody<CreateMembershipsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<CreateMembershipsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        insert_memberships(db, user.id, adds)
+    })
+    .await
+}
+
+#[utoipa::path(
+    tag = "memberships",
+    request_body = Option<HashSet<ListID>>,
+    responses((status = OK, body = BTreeSet<Membership>), JsonError)
+)]
+#[get("/api/memberships")]
+pub async fn read_memberships(
+    req: MaybeBody<ReadMembershipsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ReadMembershipsResponse>, JsonError> {
+    query_all_or_some(
+        req,
+        db_conn_pool,
+        |db| query_all_memberships(db, user.id),
+        |db, adds| query_memberships(db, user.id, adds),
+    )
+    .await
+}
+
+#[utoipa::path(
+    tag = "memberships",
+    request_body = Vec<UpdateMembership>,
+    responses((status = OK, body = BTreeSet<Membership>), JsonError)
+)]
+#[put("/api/memberships")]
+pub async fn update_memberships(
+    req: MaybeBody<UpdateMembershipsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<UpdateMembershipsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_update_memberships(db, user.id, adds)
+    })
+    .await
+}
+
+#[utoipa::path(
+    tag = "memberships",
+    request_body = HashSet<MembershipID>,
+    responses((status = OK, body = HashSet<MembershipID>), JsonError)
+)]
+#[delete("/api/memberships")]
+pub async fn delete_memberships(
+    req: MaybeBody<DeleteMembershipsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<DeleteMembershipsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_delete_memberships(db, user.id, adds)
+    })
+    .await
+}
+
+/// The lists other users shared with the caller, along with their role in each.
+#[utoipa::path(
+    tag = "memberships",
+    responses((status = OK, body = Vec<SharedList>), JsonError)
+)]
+#[get("/api/lists/shared")]
+pub async fn read_shared_lists(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ReadSharedListsResponse>, JsonError> {
+    query_all(db_conn_pool, |db| query_shared_lists(db, user.id)).await
+}
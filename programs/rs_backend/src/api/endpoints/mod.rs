@@ -1,9 +1,29 @@
+mod accounts;
 mod create;
 mod delete;
+mod graphql;
+mod markdown;
+mod memberships;
 mod read;
+mod rpc;
+mod share_links;
+mod spreadsheet;
+mod stats;
+mod taskwarrior;
+mod transfer;
 mod update;
 
+pub use accounts::*;
 pub use create::*;
 pub use delete::*;
+pub use graphql::*;
+pub use markdown::*;
+pub use memberships::*;
 pub use read::*;
+pub use rpc::*;
+pub use share_links::*;
+pub use spreadsheet::*;
+pub use stats::*;
+pub use taskwarrior::*;
+pub use transfer::*;
 pub use update::*;
@@ -1,16 +1,24 @@
 use crate::{
     api::{
-        types::{JsonError, MaybeJson},
-        utils::query_all_or_some,
+        types::{
+            Body, Cached, CurrentUser, JsonError, MaybeBody, MaybeMessage, Message, Preconditions,
+            QueryTargets,
+        },
+        utils::{query_all_or_some, query_cached},
     },
     db::sqlx::{
-        query_all_lists, query_all_sets, query_all_todos, query_lists, query_sets, query_todos,
+        CHECKLIST_ITEMS_TABLES, LISTS_TABLES, SETS_TABLES, TAGS_TABLES, TODOS_TABLES,
+        query_all_checklist_items, query_all_lists, query_all_sets, query_all_tags,
+        query_all_todos, query_checklist_items, query_lists, query_sets, query_tags, query_todos,
+    },
+    types::{
+        ChecklistItem, ChecklistItemQueryTarget, List, ListID, Set, SetID, SetQueryTarget, Tag,
+        TagID, ToDo, ToDoID, ToDoQueryTarget, ToDoReadOptions, protobuf,
     },
-    types::{List, ListID, Set, SetQueryTarget, ToDo, ToDoQueryTarget},
 };
 use actix_web::{
     get,
-    web::{Data, Json},
+    web::{Data, Query},
 };
 use sqlx::{Pool, Sqlite};
 use std::collections::{BTreeSet, HashSet};
@@ -18,31 +26,214 @@ use std::collections::{BTreeSet, HashSet};
 pub type ReadListsRequest = HashSet<ListID>;
 pub type ReadSetsRequest = HashSet<SetQueryTarget>;
 pub type ReadToDosRequest = HashSet<ToDoQueryTarget>;
+pub type ReadTagsRequest = HashSet<TagID>;
+pub type ReadChecklistItemsRequest = HashSet<ChecklistItemQueryTarget>;
 
 pub type ReadListsResponse = BTreeSet<List>;
-pub type ReadSetsResponse = BTreeSet<Set>;
-pub type ReadToDosResponse = BTreeSet<ToDo>;
+/// Sets and to dos are returned in their user-defined order, grouped by list.
+pub type ReadSetsResponse = Vec<Set>;
+pub type ReadToDosResponse = Vec<ToDo>;
+pub type ReadTagsResponse = BTreeSet<Tag>;
+/// Checklist items are returned in their checklist's order, grouped by to do.
+pub type ReadChecklistItemsResponse = Vec<ChecklistItem>;
 
+#[utoipa::path(
+    tag = "lists",
+    request_body = Option<HashSet<ListID>>,
+    params(
+        ("list" = Option<Vec<ListID>>, Query, description = "Lists to read, in place of a body"),
+    ),
+    responses(
+        (status = OK, body = BTreeSet<List>),
+        (status = NOT_MODIFIED, description = "The copy the client holds is still current"),
+        JsonError
+    )
+)]
 #[get("/api/lists")]
 pub async fn read_lists(
-    req: MaybeJson<ReadListsRequest>,
+    req: MaybeMessage<ReadListsRequest, protobuf::ReadListsRequest>,
+    targets: QueryTargets<ReadListsRequest>,
+    preconditions: Preconditions,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<ReadListsResponse>, JsonError> {
-    query_all_or_some(req, db_conn_pool, query_all_lists, query_lists).await
+    user: CurrentUser,
+) -> Result<Cached<Message<ReadListsResponse, protobuf::ReadListsResponse>>, JsonError> {
+    query_cached(
+        preconditions,
+        targets.or_body(req.into_inner())?,
+        db_conn_pool,
+        user.id,
+        LISTS_TABLES,
+        |db, req| async move {
+            query_all_or_some(
+                req,
+                db,
+                |db| query_all_lists(db, user.id),
+                |db, adds| query_lists(db, user.id, adds),
+            )
+            .await
+            .map(Message::from)
+        },
+    )
+    .await
 }
 
+#[utoipa::path(
+    tag = "sets",
+    request_body = Option<HashSet<SetQueryTarget>>,
+    params(
+        ("list" = Option<Vec<ListID>>, Query, description = "Read the sets in these lists, in place of a body"),
+        ("set" = Option<Vec<SetID>>, Query, description = "Read these sets"),
+        ("subtree" = Option<Vec<SetID>>, Query, description = "Read these sets and all the sets nested in them"),
+        ("address" = Option<Vec<String>>, Query, description = "Read the sets at these addresses, `<list>` or `<list>/<sid>`"),
+    ),
+    responses(
+        (status = OK, body = Vec<Set>),
+        (status = NOT_MODIFIED, description = "The copy the client holds is still current"),
+        JsonError
+    )
+)]
 #[get("/api/sets")]
 pub async fn read_sets(
-    req: MaybeJson<ReadSetsRequest>,
+    req: MaybeMessage<ReadSetsRequest, protobuf::ReadSetsRequest>,
+    targets: QueryTargets<ReadSetsRequest>,
+    preconditions: Preconditions,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<ReadSetsResponse>, JsonError> {
-    query_all_or_some(req, db_conn_pool, query_all_sets, query_sets).await
+    user: CurrentUser,
+) -> Result<Cached<Message<ReadSetsResponse, protobuf::ReadSetsResponse>>, JsonError> {
+    query_cached(
+        preconditions,
+        targets.or_body(req.into_inner())?,
+        db_conn_pool,
+        user.id,
+        SETS_TABLES,
+        |db, req| async move {
+            query_all_or_some(
+                req,
+                db,
+                |db| query_all_sets(db, user.id),
+                |db, adds| query_sets(db, user.id, adds),
+            )
+            .await
+            .map(Message::from)
+        },
+    )
+    .await
 }
 
+#[utoipa::path(
+    tag = "to dos",
+    request_body = Option<HashSet<ToDoQueryTarget>>,
+    params(
+        ToDoReadOptions,
+        ("list" = Option<Vec<ListID>>, Query, description = "Read the to dos in these lists, in place of a body"),
+        ("set" = Option<Vec<SetID>>, Query, description = "Read the to dos in these sets"),
+        ("todo" = Option<Vec<ToDoID>>, Query, description = "Read these to dos"),
+        ("subtree" = Option<Vec<SetID>>, Query, description = "Read the to dos in these sets and the sets nested in them"),
+        ("tag" = Option<Vec<TagID>>, Query, description = "Read the to dos with these tags"),
+        ("address" = Option<Vec<String>>, Query, description = "Read the to dos at these addresses, `<list>`, `<list>/<sid>`, `<list>/<sid>/<tdid>` or `<list>//<tdid>` for one outside of any set"),
+    ),
+    responses(
+        (status = OK, body = Vec<ToDo>),
+        (status = NOT_MODIFIED, description = "The copy the client holds is still current"),
+        JsonError
+    )
+)]
 #[get("/api/to_dos")]
 pub async fn read_to_dos(
-    req: MaybeJson<ReadToDosRequest>,
+    req: MaybeMessage<ReadToDosRequest, protobuf::ReadToDosRequest>,
+    targets: QueryTargets<ReadToDosRequest>,
+    options: Query<ToDoReadOptions>,
+    preconditions: Preconditions,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Cached<Message<ReadToDosResponse, protobuf::ReadToDosResponse>>, JsonError> {
+    let options = options.into_inner();
+    query_cached(
+        preconditions,
+        targets.or_body(req.into_inner())?,
+        db_conn_pool,
+        user.id,
+        TODOS_TABLES,
+        |db, req| async move {
+            query_all_or_some(
+                req,
+                db,
+                |db| query_all_todos(db, user.id, options.clone()),
+                |db, targets| query_todos(db, user.id, targets, options.clone()),
+            )
+            .await
+            .map(Message::from)
+        },
+    )
+    .await
+}
+
+#[utoipa::path(
+    tag = "tags",
+    request_body = Option<HashSet<TagID>>,
+    responses(
+        (status = OK, body = BTreeSet<Tag>),
+        (status = NOT_MODIFIED, description = "The copy the client holds is still current"),
+        JsonError
+    )
+)]
+#[get("/api/tags")]
+pub async fn read_tags(
+    req: MaybeBody<ReadTagsRequest>,
+    preconditions: Preconditions,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Cached<Body<ReadTagsResponse>>, JsonError> {
+    query_cached(
+        preconditions,
+        req,
+        db_conn_pool,
+        user.id,
+        TAGS_TABLES,
+        |db, req| async move {
+            query_all_or_some(
+                req,
+                db,
+                |db| query_all_tags(db, user.id),
+                |db, adds| query_tags(db, user.id, adds),
+            )
+            .await
+        },
+    )
+    .await
+}
+
+#[utoipa::path(
+    tag = "checklist items",
+    request_body = Option<HashSet<ChecklistItemQueryTarget>>,
+    responses(
+        (status = OK, body = Vec<ChecklistItem>),
+        (status = NOT_MODIFIED, description = "The copy the client holds is still current"),
+        JsonError
+    )
+)]
+#[get("/api/checklist_items")]
+pub async fn read_checklist_items(
+    req: MaybeBody<ReadChecklistItemsRequest>,
+    preconditions: Preconditions,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<ReadToDosResponse>, JsonError> {
-    query_all_or_some(req, db_conn_pool, query_all_todos, query_todos).await
+    user: CurrentUser,
+) -> Result<Cached<Body<ReadChecklistItemsResponse>>, JsonError> {
+    query_cached(
+        preconditions,
+        req,
+        db_conn_pool,
+        user.id,
+        CHECKLIST_ITEMS_TABLES,
+        |db, req| async move {
+            query_all_or_some(
+                req,
+                db,
+                |db| query_all_checklist_items(db, user.id),
+                |db, adds| query_checklist_items(db, user.id, adds),
+            )
+            .await
+        },
+    )
+    .await
 }
@@ -0,0 +1,48 @@
+use actix_web::{
+    HttpRequest, HttpResponse, post,
+    web::{Data, Payload},
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::api::{
+    rpc::{handle, refuse},
+    types::{CurrentUser, MaybeBodyConfig, RateLimiter, declared_length, read_payload},
+    utils::input_err,
+};
+
+/// Runs JSON-RPC 2.0 calls, alone or in batches, as the logged in user. The methods are the
+/// README's, like `createLists` and `deleteToDos`, and take the same bodies as their REST routes
+/// as `params`. Failed calls are reported in the response, which is always sent as `200 OK`,
+/// unless there's nothing to answer, as for a batch of notifications. The body has the same size
+/// limit as every other, and each call of a batch counts against the rate limit.
+#[post("/rpc")]
+pub async fn json_rpc(
+    req: HttpRequest,
+    payload: Payload,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    limiter: Option<Data<RateLimiter>>,
+    user: CurrentUser,
+) -> HttpResponse {
+    let limit = req
+        .app_data::<MaybeBodyConfig>()
+        .cloned()
+        .unwrap_or_default()
+        .get_limit();
+    let body = match declared_length(&req, limit) {
+        Ok(_) => read_payload(payload.into_inner(), limit).await,
+        Err(e) => Err(e),
+    };
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return HttpResponse::Ok().json(refuse(input_err(e))),
+    };
+
+    let charge = |calls| match &limiter {
+        Some(limiter) => limiter.charge(&req, calls),
+        None => Ok(()),
+    };
+    match handle(db_conn_pool, user.id, &body, charge).await {
+        Some(reply) => HttpResponse::Ok().json(reply),
+        None => HttpResponse::NoContent().finish(),
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::{BTreeSet, HashSet};
+
+use actix_web::{
+    delete, get, post,
+    web::{Data, Path},
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{
+        types::{Body, CurrentUser, JsonError, MaybeBody},
+        utils::{map_query_err, query_all_or_some, query_some},
+    },
+    db::sqlx::{
+        delete_share_links as db_delete_share_links, insert_share_links, query_all_share_links,
+        query_share_links, query_shared_document,
+    },
+    types::{CreateShareLink, ListID, ShareLink, ShareLinkID, SharedListDocument},
+};
+
+pub type CreateShareLinksRequest = Vec<CreateShareLink>;
+pub type ReadShareLinksRequest = HashSet<ListID>;
+pub type DeleteShareLinksRequest = HashSet<ShareLinkID>;
+
+pub type CreateShareLinksResponse = HashSet<ShareLink>;
+pub type ReadShareLinksResponse = BTreeSet<ShareLink>;
+pub type DeleteShareLinksResponse = HashSet<ShareLinkID>;
+pub type ReadSharedListResponse = SharedListDocument;
+
+#[utoipa::path(
+    tag = "share links",
+    request_body = Vec<CreateShareLink>,
+    responses((status = OK, body = HashSet<ShareLink>), JsonError)
+)]
+#[post("/api/share_links")]
+pub async fn create_share_links(
+    req: MaybeBody<CreateShareLinksRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<CreateShareLinksResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        insert_share_links(db, user.id, adds)
+    })
+    .await
+}
+
+#[utoipa::path(
+    tag = "share links",
+    request_body = Option<HashSet<ListID>>,
+    responses((status = OK, body = BTreeSet<ShareLink>), JsonError)
+)]
+#[get("/api/share_links")]
+pub async fn read_share_links(
+    req: MaybeBody<ReadShareLinksRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ReadShareLinksResponse>, JsonError> {
+    query_all_or_some(
+        req,
+        db_conn_pool,
+        |db| query_all_share_links(db, user.id),
+        |db, adds| query_share_links(db, user.id, adds),
+    )
+    .await
+}
+
+/// Revokes share links. Their tokens stop working right away.
+#[utoipa::path(
+    tag = "share links",
+    request_body = HashSet<ShareLinkID>,
+    responses((status = OK, body = HashSet<ShareLinkID>), JsonError)
+)]
+#[delete("/api/share_links")]
+pub async fn delete_share_links(
+    req: MaybeBody<DeleteShareLinksRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<DeleteShareLinksResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_delete_share_links(db, user.id, adds)
+    })
+    .await
+}
+
+/// The read-only view of a shared list. This route sits outside `/api`, so it needs no login.
+#[utoipa::path(
+    tag = "share links",
+    security(()),
+    responses((status = OK, body = SharedListDocument), JsonError)
+)]
+#[get("/share/{token}")]
+pub async fn read_shared_list(
+    token: Path<String>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+) -> Result<Body<ReadSharedListResponse>, JsonError> {
+    match query_shared_document(db_conn_pool, token.into_inner()).await {
+        Ok(Some(document)) => Ok(Body(document)),
+        Ok(None) => Err(JsonError::NotFound(
+            "This share link doesn't exist, was revoked or has expired".to_string(),
+        )),
+        Err(err) => map_query_err(err),
+    }
+}
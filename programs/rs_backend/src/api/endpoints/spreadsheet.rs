@@ -0,0 +1,93 @@
+use actix_web::{
+    HttpMessage, HttpRequest, HttpResponse, get, mime, post,
+    web::{Bytes, Data},
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{
+        ReadToDosRequest,
+        types::{Body, CurrentUser, JsonError, MaybeBody},
+        utils::{map_query_err, query_all_or_some},
+    },
+    db::sqlx::{import_todos_csv, query_all_todos_csv, query_todos_csv},
+    types::{CsvImport, CsvImportReport, CsvToDoRecord, ToDoQueryTarget, write_csv_records},
+};
+
+pub type ExportToDosCsvRequest = ReadToDosRequest;
+pub type ImportToDosCsvRequest = CsvImport;
+
+pub type ExportToDosCsvResponse = Vec<CsvToDoRecord>;
+pub type ImportToDosCsvResponse = CsvImportReport;
+
+#[utoipa::path(
+    tag = "transfer",
+    request_body = Option<HashSet<ToDoQueryTarget>>,
+    responses((status = OK, body = String, content_type = "text/csv"), JsonError)
+)]
+#[get("/api/to_dos/csv")]
+pub async fn export_to_dos_csv(
+    req: MaybeBody<ExportToDosCsvRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<HttpResponse, JsonError> {
+    let Body(records) = query_all_or_some(
+        req,
+        db_conn_pool,
+        |db| query_all_todos_csv(db, user.id),
+        |db, adds| query_todos_csv(db, user.id, adds),
+    )
+    .await?;
+
+    match write_csv_records(&records) {
+        Ok(body) => Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .body(body)),
+        Err(err) => Err(JsonError::ServerError(format!(
+            "Error writing the CSV export: {}",
+            err
+        ))),
+    }
+}
+
+#[utoipa::path(
+    tag = "transfer",
+    request_body(content = String, content_type = "text/csv"),
+    responses((status = OK, body = CsvImportReport), JsonError)
+)]
+#[post("/api/to_dos/csv")]
+pub async fn import_to_dos_csv(
+    req: HttpRequest,
+    body: Bytes,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ImportToDosCsvResponse>, JsonError> {
+    match req.mime_type() {
+        Ok(Some(mime)) if mime.subtype() == mime::CSV => {}
+        _ => {
+            return Err(JsonError::UnsupportedMediaType(
+                "Expected a 'Content-Type' header of text/csv".to_string(),
+            ));
+        }
+    }
+
+    let import = match CsvImport::parse(&body) {
+        Ok(import) => import,
+        Err(err) => {
+            return Err(JsonError::BadRequest(format!(
+                "Error reading your CSV header: {}",
+                err
+            )));
+        }
+    };
+    if import.is_empty() {
+        return Err(JsonError::BadRequest(
+            "Empty request not allowed".to_string(),
+        ));
+    }
+
+    match import_todos_csv(db_conn_pool, user.id, import).await {
+        Ok(report) => Ok(Body(report)),
+        Err(err) => map_query_err(err),
+    }
+}
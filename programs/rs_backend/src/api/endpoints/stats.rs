@@ -0,0 +1,25 @@
+use actix_web::{get, web::Data};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{
+        types::{Body, CurrentUser, JsonError},
+        utils::query_all,
+    },
+    db::sqlx::query_stats,
+    types::Stats,
+};
+
+pub type StatsResponse = Stats;
+
+#[utoipa::path(
+    tag = "stats",
+    responses((status = OK, body = Stats), JsonError)
+)]
+#[get("/api/stats")]
+pub async fn read_stats(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<StatsResponse>, JsonError> {
+    query_all(db_conn_pool, |db| query_stats(db, user.id)).await
+}
@@ -0,0 +1,57 @@
+use actix_web::{get, post, web::Data};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{
+        ReadToDosRequest,
+        types::{Body, CurrentUser, JsonError, MaybeBody},
+        utils::{query_all_or_some, query_some},
+    },
+    db::sqlx::{
+        import_taskwarrior as db_import_taskwarrior, query_all_taskwarrior, query_taskwarrior,
+    },
+    types::{TaskwarriorImportSummary, TaskwarriorTask, ToDoQueryTarget},
+};
+
+pub type ExportTaskwarriorRequest = ReadToDosRequest;
+pub type ImportTaskwarriorRequest = Vec<TaskwarriorTask>;
+
+pub type ExportTaskwarriorResponse = Vec<TaskwarriorTask>;
+pub type ImportTaskwarriorResponse = TaskwarriorImportSummary;
+
+#[utoipa::path(
+    tag = "transfer",
+    request_body = Option<HashSet<ToDoQueryTarget>>,
+    responses((status = OK, body = Vec<TaskwarriorTask>), JsonError)
+)]
+#[get("/api/taskwarrior")]
+pub async fn export_taskwarrior(
+    req: MaybeBody<ExportTaskwarriorRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ExportTaskwarriorResponse>, JsonError> {
+    query_all_or_some(
+        req,
+        db_conn_pool,
+        |db| query_all_taskwarrior(db, user.id),
+        |db, adds| query_taskwarrior(db, user.id, adds),
+    )
+    .await
+}
+
+#[utoipa::path(
+    tag = "transfer",
+    request_body = Vec<TaskwarriorTask>,
+    responses((status = OK, body = TaskwarriorImportSummary), JsonError)
+)]
+#[post("/api/taskwarrior")]
+pub async fn import_taskwarrior(
+    req: MaybeBody<ImportTaskwarriorRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ImportTaskwarriorResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_import_taskwarrior(db, user.id, adds)
+    })
+    .await
+}
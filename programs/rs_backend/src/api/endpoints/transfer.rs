@@ -0,0 +1,50 @@
+use actix_web::{
+    get, post,
+    web::{Data, Query},
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{
+        types::{Body, CurrentUser, JsonError, MaybeBody},
+        utils::{query_all, query_some},
+    },
+    db::sqlx::{export_all, import_all},
+    types::{ExportDocument, ImportOptions, ImportSummary},
+};
+
+pub type ExportResponse = ExportDocument;
+pub type ImportRequest = ExportDocument;
+pub type ImportResponse = ImportSummary;
+
+#[utoipa::path(
+    tag = "transfer",
+    responses((status = OK, body = ExportDocument), JsonError)
+)]
+#[get("/api/export")]
+pub async fn export_database(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ExportResponse>, JsonError> {
+    query_all(db_conn_pool, |db| export_all(db, user.id)).await
+}
+
+#[utoipa::path(
+    tag = "transfer",
+    request_body = ExportDocument,
+    params(ImportOptions),
+    responses((status = OK, body = ImportSummary), JsonError)
+)]
+#[post("/api/import")]
+pub async fn import_database(
+    req: MaybeBody<ImportRequest>,
+    options: Query<ImportOptions>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<ImportResponse>, JsonError> {
+    let mode = options.mode;
+    query_some(req, db_conn_pool, |db, document| {
+        import_all(db, user.id, document, mode)
+    })
+    .await
+}
@@ -1,51 +1,178 @@
 use std::collections::BTreeSet;
 
-use actix_web::{
-    put,
-    web::{Data, Json},
-};
+use actix_web::{put, web::Data};
 use sqlx::{Pool, Sqlite};
 
 use crate::{
     api::{
-        types::{JsonError, MaybeJson},
+        types::{Body, CurrentUser, JsonError, MaybeBody, MaybeMessage, Message},
         utils::query_some,
     },
     db::sqlx::{
+        move_checklist_items as db_move_checklist_items, move_sets as db_move_sets,
+        move_todos as db_move_todos, update_checklist_items as db_update_checklist_items,
         update_lists as db_update_lists, update_sets as db_update_sets,
-        update_todos as db_update_todos,
+        update_tags as db_update_tags, update_todos as db_update_todos,
+    },
+    types::{
+        ChecklistItem, List, MoveChecklistItem, MoveSet, MoveToDo, Set, Tag, ToDo,
+        UpdateChecklistItem, UpdateList, UpdateSet, UpdateTag, UpdateToDo, protobuf,
     },
-    types::{List, Set, ToDo, UpdateList, UpdateSet, UpdateToDo},
 };
 
 pub type UpdateListsRequest = Vec<UpdateList>;
 pub type UpdateSetsRequest = Vec<UpdateSet>;
 pub type UpdateToDosRequest = Vec<UpdateToDo>;
+pub type UpdateTagsRequest = Vec<UpdateTag>;
+pub type UpdateChecklistItemsRequest = Vec<UpdateChecklistItem>;
+pub type MoveSetsRequest = Vec<MoveSet>;
+pub type MoveToDosRequest = Vec<MoveToDo>;
+pub type MoveChecklistItemsRequest = Vec<MoveChecklistItem>;
 
 pub type UpdateListsResponse = BTreeSet<List>;
 pub type UpdateSetsResponse = BTreeSet<Set>;
 pub type UpdateToDoResponse = BTreeSet<ToDo>;
+pub type UpdateTagsResponse = BTreeSet<Tag>;
+pub type UpdateChecklistItemsResponse = BTreeSet<ChecklistItem>;
+pub type MoveSetsResponse = BTreeSet<Set>;
+pub type MoveToDosResponse = BTreeSet<ToDo>;
+pub type MoveChecklistItemsResponse = BTreeSet<ChecklistItem>;
 
+#[utoipa::path(
+    tag = "lists",
+    request_body = Vec<UpdateList>,
+    responses((status = OK, body = BTreeSet<List>), JsonError)
+)]
 #[put("/api/lists")]
 pub async fn update_lists(
-    req: MaybeJson<UpdateListsRequest>,
+    req: MaybeMessage<UpdateListsRequest, protobuf::UpdateListsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<UpdateListsResponse>, JsonError> {
-    query_some(req, db_conn_pool, db_update_lists).await
+    user: CurrentUser,
+) -> Result<Message<UpdateListsResponse, protobuf::UpdateListsResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        db_update_lists(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
 }
 
+#[utoipa::path(
+    tag = "sets",
+    request_body = Vec<UpdateSet>,
+    responses((status = OK, body = BTreeSet<Set>), JsonError)
+)]
 #[put("/api/sets")]
 pub async fn update_sets(
-    req: MaybeJson<UpdateSetsRequest>,
+    req: MaybeMessage<UpdateSetsRequest, protobuf::UpdateSetsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<UpdateSetsResponse>, JsonError> {
-    query_some(req, db_conn_pool, db_update_sets).await
+    user: CurrentUser,
+) -> Result<Message<UpdateSetsResponse, protobuf::UpdateSetsResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        db_update_sets(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
 }
 
+#[utoipa::path(
+    tag = "to dos",
+    request_body = Vec<UpdateToDo>,
+    responses((status = OK, body = BTreeSet<ToDo>), JsonError)
+)]
 #[put("/api/to_dos")]
 pub async fn update_to_dos(
-    req: MaybeJson<UpdateToDosRequest>,
+    req: MaybeMessage<UpdateToDosRequest, protobuf::UpdateToDosRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Message<UpdateToDoResponse, protobuf::UpdateToDosResponse>, JsonError> {
+    query_some(req.into_inner(), db_conn_pool, |db, adds| {
+        db_update_todos(db, user.id, adds)
+    })
+    .await
+    .map(Message::from)
+}
+
+#[utoipa::path(
+    tag = "tags",
+    request_body = Vec<UpdateTag>,
+    responses((status = OK, body = BTreeSet<Tag>), JsonError)
+)]
+#[put("/api/tags")]
+pub async fn update_tags(
+    req: MaybeBody<UpdateTagsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<UpdateTagsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_update_tags(db, user.id, adds)
+    })
+    .await
+}
+
+#[utoipa::path(
+    tag = "checklist items",
+    request_body = Vec<UpdateChecklistItem>,
+    responses((status = OK, body = BTreeSet<ChecklistItem>), JsonError)
+)]
+#[put("/api/checklist_items")]
+pub async fn update_checklist_items(
+    req: MaybeBody<UpdateChecklistItemsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<UpdateChecklistItemsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_update_checklist_items(db, user.id, adds)
+    })
+    .await
+}
+
+#[utoipa::path(
+    tag = "sets",
+    request_body = Vec<MoveSet>,
+    responses((status = OK, body = BTreeSet<Set>), JsonError)
+)]
+#[put("/api/sets/move")]
+pub async fn move_sets(
+    req: MaybeBody<MoveSetsRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<MoveSetsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_move_sets(db, user.id, adds)
+    })
+    .await
+}
+
+#[utoipa::path(
+    tag = "to dos",
+    request_body = Vec<MoveToDo>,
+    responses((status = OK, body = BTreeSet<ToDo>), JsonError)
+)]
+#[put("/api/to_dos/move")]
+pub async fn move_to_dos(
+    req: MaybeBody<MoveToDosRequest>,
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user: CurrentUser,
+) -> Result<Body<MoveToDosResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_move_todos(db, user.id, adds)
+    })
+    .await
+}
+
+#[utoipa::path(
+    tag = "checklist items",
+    request_body = Vec<MoveChecklistItem>,
+    responses((status = OK, body = BTreeSet<ChecklistItem>), JsonError)
+)]
+#[put("/api/checklist_items/move")]
+pub async fn move_checklist_items(
+    req: MaybeBody<MoveChecklistItemsRequest>,
     db_conn_pool: Data<Pool<Sqlite>>,
-) -> Result<Json<UpdateToDoResponse>, JsonError> {
-    query_some(req, db_conn_pool, db_update_todos).await
+    user: CurrentUser,
+) -> Result<Body<MoveChecklistItemsResponse>, JsonError> {
+    query_some(req, db_conn_pool, |db, adds| {
+        db_move_checklist_items(db, user.id, adds)
+    })
+    .await
 }
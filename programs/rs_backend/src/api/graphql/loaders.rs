@@ -0,0 +1,183 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use actix_web::web::Data;
+use async_graphql::dataloader::Loader;
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::{types::JsonError, utils::query_err},
+    db::sqlx::{query_lists, query_sets, query_todos},
+    types::{
+        List, ListID, Set, SetID, SetQueryTarget, ToDo, ToDoID, ToDoQueryTarget, ToDoReadOptions,
+        UserID,
+    },
+};
+
+/// Batches the reads of a GraphQL request, so a query walking down lists, sets and to dos makes
+/// one database query per level instead of one per entry. Everything is read as `user_id`, with
+/// the same access checks as the REST routes.
+pub struct Store {
+    pub db: Data<Pool<Sqlite>>,
+    pub user_id: UserID,
+}
+
+/// The sets sitting directly in a list, outside of any other set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListSets(pub ListID);
+
+/// The sets nested directly inside a set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SetSets(pub SetID);
+
+/// The to dos of a list that aren't in any set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListToDos(pub ListID);
+
+/// The to dos directly inside a set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SetToDos(pub SetID);
+
+/// Files `values` under the key they belong to, if any. Every key gets an entry, so keys without
+/// values are cached as empty too.
+fn group<K: Copy + Eq + Hash, V>(
+    keys: &[K],
+    values: impl IntoIterator<Item = V>,
+    key: impl Fn(&V) -> Option<K>,
+) -> HashMap<K, Vec<V>> {
+    let mut groups: HashMap<K, Vec<V>> = keys.iter().map(|&k| (k, Vec::new())).collect();
+    for value in values {
+        if let Some(group) = key(&value).and_then(|k| groups.get_mut(&k)) {
+            group.push(value);
+        }
+    }
+    groups
+}
+
+impl Loader<ListID> for Store {
+    type Value = List;
+    type Error = JsonError;
+
+    async fn load(&self, keys: &[ListID]) -> Result<HashMap<ListID, List>, JsonError> {
+        let lists = query_lists(
+            self.db.clone(),
+            self.user_id,
+            keys.iter().copied().collect(),
+        )
+        .await
+        .map_err(query_err)?;
+        Ok(lists.into_iter().map(|list| (list.id, list)).collect())
+    }
+}
+
+impl Loader<SetID> for Store {
+    type Value = Set;
+    type Error = JsonError;
+
+    async fn load(&self, keys: &[SetID]) -> Result<HashMap<SetID, Set>, JsonError> {
+        let targets = keys.iter().copied().map(SetQueryTarget::Set).collect();
+        let sets = query_sets(self.db.clone(), self.user_id, targets)
+            .await
+            .map_err(query_err)?;
+        Ok(sets.into_iter().map(|set| (set.id, set)).collect())
+    }
+}
+
+impl Loader<ToDoID> for Store {
+    type Value = ToDo;
+    type Error = JsonError;
+
+    async fn load(&self, keys: &[ToDoID]) -> Result<HashMap<ToDoID, ToDo>, JsonError> {
+        let targets = keys.iter().copied().map(ToDoQueryTarget::ToDo).collect();
+        let todos = query_todos(
+            self.db.clone(),
+            self.user_id,
+            targets,
+            ToDoReadOptions::default(),
+        )
+        .await
+        .map_err(query_err)?;
+        Ok(todos.into_iter().map(|todo| (todo.id, todo)).collect())
+    }
+}
+
+impl Loader<ListSets> for Store {
+    type Value = Vec<Set>;
+    type Error = JsonError;
+
+    async fn load(&self, keys: &[ListSets]) -> Result<HashMap<ListSets, Vec<Set>>, JsonError> {
+        let targets = keys
+            .iter()
+            .map(|&ListSets(id)| SetQueryTarget::List(id))
+            .collect();
+        let sets = query_sets(self.db.clone(), self.user_id, targets)
+            .await
+            .map_err(query_err)?;
+        Ok(group(keys, sets, |set| {
+            set.parent_id.is_none().then_some(ListSets(set.list_id))
+        }))
+    }
+}
+
+impl Loader<SetSets> for Store {
+    type Value = Vec<Set>;
+    type Error = JsonError;
+
+    async fn load(&self, keys: &[SetSets]) -> Result<HashMap<SetSets, Vec<Set>>, JsonError> {
+        // Subtrees are the one target that reaches nested sets, and they hold the children too.
+        let targets = keys
+            .iter()
+            .map(|&SetSets(id)| SetQueryTarget::Subtree(id))
+            .collect();
+        let sets = query_sets(self.db.clone(), self.user_id, targets)
+            .await
+            .map_err(query_err)?;
+        Ok(group(keys, sets, |set| set.parent_id.map(SetSets)))
+    }
+}
+
+impl Loader<ListToDos> for Store {
+    type Value = Vec<ToDo>;
+    type Error = JsonError;
+
+    async fn load(&self, keys: &[ListToDos]) -> Result<HashMap<ListToDos, Vec<ToDo>>, JsonError> {
+        let targets = keys
+            .iter()
+            .map(|&ListToDos(id)| ToDoQueryTarget::List(id))
+            .collect::<HashSet<ToDoQueryTarget>>();
+        let todos = query_todos(
+            self.db.clone(),
+            self.user_id,
+            targets,
+            ToDoReadOptions::default(),
+        )
+        .await
+        .map_err(query_err)?;
+        Ok(group(keys, todos, |todo| {
+            todo.set_id.is_none().then_some(ListToDos(todo.list_id))
+        }))
+    }
+}
+
+impl Loader<SetToDos> for Store {
+    type Value = Vec<ToDo>;
+    type Error = JsonError;
+
+    async fn load(&self, keys: &[SetToDos]) -> Result<HashMap<SetToDos, Vec<ToDo>>, JsonError> {
+        let targets = keys
+            .iter()
+            .map(|&SetToDos(id)| ToDoQueryTarget::Set(id))
+            .collect::<HashSet<ToDoQueryTarget>>();
+        let todos = query_todos(
+            self.db.clone(),
+            self.user_id,
+            targets,
+            ToDoReadOptions::default(),
+        )
+        .await
+        .map_err(query_err)?;
+        Ok(group(keys, todos, |todo| todo.set_id.map(SetToDos)))
+    }
+}
@@ -0,0 +1,409 @@
+mod loaders;
+mod objects;
+
+use std::collections::HashSet;
+
+use actix_web::web::Data;
+use async_graphql::{
+    Context, EmptySubscription, Object, Request, Result, Schema, dataloader::DataLoader,
+    parser::types::OperationType,
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::utils::query_err,
+    db::sqlx::{
+        delete_lists, delete_sets, delete_todos, insert_lists, insert_sets, insert_todos,
+        query_all_lists, query_all_sets, query_all_todos, query_lists, query_sets, query_todos,
+        update_lists, update_sets, update_todos,
+    },
+    types::{List, ListID, Set, SetID, ToDo, ToDoID, ToDoReadOptions, UserID},
+};
+
+pub use loaders::Store;
+use objects::{
+    CreateListInput, CreateSetInput, CreateToDoInput, SetTarget, ToDoTarget, UpdateListInput,
+    UpdateSetInput, UpdateToDoInput, load_one,
+};
+
+pub type ToDoSchema = Schema<Query, Mutation, EmptySubscription>;
+
+/// How deeply a query may nest fields. Entries link back to each other (a set to its list and
+/// the list to its sets again), so without a limit one short query could make the server load
+/// the same rows over and over. It leaves room for sets nested about ten deep.
+const MAX_DEPTH: usize = 12;
+
+/// How many fields a query may select in total, counting each field once.
+const MAX_COMPLEXITY: usize = 500;
+
+pub fn new_schema() -> ToDoSchema {
+    Schema::build(Query, Mutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+impl Store {
+    /// The loader a request is run with, which also tells resolvers who's asking.
+    pub fn loader(db: Data<Pool<Sqlite>>, user_id: UserID) -> DataLoader<Store> {
+        DataLoader::new(Store { db, user_id }, actix_web::rt::spawn)
+    }
+}
+
+/// Whether the operation `request` runs is a mutation. Queries that don't parse fail once they're
+/// run, so they count as reads.
+pub fn is_mutation(request: &mut Request) -> bool {
+    let operation_name = request.operation_name.clone();
+    let Ok(document) = request.parsed_query() else {
+        return false;
+    };
+    document.operations.iter().any(|(name, operation)| {
+        operation.node.ty == OperationType::Mutation
+            && operation_name
+                .as_deref()
+                .is_none_or(|wanted| name.is_some_and(|name| name.as_str() == wanted))
+    })
+}
+
+fn store<'a>(ctx: &Context<'a>) -> Result<&'a Store> {
+    Ok(ctx.data::<DataLoader<Store>>()?.loader())
+}
+
+/// Sorts the entries a write hands back, which come as a set, by id.
+fn sorted<T: Ord>(entries: impl IntoIterator<Item = T>) -> Vec<T> {
+    let mut entries: Vec<T> = entries.into_iter().collect();
+    entries.sort();
+    entries
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Lists by id. Leaving the ids out, or passing none, reads every list.
+    async fn lists(&self, ctx: &Context<'_>, ids: Option<Vec<ListID>>) -> Result<Vec<List>> {
+        let store = store(ctx)?;
+        let lists = match ids.filter(|ids| !ids.is_empty()) {
+            Some(ids) => {
+                query_lists(store.db.clone(), store.user_id, HashSet::from_iter(ids)).await
+            }
+            None => query_all_lists(store.db.clone(), store.user_id).await,
+        };
+        Ok(lists.map_err(query_err)?.into_iter().collect())
+    }
+
+    async fn list(&self, ctx: &Context<'_>, id: ListID) -> Result<Option<List>> {
+        load_one(ctx, id).await
+    }
+
+    /// Sets in their user-defined order, grouped by list. Leaving the targets out, or passing
+    /// none, reads every set.
+    async fn sets(&self, ctx: &Context<'_>, targets: Option<Vec<SetTarget>>) -> Result<Vec<Set>> {
+        let store = store(ctx)?;
+        let sets = match targets.filter(|targets| !targets.is_empty()) {
+            Some(targets) => {
+                let targets = targets.into_iter().map(Into::into).collect();
+                query_sets(store.db.clone(), store.user_id, targets).await
+            }
+            None => query_all_sets(store.db.clone(), store.user_id).await,
+        };
+        Ok(sets.map_err(query_err)?)
+    }
+
+    async fn set(&self, ctx: &Context<'_>, id: SetID) -> Result<Option<Set>> {
+        load_one(ctx, id).await
+    }
+
+    /// To dos in their user-defined order, grouped by list and set. Leaving the targets out, or
+    /// passing none, reads every to do.
+    async fn to_dos(
+        &self,
+        ctx: &Context<'_>,
+        targets: Option<Vec<ToDoTarget>>,
+    ) -> Result<Vec<ToDo>> {
+        let store = store(ctx)?;
+        let options = ToDoReadOptions::default();
+        let todos = match targets.filter(|targets| !targets.is_empty()) {
+            Some(targets) => {
+                let targets = targets.into_iter().map(Into::into).collect();
+                query_todos(store.db.clone(), store.user_id, targets, options).await
+            }
+            None => query_all_todos(store.db.clone(), store.user_id, options).await,
+        };
+        Ok(todos.map_err(query_err)?)
+    }
+
+    async fn to_do(&self, ctx: &Context<'_>, id: ToDoID) -> Result<Option<ToDo>> {
+        load_one(ctx, id).await
+    }
+}
+
+/// The same writes as the REST routes, which hand back what they made or changed.
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    async fn create_lists(
+        &self,
+        ctx: &Context<'_>,
+        lists: Vec<CreateListInput>,
+    ) -> Result<Vec<List>> {
+        let store = store(ctx)?;
+        let adds = lists.into_iter().map(Into::into).collect();
+        let lists = insert_lists(store.db.clone(), store.user_id, adds).await;
+        Ok(sorted(lists.map_err(query_err)?))
+    }
+
+    async fn create_sets(&self, ctx: &Context<'_>, sets: Vec<CreateSetInput>) -> Result<Vec<Set>> {
+        let store = store(ctx)?;
+        let adds = sets.into_iter().map(Into::into).collect();
+        let sets = insert_sets(store.db.clone(), store.user_id, adds).await;
+        Ok(sorted(sets.map_err(query_err)?))
+    }
+
+    async fn create_to_dos(
+        &self,
+        ctx: &Context<'_>,
+        to_dos: Vec<CreateToDoInput>,
+    ) -> Result<Vec<ToDo>> {
+        let store = store(ctx)?;
+        let adds = to_dos.into_iter().map(Into::into).collect();
+        let todos = insert_todos(store.db.clone(), store.user_id, adds).await;
+        Ok(sorted(todos.map_err(query_err)?))
+    }
+
+    async fn update_lists(
+        &self,
+        ctx: &Context<'_>,
+        lists: Vec<UpdateListInput>,
+    ) -> Result<Vec<List>> {
+        let store = store(ctx)?;
+        let mods = lists.into_iter().map(Into::into).collect();
+        let lists = update_lists(store.db.clone(), store.user_id, mods).await;
+        Ok(sorted(lists.map_err(query_err)?))
+    }
+
+    async fn update_sets(&self, ctx: &Context<'_>, sets: Vec<UpdateSetInput>) -> Result<Vec<Set>> {
+        let store = store(ctx)?;
+        let mods = sets.into_iter().map(Into::into).collect();
+        let sets = update_sets(store.db.clone(), store.user_id, mods).await;
+        Ok(sorted(sets.map_err(query_err)?))
+    }
+
+    async fn update_to_dos(
+        &self,
+        ctx: &Context<'_>,
+        to_dos: Vec<UpdateToDoInput>,
+    ) -> Result<Vec<ToDo>> {
+        let store = store(ctx)?;
+        let mods = to_dos.into_iter().map(Into::into).collect();
+        let todos = update_todos(store.db.clone(), store.user_id, mods).await;
+        Ok(sorted(todos.map_err(query_err)?))
+    }
+
+    /// Deletes lists along with everything in them, handing back the ids of the deleted lists.
+    async fn delete_lists(&self, ctx: &Context<'_>, ids: Vec<ListID>) -> Result<Vec<ListID>> {
+        let store = store(ctx)?;
+        let ids = delete_lists(store.db.clone(), store.user_id, HashSet::from_iter(ids)).await;
+        Ok(sorted(ids.map_err(query_err)?))
+    }
+
+    async fn delete_sets(&self, ctx: &Context<'_>, targets: Vec<SetTarget>) -> Result<Vec<SetID>> {
+        let store = store(ctx)?;
+        let targets = targets.into_iter().map(Into::into).collect();
+        let ids = delete_sets(store.db.clone(), store.user_id, targets).await;
+        Ok(sorted(ids.map_err(query_err)?))
+    }
+
+    async fn delete_to_dos(
+        &self,
+        ctx: &Context<'_>,
+        targets: Vec<ToDoTarget>,
+    ) -> Result<Vec<ToDoID>> {
+        let store = store(ctx)?;
+        let targets = targets.into_iter().map(Into::into).collect();
+        let ids = delete_todos(store.db.clone(), store.user_id, targets).await;
+        Ok(sorted(ids.map_err(query_err)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_graphql::Value;
+    use serde_json::{Value as JsonValue, json};
+
+    use crate::db::sqlx::setup_test_db;
+
+    use super::{loaders::ListSets, *};
+
+    async fn run(db: &Data<Pool<Sqlite>>, user_id: UserID, query: &str) -> JsonValue {
+        let request = Request::new(query).data(Store::loader(db.clone(), user_id));
+        let response = new_schema().execute(request).await;
+        assert_eq!(response.errors, Vec::new());
+        response.data.into_json().unwrap()
+    }
+
+    #[actix_web::test]
+    async fn mutations_are_told_from_queries() {
+        assert!(!is_mutation(&mut Request::new("{ lists { title } }")));
+        assert!(is_mutation(&mut Request::new(
+            "mutation { deleteLists(ids: []) }"
+        )));
+        assert!(!is_mutation(&mut Request::new("not graphql")));
+
+        let both = "query Read { lists { title } } mutation Write { deleteLists(ids: []) }";
+        assert!(!is_mutation(&mut Request::new(both).operation_name("Read")));
+        assert!(is_mutation(&mut Request::new(both).operation_name("Write")));
+    }
+
+    #[actix_web::test]
+    async fn queries_walk_down_lists_sets_and_to_dos() {
+        let db = setup_test_db().await;
+
+        let data = run(
+            &db,
+            UserID(1),
+            "{ lists { title
+                 sets { title toDos { title } sets { title toDos { title } sets { title } } }
+                 toDos { title set { title } } } }",
+        )
+        .await;
+        assert_eq!(
+            data,
+            json!({ "lists": [
+                {
+                    "title": "Chores",
+                    "sets": [{
+                        "title": "Clean Bath Room",
+                        "toDos": [{ "title": "Scrub Toilet Bowl" }, { "title": "Mop Floor" }],
+                        "sets": [{
+                            "title": "Clean Tub",
+                            "toDos": [{ "title": "Scrub Tub" }],
+                            "sets": [],
+                        }],
+                    }],
+                    "toDos": [{ "title": "Sweep Floor", "set": null }],
+                },
+                { "title": "Projects", "sets": [{ "title": "Garden", "toDos": [], "sets": [] }], "toDos": [] },
+            ]})
+        );
+
+        // Walking back up from a to do goes through the same loaders.
+        let query = format!(
+            "{{ toDo(id: \"{}\") {{ set {{ title parent {{ title }} list {{ title }} }} }} }}",
+            ToDoID(4).public()
+        );
+        assert_eq!(
+            run(&db, UserID(1), &query).await,
+            json!({ "toDo": { "set": {
+                "title": "Clean Tub",
+                "parent": { "title": "Clean Bath Room" },
+                "list": { "title": "Chores" },
+            }}})
+        );
+
+        // Bob can't see any of Alice's lists.
+        assert_eq!(run(&db, UserID(2), &query).await, json!({ "toDo": null }));
+        assert_eq!(
+            run(&db, UserID(2), "{ lists { title } }").await,
+            json!({ "lists": [] })
+        );
+    }
+
+    #[actix_web::test]
+    async fn loaders_group_a_batch_by_key() {
+        let db = setup_test_db().await;
+        let loader = Store::loader(db, UserID(1));
+
+        let sets = loader
+            .load_many([
+                ListSets(ListID(1)),
+                ListSets(ListID(2)),
+                ListSets(ListID(9)),
+            ])
+            .await
+            .unwrap();
+        let titles = |key| {
+            sets[&key]
+                .iter()
+                .map(|set| set.title.as_str())
+                .collect::<Vec<&str>>()
+        };
+        assert_eq!(titles(ListSets(ListID(1))), vec!["Clean Bath Room"]);
+        assert_eq!(titles(ListSets(ListID(2))), vec!["Garden"]);
+        assert!(titles(ListSets(ListID(9))).is_empty());
+    }
+
+    #[actix_web::test]
+    async fn mutations_mirror_the_rest_writes() {
+        let db = setup_test_db().await;
+
+        let created = run(
+            &db,
+            UserID(1),
+            &format!(
+                "mutation {{ createToDos(toDos: [{{ listId: \"{}\", title: \"Wipe Mirror\", priority: HIGH, notes: \"Streak free\" }}]) {{ id title priority notes list {{ title }} }} }}",
+                ListID(1).public()
+            ),
+        )
+        .await;
+        let todo = &created["createToDos"][0];
+        assert_eq!(todo["title"], "Wipe Mirror");
+        assert_eq!(todo["priority"], "HIGH");
+        assert_eq!(todo["list"]["title"], "Chores");
+        let id = todo["id"].as_str().unwrap().to_string();
+
+        let updated = run(
+            &db,
+            UserID(1),
+            &format!(
+                "mutation {{ updateToDos(toDos: [{{ target: {{ toDo: \"{}\" }}, complete: true, notes: null }}]) {{ complete notes }} }}",
+                id
+            ),
+        )
+        .await;
+        assert_eq!(
+            updated,
+            json!({ "updateToDos": [{ "complete": true, "notes": null }] })
+        );
+
+        let deleted = run(
+            &db,
+            UserID(1),
+            &format!(
+                "mutation {{ deleteToDos(targets: [{{ toDo: \"{}\" }}]) }}",
+                id
+            ),
+        )
+        .await;
+        assert_eq!(deleted, json!({ "deleteToDos": [id] }));
+
+        // Database errors come back the way the REST routes word them.
+        let request = Request::new("mutation { createLists(lists: []) { id } }")
+            .data(Store::loader(db.clone(), UserID(1)));
+        let response = new_schema().execute(request).await;
+        assert_eq!(response.data, Value::Null);
+        assert!(response.errors[0].message.starts_with("Bad Request: "));
+    }
+
+    #[actix_web::test]
+    async fn queries_nesting_too_deep_are_refused() {
+        let db = setup_test_db().await;
+
+        // Walking back and forth between a list and its sets goes past the limit quickly.
+        let query = format!(
+            "{{ lists {} }}",
+            "{ sets { list ".repeat(MAX_DEPTH / 2) + "{ title }" + &" } }".repeat(MAX_DEPTH / 2)
+        );
+        let request = Request::new(query).data(Store::loader(db.clone(), UserID(1)));
+        let response = new_schema().execute(request).await;
+        assert_eq!(response.data, Value::Null);
+        assert_eq!(response.errors[0].message, "Query is nested too deep.");
+
+        let fields = vec!["title"; MAX_COMPLEXITY].join(" ");
+        let request = Request::new(format!("{{ lists {{ {} }} }}", fields))
+            .data(Store::loader(db, UserID(1)));
+        let response = new_schema().execute(request).await;
+        assert_eq!(response.errors[0].message, "Query is too complex.");
+    }
+}
@@ -0,0 +1,391 @@
+use std::{collections::BTreeSet, hash::Hash};
+
+use async_graphql::{
+    Context, Enum, InputObject, MaybeUndefined, Object, OneofObject, Result,
+    dataloader::{DataLoader, Loader},
+};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    api::types::JsonError,
+    types::{
+        CreateList, CreateSet, CreateToDo, List, ListID, Priority, Set, SetID, SetNumber,
+        SetQueryTarget, TagID, ToDo, ToDoID, ToDoNumber, ToDoQueryTarget, UpdateList, UpdateSet,
+        UpdateToDo,
+    },
+};
+
+use super::loaders::{ListSets, ListToDos, SetSets, SetToDos, Store};
+
+async_graphql::scalar!(ListID, "ListID", "A list's public id, like \"list_…\".");
+async_graphql::scalar!(SetID, "SetID", "A set's public id, like \"set_…\".");
+async_graphql::scalar!(ToDoID, "ToDoID", "A to do's public id, like \"todo_…\".");
+async_graphql::scalar!(TagID, "TagID", "A tag's public id, like \"tag_…\".");
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Priority", remote = "crate::types::Priority")]
+pub enum PriorityValue {
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+/// Loads one entry through the request's [`Store`].
+pub(super) async fn load_one<K, V>(ctx: &Context<'_>, key: K) -> Result<Option<V>>
+where
+    K: Send + Sync + Hash + Eq + Clone + 'static,
+    Store: Loader<K, Value = V, Error = JsonError>,
+{
+    Ok(ctx.data::<DataLoader<Store>>()?.load_one(key).await?)
+}
+
+/// Loads the entries under one key, like the sets of a list, which may well be none.
+async fn load_all<K, V>(ctx: &Context<'_>, key: K) -> Result<Vec<V>>
+where
+    K: Send + Sync + Hash + Eq + Clone + 'static,
+    Store: Loader<K, Value = Vec<V>, Error = JsonError>,
+{
+    Ok(load_one(ctx, key).await?.unwrap_or_default())
+}
+
+#[Object]
+impl List {
+    async fn id(&self) -> ListID {
+        self.id
+    }
+
+    async fn title(&self) -> &str {
+        &self.title
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    /// The sets sitting directly in the list. Nested sets are reached through their parents.
+    async fn sets(&self, ctx: &Context<'_>) -> Result<Vec<Set>> {
+        load_all(ctx, ListSets(self.id)).await
+    }
+
+    /// The to dos that aren't in any set.
+    async fn to_dos(&self, ctx: &Context<'_>) -> Result<Vec<ToDo>> {
+        load_all(ctx, ListToDos(self.id)).await
+    }
+}
+
+#[Object]
+impl Set {
+    async fn id(&self) -> SetID {
+        self.id
+    }
+
+    /// The set's number within its list.
+    async fn sid(&self) -> SetNumber {
+        self.sid
+    }
+
+    async fn title(&self) -> &str {
+        &self.title
+    }
+
+    async fn position(&self) -> f64 {
+        self.position
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    async fn list(&self, ctx: &Context<'_>) -> Result<Option<List>> {
+        load_one(ctx, self.list_id).await
+    }
+
+    async fn parent(&self, ctx: &Context<'_>) -> Result<Option<Set>> {
+        match self.parent_id {
+            Some(parent_id) => load_one(ctx, parent_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// The sets nested directly inside this one.
+    async fn sets(&self, ctx: &Context<'_>) -> Result<Vec<Set>> {
+        load_all(ctx, SetSets(self.id)).await
+    }
+
+    /// The to dos directly inside this set, leaving out those of nested sets.
+    async fn to_dos(&self, ctx: &Context<'_>) -> Result<Vec<ToDo>> {
+        load_all(ctx, SetToDos(self.id)).await
+    }
+}
+
+#[Object]
+impl ToDo {
+    async fn id(&self) -> ToDoID {
+        self.id
+    }
+
+    /// The to do's number within its list.
+    async fn tdid(&self) -> ToDoNumber {
+        self.tdid
+    }
+
+    async fn title(&self) -> &str {
+        &self.title
+    }
+
+    async fn complete(&self) -> bool {
+        self.complete
+    }
+
+    async fn due_date(&self) -> Option<DateTime<Utc>> {
+        self.due_date
+    }
+
+    async fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    async fn priority(&self) -> PriorityValue {
+        self.priority.into()
+    }
+
+    async fn position(&self) -> f64 {
+        self.position
+    }
+
+    async fn tags(&self) -> Vec<TagID> {
+        self.tags.iter().copied().collect()
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    async fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.completed_at
+    }
+
+    async fn auto_complete(&self) -> bool {
+        self.auto_complete
+    }
+
+    async fn list(&self, ctx: &Context<'_>) -> Result<Option<List>> {
+        load_one(ctx, self.list_id).await
+    }
+
+    async fn set(&self, ctx: &Context<'_>) -> Result<Option<Set>> {
+        match self.set_id {
+            Some(set_id) => load_one(ctx, set_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// The to dos that have to be complete before this one can be worked on.
+    async fn blocked_by(&self, ctx: &Context<'_>) -> Result<Vec<ToDo>> {
+        let blockers = ctx
+            .data::<DataLoader<Store>>()?
+            .load_many(self.blocked_by.iter().copied())
+            .await?;
+        Ok(self
+            .blocked_by
+            .iter()
+            .filter_map(|id| blockers.get(id).cloned())
+            .collect())
+    }
+}
+
+/// Which sets to read or delete, as in the `target` of a REST body.
+#[derive(OneofObject, Debug)]
+pub enum SetTarget {
+    List(ListID),
+    Set(SetID),
+    /// A set along with every set nested inside it, however deep.
+    Subtree(SetID),
+}
+
+impl From<SetTarget> for SetQueryTarget {
+    fn from(target: SetTarget) -> Self {
+        match target {
+            SetTarget::List(id) => SetQueryTarget::List(id),
+            SetTarget::Set(id) => SetQueryTarget::Set(id),
+            SetTarget::Subtree(id) => SetQueryTarget::Subtree(id),
+        }
+    }
+}
+
+/// Which to dos to read, update or delete, as in the `target` of a REST body.
+#[derive(OneofObject, Debug)]
+pub enum ToDoTarget {
+    List(ListID),
+    Set(SetID),
+    ToDo(ToDoID),
+    /// The to dos of a set and of every set nested inside it, however deep.
+    Subtree(SetID),
+    /// Every to do carrying a tag, whatever list it's in.
+    Tag(TagID),
+}
+
+impl From<ToDoTarget> for ToDoQueryTarget {
+    fn from(target: ToDoTarget) -> Self {
+        match target {
+            ToDoTarget::List(id) => ToDoQueryTarget::List(id),
+            ToDoTarget::Set(id) => ToDoQueryTarget::Set(id),
+            ToDoTarget::ToDo(id) => ToDoQueryTarget::ToDo(id),
+            ToDoTarget::Subtree(id) => ToDoQueryTarget::Subtree(id),
+            ToDoTarget::Tag(id) => ToDoQueryTarget::Tag(id),
+        }
+    }
+}
+
+#[derive(InputObject, Debug)]
+pub struct CreateListInput {
+    pub title: String,
+}
+
+impl From<CreateListInput> for CreateList {
+    fn from(input: CreateListInput) -> Self {
+        CreateList { title: input.title }
+    }
+}
+
+#[derive(InputObject, Debug)]
+pub struct CreateSetInput {
+    pub list_id: ListID,
+    /// The set to nest this one in, which must belong to the same list.
+    pub parent_id: Option<SetID>,
+    pub title: String,
+}
+
+impl From<CreateSetInput> for CreateSet {
+    fn from(input: CreateSetInput) -> Self {
+        CreateSet {
+            list_id: input.list_id,
+            parent_id: input.parent_id,
+            title: input.title,
+        }
+    }
+}
+
+#[derive(InputObject, Debug)]
+pub struct CreateToDoInput {
+    pub list_id: ListID,
+    pub set_id: Option<SetID>,
+    pub title: String,
+    pub complete: Option<bool>,
+    pub due_date: Option<DateTime<Utc>>,
+    #[graphql(default)]
+    pub tags: Vec<TagID>,
+    #[graphql(default)]
+    pub blocked_by: Vec<ToDoID>,
+    pub notes: Option<String>,
+    pub priority: Option<PriorityValue>,
+    pub auto_complete: Option<bool>,
+}
+
+impl From<CreateToDoInput> for CreateToDo {
+    fn from(input: CreateToDoInput) -> Self {
+        CreateToDo {
+            list_id: input.list_id,
+            set_id: input.set_id,
+            title: input.title,
+            complete: input.complete,
+            due_date: input.due_date,
+            tags: input.tags.into_iter().collect(),
+            blocked_by: input.blocked_by.into_iter().collect(),
+            notes: input.notes,
+            priority: input.priority.map(Priority::from),
+            auto_complete: input.auto_complete,
+        }
+    }
+}
+
+#[derive(InputObject, Debug)]
+pub struct UpdateListInput {
+    pub list_id: ListID,
+    pub title: String,
+}
+
+impl From<UpdateListInput> for UpdateList {
+    fn from(input: UpdateListInput) -> Self {
+        UpdateList {
+            list_id: input.list_id,
+            title: input.title,
+        }
+    }
+}
+
+#[derive(InputObject, Debug)]
+pub struct UpdateSetInput {
+    pub target: SetTarget,
+    pub list_id: Option<ListID>,
+    /// `null` moves the set to the top level of its list, while leaving the field out keeps
+    /// the current parent.
+    pub parent_id: MaybeUndefined<SetID>,
+    pub title: Option<String>,
+}
+
+impl From<UpdateSetInput> for UpdateSet {
+    fn from(input: UpdateSetInput) -> Self {
+        UpdateSet {
+            target: input.target.into(),
+            list_id: input.list_id,
+            parent_id: input.parent_id.as_opt_ref().map(|id| id.copied()),
+            title: input.title,
+        }
+    }
+}
+
+#[derive(InputObject, Debug)]
+pub struct UpdateToDoInput {
+    pub target: ToDoTarget,
+    pub set_id: Option<SetID>,
+    pub list_id: Option<ListID>,
+    pub title: Option<String>,
+    pub complete: Option<bool>,
+    pub due_date: Option<DateTime<Utc>>,
+    /// `null` clears the notes, while leaving the field out keeps them.
+    pub notes: MaybeUndefined<String>,
+    pub priority: Option<PriorityValue>,
+    pub auto_complete: Option<bool>,
+    #[graphql(default)]
+    pub attach_tags: Vec<TagID>,
+    #[graphql(default)]
+    pub detach_tags: Vec<TagID>,
+    #[graphql(default)]
+    pub add_blockers: Vec<ToDoID>,
+    #[graphql(default)]
+    pub remove_blockers: Vec<ToDoID>,
+}
+
+impl From<UpdateToDoInput> for UpdateToDo {
+    fn from(input: UpdateToDoInput) -> Self {
+        UpdateToDo {
+            target: input.target.into(),
+            set_id: input.set_id,
+            list_id: input.list_id,
+            title: input.title,
+            complete: input.complete,
+            due_date: input.due_date,
+            notes: input.notes.as_opt_ref().map(|notes| notes.cloned()),
+            priority: input.priority.map(Priority::from),
+            auto_complete: input.auto_complete,
+            attach_tags: BTreeSet::from_iter(input.attach_tags),
+            detach_tags: BTreeSet::from_iter(input.detach_tags),
+            add_blockers: BTreeSet::from_iter(input.add_blockers),
+            remove_blockers: BTreeSet::from_iter(input.remove_blockers),
+        }
+    }
+}
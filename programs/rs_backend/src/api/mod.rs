@@ -1,5 +1,11 @@
 mod endpoints;
+mod graphql;
+mod openapi;
+mod rpc;
 mod types;
 mod utils;
 
 pub use endpoints::*;
+pub use graphql::new_schema;
+pub use openapi::ApiDoc;
+pub use types::{RateLimitConfig, RateLimiter, authenticate, rate_limit};
@@ -0,0 +1,145 @@
+use utoipa::{
+    Modify, OpenApi,
+    openapi::{
+        self,
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+
+use crate::api::{
+    endpoints::*,
+    types::{ErrorBody, SESSION_COOKIE},
+};
+
+/// The OpenAPI document of the REST routes, built from their `#[utoipa::path]` annotations and
+/// the schemas of the types they take and return. It's served at `/api/openapi.json`, next to
+/// Swagger UI at `/api/docs/`, and checked into `openapi.json` so changes to it show up in review.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "To Do API",
+        description = "Lists hold sets and to dos, and sets can be nested. Routes take and return \
+            JSON, or MessagePack or CBOR when picked by `Content-Type` and `Accept`, and the list, \
+            set and to do routes also take the messages of `schema.proto` as protobuf. Reads \
+            without a body read everything, and the list, set and to do reads also take their \
+            targets in the query string, like `?list=1&set=4`. Reads of the entities send an \
+            `ETag`, and a `Last-Modified` once the second of the last change is over, and answer \
+            `If-None-Match` and `If-Modified-Since` with `304 Not Modified` when nothing changed. \
+            Errors come back as an `ErrorBody`."
+    ),
+    paths(
+        create_user,
+        create_session,
+        delete_current_session,
+        read_current_user,
+        create_lists,
+        read_lists,
+        update_lists,
+        delete_lists,
+        read_shared_lists,
+        create_sets,
+        read_sets,
+        update_sets,
+        move_sets,
+        delete_sets,
+        create_to_dos,
+        read_to_dos,
+        update_to_dos,
+        move_to_dos,
+        delete_to_dos,
+        create_tags,
+        read_tags,
+        update_tags,
+        delete_tags,
+        create_checklist_items,
+        read_checklist_items,
+        update_checklist_items,
+        move_checklist_items,
+        delete_checklist_items,
+        create_memberships,
+        read_memberships,
+        update_memberships,
+        delete_memberships,
+        create_share_links,
+        read_share_links,
+        delete_share_links,
+        read_shared_list,
+        read_stats,
+        export_database,
+        import_database,
+        export_to_dos_csv,
+        import_to_dos_csv,
+        export_markdown,
+        import_markdown_lists,
+        export_taskwarrior,
+        import_taskwarrior,
+    ),
+    components(schemas(ErrorBody)),
+    modifiers(&Sessions),
+    security(("token" = []), ("cookie" = []))
+)]
+pub struct ApiDoc;
+
+/// Adds the two ways a session can be sent, which every route but the open ones requires.
+struct Sessions;
+
+impl Modify for Sessions {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "cookie",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(SESSION_COOKIE))),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, path::Path};
+
+    use actix_web::{
+        App,
+        test::{TestRequest, call_and_read_body_json, call_service, init_service},
+    };
+    use utoipa_swagger_ui::SwaggerUi;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn serves_the_document_and_swagger_ui() {
+        let app = init_service(App::new().service(
+            SwaggerUi::new("/api/docs/{_:.*}").url("/api/openapi.json", ApiDoc::openapi()),
+        ))
+        .await;
+
+        let req = TestRequest::get().uri("/api/openapi.json").to_request();
+        let document: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(document["openapi"], "3.1.0");
+        assert!(document["paths"]["/api/to_dos"]["get"].is_object());
+
+        let req = TestRequest::get().uri("/api/docs/").to_request();
+        let resp = call_service(&app, req).await;
+        assert!(resp.status().is_success());
+    }
+
+    /// The checked in document has to match the one the code builds. Run the tests with
+    /// `UPDATE_OPENAPI=1` to write it again after changing a route or a type.
+    #[test]
+    fn checked_in_document_is_up_to_date() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+        let built = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+
+        if env::var_os("UPDATE_OPENAPI").is_some() {
+            fs::write(&path, &built).unwrap();
+        }
+        let checked_in = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            checked_in == built,
+            "openapi.json is out of date, run `UPDATE_OPENAPI=1 cargo test` to write it again"
+        );
+    }
+}
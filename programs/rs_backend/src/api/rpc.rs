@@ -0,0 +1,531 @@
+use actix_web::{error::JsonPayloadError, web::Data};
+use len_trait::Len;
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use sqlx::{Error as SQLXError, Pool, Sqlite};
+
+use crate::{
+    api::{
+        types::{JsonError, MaybeBody},
+        utils::{query_all_or_some, query_some},
+    },
+    db::sqlx::{
+        delete_lists, delete_sets, delete_todos, insert_lists, insert_sets, insert_todos,
+        query_all_lists, query_all_sets, query_all_todos, query_lists, query_sets, query_todos,
+        update_lists, update_sets, update_todos,
+    },
+    types::{ToDoReadOptions, UserID},
+};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+// Codes in the range JSON-RPC leaves to servers, for errors the REST routes have a status for.
+pub const UNAUTHORIZED: i64 = -32001;
+pub const NOT_FOUND: i64 = -32002;
+pub const TOO_MANY_REQUESTS: i64 = -32003;
+pub const PAYLOAD_TOO_LARGE: i64 = -32004;
+pub const UNSUPPORTED_MEDIA_TYPE: i64 = -32005;
+
+/// The most calls a batch can hold. It matches the default burst of writes, which a batch is
+/// charged for call by call.
+pub const MAX_BATCH: usize = 30;
+
+/// One call of a JSON-RPC request.
+#[derive(Deserialize, Debug)]
+struct Call {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Option<Value>,
+    /// `None` for a notification, which gets no response, and `Some(Value::Null)` for an
+    /// explicit `"id": null`, which does.
+    #[serde(default, deserialize_with = "present")]
+    id: Option<Value>,
+}
+
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+/// Errors keep the message the REST routes would send, with their status code as `data`.
+impl From<JsonError> for RpcError {
+    fn from(err: JsonError) -> Self {
+        use actix_web::ResponseError;
+
+        let code = match err {
+            JsonError::BadRequest(_) => INVALID_PARAMS,
+            JsonError::ServerError(_) | JsonError::Unknown(_) => INTERNAL_ERROR,
+            JsonError::Unauthorized(_) => UNAUTHORIZED,
+            JsonError::NotFound(_) => NOT_FOUND,
+            JsonError::TooManyRequests(_) => TOO_MANY_REQUESTS,
+            JsonError::PayloadTooLarge(_) => PAYLOAD_TOO_LARGE,
+            JsonError::UnsupportedMediaType(_) => UNSUPPORTED_MEDIA_TYPE,
+        };
+        RpcError {
+            code,
+            message: err.to_string(),
+            data: Some(json!({ "status": err.status_code().as_u16() })),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Result(Value),
+    Error(RpcError),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Response {
+    pub jsonrpc: &'static str,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+    pub id: Value,
+}
+
+impl Response {
+    fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        Response {
+            jsonrpc: "2.0",
+            outcome: match outcome {
+                Ok(result) => Outcome::Result(result),
+                Err(err) => Outcome::Error(err),
+            },
+            id,
+        }
+    }
+}
+
+/// What goes back for a request, shaped like the request was: one response for one call and an
+/// array of them for a batch.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Reply {
+    Single(Response),
+    Batch(Vec<Response>),
+}
+
+/// Runs a JSON-RPC 2.0 request, one call or a batch of them, as `user_id`. The calls of a batch
+/// run in order, so later calls see what earlier ones wrote. Before a batch runs, `charge` is
+/// given the number of calls past the first, which the request itself already paid for, and
+/// nothing runs if it fails. `None` means there's nothing to send back, as when every call was a
+/// notification.
+pub async fn handle(
+    db: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    body: &[u8],
+    charge: impl FnOnce(usize) -> Result<(), JsonError>,
+) -> Option<Reply> {
+    let request = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(err) => {
+            let err = RpcError::new(PARSE_ERROR, format!("Parse error: {}", err));
+            return Some(Reply::Single(Response::new(Value::Null, Err(err))));
+        }
+    };
+
+    match request {
+        Value::Array(calls) if calls.is_empty() => {
+            let err = RpcError::new(INVALID_REQUEST, "Invalid Request: empty batch");
+            Some(Reply::Single(Response::new(Value::Null, Err(err))))
+        }
+        Value::Array(calls) if calls.len() > MAX_BATCH => {
+            let err = RpcError::new(
+                INVALID_REQUEST,
+                format!(
+                    "Invalid Request: a batch can't hold more than {} calls",
+                    MAX_BATCH
+                ),
+            );
+            Some(Reply::Single(Response::new(Value::Null, Err(err))))
+        }
+        Value::Array(calls) => {
+            if let Err(err) = charge(calls.len() - 1) {
+                return Some(refuse(err));
+            }
+            let mut responses = Vec::new();
+            for call in calls {
+                responses.extend(handle_call(&db, user_id, call).await);
+            }
+            (!responses.is_empty()).then_some(Reply::Batch(responses))
+        }
+        call => handle_call(&db, user_id, call).await.map(Reply::Single),
+    }
+}
+
+/// The reply to a request that couldn't be read or run at all.
+pub fn refuse(err: JsonError) -> Reply {
+    Reply::Single(Response::new(Value::Null, Err(err.into())))
+}
+
+async fn handle_call(db: &Data<Pool<Sqlite>>, user_id: UserID, call: Value) -> Option<Response> {
+    let call: Call = match serde_json::from_value(call) {
+        Ok(call) => call,
+        Err(err) => {
+            let err = RpcError::new(INVALID_REQUEST, format!("Invalid Request: {}", err));
+            return Some(Response::new(Value::Null, Err(err)));
+        }
+    };
+    if call.jsonrpc != "2.0" {
+        let err = RpcError::new(
+            INVALID_REQUEST,
+            "Invalid Request: `jsonrpc` must be \"2.0\"",
+        );
+        return Some(Response::new(Value::Null, Err(err)));
+    }
+    if let Some(id) = call
+        .id
+        .as_ref()
+        .filter(|id| id.is_object() || id.is_array())
+    {
+        let err = RpcError::new(
+            INVALID_REQUEST,
+            format!("Invalid Request: {} isn't a string or number id", id),
+        );
+        return Some(Response::new(Value::Null, Err(err)));
+    }
+
+    let outcome = dispatch(db.clone(), user_id, &call.method, call.params).await;
+    call.id.map(|id| Response::new(id, outcome))
+}
+
+/// Calls the method's storage function with the same body its REST route takes.
+async fn dispatch(
+    db: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    method: &str,
+    params: Option<Value>,
+) -> Result<Value, RpcError> {
+    let options = ToDoReadOptions::default();
+    match method {
+        "createLists" => some(params, db, |db, adds| insert_lists(db, user_id, adds)).await,
+        "createSets" => some(params, db, |db, adds| insert_sets(db, user_id, adds)).await,
+        "createToDos" => some(params, db, |db, adds| insert_todos(db, user_id, adds)).await,
+        "readLists" => {
+            all_or_some(
+                params,
+                db,
+                |db| query_all_lists(db, user_id),
+                |db, ids| query_lists(db, user_id, ids),
+            )
+            .await
+        }
+        "readSets" => {
+            all_or_some(
+                params,
+                db,
+                |db| query_all_sets(db, user_id),
+                |db, targets| query_sets(db, user_id, targets),
+            )
+            .await
+        }
+        "readToDos" => {
+            all_or_some(
+                params,
+                db,
+                |db| query_all_todos(db, user_id, options.clone()),
+                |db, targets| query_todos(db, user_id, targets, options.clone()),
+            )
+            .await
+        }
+        "updateLists" => some(params, db, |db, mods| update_lists(db, user_id, mods)).await,
+        "updateSets" => some(params, db, |db, mods| update_sets(db, user_id, mods)).await,
+        "updateToDos" => some(params, db, |db, mods| update_todos(db, user_id, mods)).await,
+        "deleteLists" => some(params, db, |db, ids| delete_lists(db, user_id, ids)).await,
+        "deleteSets" => some(params, db, |db, targets| delete_sets(db, user_id, targets)).await,
+        "deleteToDos" => some(params, db, |db, targets| delete_todos(db, user_id, targets)).await,
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+/// Reads `params` like a request body, where leaving them out or passing `[]` is no body.
+fn body<T: DeserializeOwned>(params: Option<Value>) -> MaybeBody<T> {
+    match params {
+        None => MaybeBody::Empty,
+        Some(Value::Array(params)) if params.is_empty() => MaybeBody::Empty,
+        Some(params) => match serde_json::from_value(params) {
+            Ok(params) => MaybeBody::Valid(params),
+            Err(err) => MaybeBody::Invalid(JsonPayloadError::Deserialize(err)),
+        },
+    }
+}
+
+fn result<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|err| {
+        JsonError::ServerError(format!("Error encoding the response: {}", err)).into()
+    })
+}
+
+async fn some<In, Out, Q, Fut>(
+    params: Option<Value>,
+    db: Data<Pool<Sqlite>>,
+    query: Q,
+) -> Result<Value, RpcError>
+where
+    In: Len + DeserializeOwned,
+    Out: Serialize,
+    Fut: Future<Output = Result<Out, SQLXError>>,
+    Q: Fn(Data<Pool<Sqlite>>, In) -> Fut,
+{
+    result(query_some(body(params), db, query).await?.0)
+}
+
+async fn all_or_some<In, Out, Qall, Qsome, Fall, Fsome>(
+    params: Option<Value>,
+    db: Data<Pool<Sqlite>>,
+    query_all: Qall,
+    query_some: Qsome,
+) -> Result<Value, RpcError>
+where
+    In: Len + DeserializeOwned,
+    Out: Serialize,
+    Fall: Future<Output = Result<Out, SQLXError>>,
+    Fsome: Future<Output = Result<Out, SQLXError>>,
+    Qall: Fn(Data<Pool<Sqlite>>) -> Fall,
+    Qsome: Fn(Data<Pool<Sqlite>>, In) -> Fsome,
+{
+    result(
+        query_all_or_some(body(params), db, query_all, query_some)
+            .await?
+            .0,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{Value, json};
+
+    use crate::{
+        db::sqlx::setup_test_db,
+        types::{ListID, UserID},
+    };
+
+    use super::*;
+
+    async fn call(db: &Data<Pool<Sqlite>>, request: Value) -> Option<Value> {
+        let body = serde_json::to_vec(&request).unwrap();
+        handle(db.clone(), UserID(1), &body, |_| Ok(()))
+            .await
+            .map(|reply| serde_json::to_value(reply).unwrap())
+    }
+
+    fn code(response: &Value) -> i64 {
+        response["error"]["code"].as_i64().unwrap()
+    }
+
+    #[actix_web::test]
+    async fn calls_run_against_the_same_storage_as_rest() {
+        let db = setup_test_db().await;
+
+        let response = call(
+            &db,
+            json!({ "jsonrpc": "2.0", "method": "readLists", "params": [ListID(2).public()], "id": 1 }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["jsonrpc"], "2.0");
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"][0]["title"], "Projects");
+
+        // No params reads everything, like a read route without a body.
+        let response = call(
+            &db,
+            json!({ "jsonrpc": "2.0", "method": "readToDos", "id": "all" }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["id"], "all");
+        assert_eq!(response["result"].as_array().unwrap().len(), 4);
+
+        let response = call(
+            &db,
+            json!({ "jsonrpc": "2.0", "method": "createLists", "params": [{ "title": "Errands" }], "id": null }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["result"][0]["title"], "Errands");
+    }
+
+    #[actix_web::test]
+    async fn batches_answer_every_call_but_notifications() {
+        let db = setup_test_db().await;
+
+        let response = call(
+            &db,
+            json!([
+                { "jsonrpc": "2.0", "method": "createLists", "params": [{ "title": "Errands" }] },
+                { "jsonrpc": "2.0", "method": "readLists", "id": 1 },
+                { "jsonrpc": "2.0", "method": "readLists", "params": ["not an id"], "id": 2 },
+                { "jsonrpc": "2.0", "method": "createLists", "id": 3 },
+                { "jsonrpc": "2.0", "method": "archiveLists", "id": 4 },
+                { "jsonrpc": "1.0", "method": "readLists", "id": 5 },
+                7,
+            ]),
+        )
+        .await
+        .unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 6);
+
+        // The notification ran before the read, even though nothing came back for it.
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"].as_array().unwrap().len(), 3);
+
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(code(&responses[1]), INVALID_PARAMS);
+        assert_eq!(responses[1]["error"]["data"], json!({ "status": 400 }));
+
+        // Writes can't go without params, just like their routes can't go without a body.
+        assert_eq!(responses[2]["id"], 3);
+        assert_eq!(code(&responses[2]), INVALID_PARAMS);
+
+        assert_eq!(responses[3]["id"], 4);
+        assert_eq!(code(&responses[3]), METHOD_NOT_FOUND);
+
+        assert_eq!(responses[4]["id"], Value::Null);
+        assert_eq!(code(&responses[4]), INVALID_REQUEST);
+        assert_eq!(responses[5]["id"], Value::Null);
+        assert_eq!(code(&responses[5]), INVALID_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn malformed_requests_get_an_error_with_no_id() {
+        let db = setup_test_db().await;
+
+        let reply = handle(
+            db.clone(),
+            UserID(1),
+            b"{\"jsonrpc\": \"2.0\", \"method\"",
+            |_| Ok(()),
+        )
+        .await
+        .map(|reply| serde_json::to_value(reply).unwrap())
+        .unwrap();
+        assert_eq!(code(&reply), PARSE_ERROR);
+        assert_eq!(reply["id"], Value::Null);
+
+        let reply = call(&db, json!([])).await.unwrap();
+        assert_eq!(code(&reply), INVALID_REQUEST);
+
+        let reply = call(
+            &db,
+            json!({ "jsonrpc": "2.0", "method": "readLists", "id": { "nested": true } }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(code(&reply), INVALID_REQUEST);
+        assert_eq!(reply["id"], Value::Null);
+
+        // Nothing at all comes back for a batch of notifications.
+        let reply = call(
+            &db,
+            json!([
+                { "jsonrpc": "2.0", "method": "readLists" },
+                { "jsonrpc": "2.0", "method": "deleteLists", "params": [1] },
+            ]),
+        )
+        .await;
+        assert_eq!(reply, None);
+        let response = call(
+            &db,
+            json!({ "jsonrpc": "2.0", "method": "readLists", "id": 1 }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["result"][0]["title"], "Projects");
+    }
+
+    #[actix_web::test]
+    async fn batches_are_capped_and_charged_per_call() {
+        let db = setup_test_db().await;
+        let read = json!({ "jsonrpc": "2.0", "method": "readLists", "id": 1 });
+
+        let body = serde_json::to_vec(&vec![read.clone(); MAX_BATCH + 1]).unwrap();
+        let reply = handle(db.clone(), UserID(1), &body, |_| {
+            panic!("charged an oversized batch")
+        })
+        .await
+        .map(|reply| serde_json::to_value(reply).unwrap())
+        .unwrap();
+        assert_eq!(code(&reply), INVALID_REQUEST);
+
+        let body = serde_json::to_vec(&vec![read.clone(); 3]).unwrap();
+        let mut charged = None;
+        let reply = handle(db.clone(), UserID(1), &body, |calls| {
+            charged = Some(calls);
+            Err(JsonError::TooManyRequests("x".to_string()))
+        })
+        .await
+        .map(|reply| serde_json::to_value(reply).unwrap())
+        .unwrap();
+        assert_eq!(charged, Some(2));
+        assert_eq!(code(&reply), TOO_MANY_REQUESTS);
+        assert_eq!(reply["id"], Value::Null);
+
+        // A single call was paid for by its request.
+        let body = serde_json::to_vec(&read).unwrap();
+        let reply = handle(db.clone(), UserID(1), &body, |_| {
+            panic!("charged a single call")
+        })
+        .await;
+        assert!(reply.is_some());
+    }
+
+    #[test]
+    fn json_errors_map_to_rpc_codes() {
+        for (err, code, status) in [
+            (JsonError::BadRequest("x".to_string()), INVALID_PARAMS, 400),
+            (JsonError::Unauthorized("x".to_string()), UNAUTHORIZED, 401),
+            (JsonError::NotFound("x".to_string()), NOT_FOUND, 404),
+            (
+                JsonError::PayloadTooLarge("x".to_string()),
+                PAYLOAD_TOO_LARGE,
+                413,
+            ),
+            (
+                JsonError::UnsupportedMediaType("x".to_string()),
+                UNSUPPORTED_MEDIA_TYPE,
+                415,
+            ),
+            (
+                JsonError::TooManyRequests("x".to_string()),
+                TOO_MANY_REQUESTS,
+                429,
+            ),
+            (JsonError::ServerError("x".to_string()), INTERNAL_ERROR, 500),
+            (JsonError::Unknown("x".to_string()), INTERNAL_ERROR, 500),
+        ] {
+            let message = err.to_string();
+            let rpc = RpcError::from(err);
+            assert_eq!(rpc.code, code);
+            assert_eq!(rpc.message, message);
+            assert_eq!(rpc.data, Some(json!({ "status": status })));
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::ops::Deref;
+
+use actix_web::{
+    HttpRequest, HttpResponse, Responder, ResponseError,
+    body::BoxBody,
+    http::header::{self, Accept, Header},
+    mime,
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json as json;
+
+use super::JsonError;
+
+pub const MSGPACK: &str = "application/msgpack";
+pub const CBOR: &str = "application/cbor";
+
+/// The encodings request and response bodies can use. JSON is the default whenever the client
+/// doesn't ask for one of the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl BodyFormat {
+    /// The format of a `Content-Type`, or `None` when it's not one we can decode.
+    pub fn from_mime(mime: &mime::Mime) -> Option<Self> {
+        let subtype = mime.subtype().as_str();
+        let suffix = mime.suffix().map(|suffix| suffix.as_str());
+        match (subtype, suffix) {
+            ("json", _) | (_, Some("json")) => Some(BodyFormat::Json),
+            _ if mime.type_() != mime::APPLICATION => None,
+            ("msgpack" | "x-msgpack" | "vnd.msgpack", _) | (_, Some("msgpack")) => {
+                Some(BodyFormat::MessagePack)
+            }
+            ("cbor", _) | (_, Some("cbor")) => Some(BodyFormat::Cbor),
+            _ => None,
+        }
+    }
+
+    /// The client's most preferred format in `Accept`, falling back to JSON.
+    pub fn accepted(req: &HttpRequest) -> Self {
+        Accept::parse(req)
+            .map(|accept| accept.ranked())
+            .unwrap_or_default()
+            .iter()
+            .find_map(|mime| match (mime.type_(), mime.subtype()) {
+                (mime::STAR, _) | (mime::APPLICATION, mime::STAR) => Some(BodyFormat::Json),
+                _ => BodyFormat::from_mime(mime),
+            })
+            .unwrap_or(BodyFormat::Json)
+    }
+
+    pub fn mime(&self) -> mime::Mime {
+        match self {
+            BodyFormat::Json => mime::APPLICATION_JSON,
+            BodyFormat::MessagePack => MSGPACK.parse().unwrap(),
+            BodyFormat::Cbor => CBOR.parse().unwrap(),
+        }
+    }
+
+    /// Decodes a body. Errors are converted to `serde_json`'s so every format reports them
+    /// through `JsonPayloadError::Deserialize`.
+    pub fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, json::Error> {
+        match self {
+            BodyFormat::Json => json::from_slice(body),
+            BodyFormat::MessagePack => {
+                rmp_serde::from_slice(body).map_err(serde::de::Error::custom)
+            }
+            BodyFormat::Cbor => ciborium::from_reader(body).map_err(serde::de::Error::custom),
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            BodyFormat::Json => json::to_vec(value).map_err(|e| e.to_string()),
+            // Maps keep their field names, so the tagged and defaulted fields read the same as
+            // they do in JSON.
+            BodyFormat::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+            BodyFormat::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(|e| e.to_string())?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Whether a body is an empty array, which requests treat the same as no body.
+    pub fn is_empty_array(&self, body: &[u8]) -> bool {
+        match self {
+            BodyFormat::Json => body
+                .iter()
+                .copied()
+                .filter(|b| !b.is_ascii_whitespace())
+                .eq(*b"[]"),
+            BodyFormat::MessagePack => body == [0x90],
+            // Either a definite or an indefinite length array.
+            BodyFormat::Cbor => body == [0x80] || body == [0x9f, 0xff],
+        }
+    }
+}
+
+/// A response body encoded in the format the client `Accept`s.
+#[derive(Debug)]
+pub struct Body<T>(pub T);
+
+impl<T> Deref for Body<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Serialize> Responder for Body<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let format = BodyFormat::accepted(req);
+        match format.encode(&self.0) {
+            Ok(bytes) => HttpResponse::Ok()
+                .content_type(format.mime())
+                .insert_header((header::VARY, "Accept"))
+                .body(bytes),
+            Err(e) => JsonError::ServerError(format!("Error encoding the response: {}", e))
+                .error_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{App, http::header, test, web};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Entry {
+        name: String,
+        tags: Vec<i64>,
+    }
+
+    async fn entry() -> Body<Entry> {
+        Body(Entry {
+            name: "Alice".to_string(),
+            tags: vec![1, 2],
+        })
+    }
+
+    #[actix_web::test]
+    async fn responds_in_the_accepted_format() {
+        let app = test::init_service(App::new().route("/", web::to(entry))).await;
+        let expected = Entry {
+            name: "Alice".to_string(),
+            tags: vec![1, 2],
+        };
+
+        for (accept, format) in [
+            (None, BodyFormat::Json),
+            (Some("*/*"), BodyFormat::Json),
+            (
+                Some("text/html, application/msgpack;q=0.9"),
+                BodyFormat::MessagePack,
+            ),
+            (
+                Some("application/cbor, application/json;q=0.5"),
+                BodyFormat::Cbor,
+            ),
+            (Some("text/html"), BodyFormat::Json),
+        ] {
+            let mut req = test::TestRequest::get().uri("/");
+            if let Some(accept) = accept {
+                req = req.insert_header((header::ACCEPT, accept));
+            }
+
+            let resp = test::call_service(&app, req.to_request()).await;
+            assert_eq!(resp.status().as_u16(), 200);
+            assert_eq!(
+                resp.headers().get(header::CONTENT_TYPE).unwrap(),
+                format.mime().as_ref()
+            );
+
+            let body = test::read_body(resp).await;
+            assert_eq!(format.decode::<Entry>(&body).unwrap(), expected);
+        }
+    }
+
+    #[actix_web::test]
+    async fn recognises_empty_arrays() {
+        let empty: Vec<Entry> = Vec::new();
+        for format in [BodyFormat::Json, BodyFormat::MessagePack, BodyFormat::Cbor] {
+            assert!(format.is_empty_array(&format.encode(&empty).unwrap()));
+            assert!(!format.is_empty_array(&format.encode(&vec![1]).unwrap()));
+        }
+        assert!(BodyFormat::Json.is_empty_array(b" [ ]\n"));
+        assert!(BodyFormat::Cbor.is_empty_array(&[0x9f, 0xff]));
+    }
+}
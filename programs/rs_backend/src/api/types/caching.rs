@@ -0,0 +1,282 @@
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    future::{Ready, ready},
+    hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash, Hasher},
+    time::{Duration, SystemTime},
+};
+
+use actix_web::{
+    FromRequest, HttpRequest, HttpResponse, Responder,
+    body::BoxBody,
+    dev::Payload,
+    http::header::{
+        self, CacheControl, CacheDirective, ETag, EntityTag, Header, HttpDate, IfModifiedSince,
+        IfNoneMatch, LastModified, TryIntoHeaderPair,
+    },
+};
+
+use chrono::Utc;
+
+use crate::{db::sqlx::Changes, types::UserID};
+
+/// The conditional headers of a read, along with what else decides the representation it's sent:
+/// the path and query string, and the formats the client accepts. Malformed conditions are
+/// ignored, so the read is answered in full.
+#[derive(Debug)]
+pub struct Preconditions {
+    if_none_match: Option<IfNoneMatch>,
+    if_modified_since: Option<HttpDate>,
+    representation: String,
+}
+
+impl FromRequest for Preconditions {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let accept = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .unwrap_or_default();
+        ready(Ok(Preconditions {
+            // A missing `If-None-Match` parses as an empty list.
+            if_none_match: IfNoneMatch::parse(req)
+                .ok()
+                .filter(|etags| !matches!(etags, IfNoneMatch::Items(etags) if etags.is_empty())),
+            if_modified_since: IfModifiedSince::parse(req).ok().map(|since| since.0),
+            representation: format!("{} {}", req.uri(), accept),
+        }))
+    }
+}
+
+impl Preconditions {
+    /// The validators of what `user_id` is sent for this request, with the tables it reads from
+    /// last changed as in `changes`. The body's read targets are passed as `request`, since they
+    /// aren't part of the URL.
+    pub fn validators(&self, user_id: UserID, changes: Changes, request: impl Hash) -> Validators {
+        let mut hasher = DefaultHasher::new();
+        (user_id, changes.version, &self.representation, request).hash(&mut hasher);
+
+        // HTTP dates are only precise to the second, so one is only given once its second is
+        // over. Until then, another change in the same second would keep the same date, and a
+        // copy sent before that change would pass for current.
+        let seconds = changes.changed_at.timestamp();
+        let last_modified = (seconds < Utc::now().timestamp()).then(|| {
+            HttpDate::from(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64))
+        });
+        Validators {
+            etag: EntityTag::new_strong(format!("{:016x}", hasher.finish())),
+            last_modified,
+        }
+    }
+
+    /// Whether the client already holds the representation `validators` describe. `If-None-Match`
+    /// is used when it's sent, and `If-Modified-Since` only otherwise, and only when there's a
+    /// date to compare it with.
+    pub fn is_fresh(&self, validators: &Validators) -> bool {
+        match (&self.if_none_match, self.if_modified_since) {
+            (Some(IfNoneMatch::Any), _) => true,
+            (Some(IfNoneMatch::Items(etags)), _) => {
+                etags.iter().any(|etag| etag.weak_eq(&validators.etag))
+            }
+            (None, Some(since)) => validators
+                .last_modified
+                .is_some_and(|last_modified| last_modified <= since),
+            (None, None) => false,
+        }
+    }
+}
+
+/// An `ETag` and `Last-Modified` pair for a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: EntityTag,
+    /// Left out while the last change is still in the current second.
+    pub last_modified: Option<HttpDate>,
+}
+
+/// A read that's either sent in full or, when the client's copy is still current, answered with
+/// `304 Not Modified` and no body. Both carry the validators, and ask clients to check back
+/// before reusing what they hold.
+#[derive(Debug)]
+pub enum Cached<R> {
+    Modified(R, Validators),
+    NotModified(Validators),
+}
+
+impl<R: Responder> Responder for Cached<R> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let (mut resp, validators) = match self {
+            Cached::Modified(body, validators) => {
+                let resp = body.respond_to(req).map_into_boxed_body();
+                if !resp.status().is_success() {
+                    return resp;
+                }
+                (resp, validators)
+            }
+            Cached::NotModified(validators) => (HttpResponse::NotModified().finish(), validators),
+        };
+
+        let headers = resp.headers_mut();
+        let pairs = [
+            ETag(validators.etag).try_into_pair().ok(),
+            validators
+                .last_modified
+                .and_then(|date| LastModified(date).try_into_pair().ok()),
+            CacheControl(vec![CacheDirective::Private, CacheDirective::NoCache])
+                .try_into_pair()
+                .ok(),
+            (header::VARY, "Accept").try_into_pair().ok(),
+        ];
+        for (name, value) in pairs.into_iter().flatten() {
+            headers.insert(name, value);
+        }
+        resp
+    }
+}
+
+/// Hashes a set the same way whichever order it iterates in, as the read targets of two equal
+/// requests can.
+pub struct Unordered<'a, T>(pub &'a HashSet<T>);
+
+impl<T: Hash> Hash for Unordered<'_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let sum = self
+            .0
+            .iter()
+            .map(|item| hasher.hash_one(item))
+            .fold(0u64, u64::wrapping_add);
+        state.write_usize(self.0.len());
+        state.write_u64(sum);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::test::TestRequest;
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::types::ListID;
+
+    fn changes(version: i64) -> Changes {
+        Changes {
+            version,
+            changed_at: Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap(),
+        }
+    }
+
+    async fn preconditions(req: TestRequest) -> Preconditions {
+        let (req, mut payload) = req.to_http_parts();
+        Preconditions::from_request(&req, &mut payload)
+            .await
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn etags_follow_the_version_user_and_request() {
+        let plain = preconditions(TestRequest::get().uri("/api/lists")).await;
+        let targets = HashSet::from([ListID(1), ListID(2), ListID(3)]);
+        let reordered = HashSet::from([ListID(3), ListID(2), ListID(1)]);
+        let validators = plain.validators(UserID(1), changes(4), Unordered(&targets));
+
+        assert_eq!(
+            validators,
+            plain.validators(UserID(1), changes(4), Unordered(&reordered))
+        );
+        assert_ne!(
+            validators.etag,
+            plain
+                .validators(UserID(1), changes(5), Unordered(&targets))
+                .etag
+        );
+        assert_ne!(
+            validators.etag,
+            plain
+                .validators(UserID(2), changes(4), Unordered(&targets))
+                .etag
+        );
+        assert_ne!(
+            validators.etag,
+            plain
+                .validators(
+                    UserID(1),
+                    changes(4),
+                    Unordered(&HashSet::from([ListID(1)]))
+                )
+                .etag
+        );
+
+        let cbor = preconditions(
+            TestRequest::get()
+                .uri("/api/lists")
+                .insert_header((header::ACCEPT, "application/cbor")),
+        )
+        .await;
+        assert_ne!(
+            validators.etag,
+            cbor.validators(UserID(1), changes(4), Unordered(&targets))
+                .etag
+        );
+    }
+
+    #[actix_web::test]
+    async fn conditions_match_the_validators() {
+        let plain = preconditions(TestRequest::get()).await;
+        let validators = plain.validators(UserID(1), changes(4), ());
+        assert!(!plain.is_fresh(&validators));
+
+        let matching = preconditions(TestRequest::get().insert_header(IfNoneMatch::Items(vec![
+            EntityTag::new_strong("stale".to_string()),
+            validators.etag.clone(),
+        ])))
+        .await;
+        assert!(matching.is_fresh(&validators));
+        let stale = plain.validators(UserID(1), changes(5), ());
+        assert!(!matching.is_fresh(&stale));
+
+        let since =
+            |date: HttpDate| preconditions(TestRequest::get().insert_header(IfModifiedSince(date)));
+        let last_modified = validators.last_modified.unwrap();
+        assert!(since(last_modified).await.is_fresh(&validators));
+        let earlier = HttpDate::from(SystemTime::UNIX_EPOCH);
+        assert!(!since(earlier).await.is_fresh(&validators));
+
+        // An entity tag that doesn't match wins over a date that would.
+        let both = preconditions(
+            TestRequest::get()
+                .insert_header(IfNoneMatch::Items(vec![EntityTag::new_strong(
+                    "stale".to_string(),
+                )]))
+                .insert_header(IfModifiedSince(last_modified)),
+        )
+        .await;
+        assert!(!both.is_fresh(&validators));
+    }
+
+    #[actix_web::test]
+    async fn dates_wait_for_their_second_to_be_over() {
+        let just_now = Changes {
+            version: 4,
+            changed_at: Utc::now(),
+        };
+        let plain = preconditions(TestRequest::get()).await;
+        let validators = plain.validators(UserID(1), just_now, ());
+        assert_eq!(validators.last_modified, None);
+
+        // Even a date that's later than the change can't vouch for a copy yet.
+        let later = HttpDate::from(SystemTime::now() + Duration::from_secs(60));
+        let since = preconditions(TestRequest::get().insert_header(IfModifiedSince(later))).await;
+        assert!(!since.is_fresh(&validators));
+
+        let resp = Cached::<HttpResponse>::NotModified(validators)
+            .respond_to(&TestRequest::get().to_http_request());
+        assert!(resp.headers().contains_key(header::ETAG));
+        assert!(!resp.headers().contains_key(header::LAST_MODIFIED));
+    }
+}
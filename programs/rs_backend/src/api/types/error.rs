@@ -1,40 +1,38 @@
-use std::fmt::{Debug, Display};
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display},
+};
 
 use actix_web::{HttpResponse, ResponseError, body::BoxBody, http::StatusCode};
-use serde_json::json;
+use serde::Serialize;
+use utoipa::{
+    IntoResponses, ToSchema,
+    openapi::{ContentBuilder, Ref, RefOr, ResponseBuilder, response::Response},
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum JsonError {
     PayloadTooLarge(String),
     UnsupportedMediaType(String),
     BadRequest(String),
+    Unauthorized(String),
+    NotFound(String),
+    TooManyRequests(String),
     ServerError(String),
     Unknown(String),
 }
 
+/// The body every error is sent with, e.g. `{"error": "Bad Request: Empty request not allowed"}`.
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
 impl ResponseError for JsonError {
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        match self {
-            JsonError::PayloadTooLarge(msg) => {
-                HttpResponse::build(self.status_code()).json(json!({
-                    "error": format!("Payload Too Large: {}", msg),
-                }))
-            }
-            JsonError::UnsupportedMediaType(msg) => {
-                HttpResponse::build(self.status_code()).json(json!({
-                    "error": format!("Unsupported Media Type: {}", msg),
-                }))
-            }
-            JsonError::ServerError(msg) => HttpResponse::build(self.status_code()).json(json!({
-                "error": format!("Internal Server Error: {}", msg),
-            })),
-            JsonError::Unknown(msg) => HttpResponse::build(self.status_code()).json(json!({
-                "error": format!("Unknown Error: {}", msg),
-            })),
-            JsonError::BadRequest(msg) => HttpResponse::build(self.status_code()).json(json!({
-                "error": format!("Bad Request: {}", msg),
-            })),
-        }
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            error: self.to_string(),
+        })
     }
 
     fn status_code(&self) -> StatusCode {
@@ -44,12 +42,55 @@ impl ResponseError for JsonError {
             JsonError::ServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             JsonError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
             JsonError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            JsonError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            JsonError::NotFound(_) => StatusCode::NOT_FOUND,
+            JsonError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
 
 impl Display for JsonError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Json Error")
+        match self {
+            JsonError::PayloadTooLarge(msg) => write!(f, "Payload Too Large: {}", msg),
+            JsonError::UnsupportedMediaType(msg) => write!(f, "Unsupported Media Type: {}", msg),
+            JsonError::ServerError(msg) => write!(f, "Internal Server Error: {}", msg),
+            JsonError::Unknown(msg) => write!(f, "Unknown Error: {}", msg),
+            JsonError::BadRequest(msg) => write!(f, "Bad Request: {}", msg),
+            JsonError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            JsonError::NotFound(msg) => write!(f, "Not Found: {}", msg),
+            JsonError::TooManyRequests(msg) => write!(f, "Too Many Requests: {}", msg),
+        }
+    }
+}
+
+/// Documents every status an error can be sent with, each with an [`ErrorBody`].
+impl IntoResponses for JsonError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        let empty = String::new;
+        [
+            JsonError::BadRequest(empty()),
+            JsonError::Unauthorized(empty()),
+            JsonError::NotFound(empty()),
+            JsonError::PayloadTooLarge(empty()),
+            JsonError::UnsupportedMediaType(empty()),
+            JsonError::TooManyRequests(empty()),
+            JsonError::ServerError(empty()),
+        ]
+        .iter()
+        .map(|err| {
+            let status = err.status_code();
+            let response = ResponseBuilder::new()
+                .description(status.canonical_reason().unwrap_or_default())
+                .content(
+                    "application/json",
+                    ContentBuilder::new()
+                        .schema(Some(Ref::from_schema_name("ErrorBody")))
+                        .build(),
+                )
+                .build();
+            (status.as_str().to_string(), response.into())
+        })
+        .collect()
     }
 }
@@ -10,110 +10,122 @@ use actix_web::{
 };
 use futures_util::{StreamExt, future::LocalBoxFuture};
 use serde::de::DeserializeOwned;
-use serde_json as json;
 
+use super::BodyFormat;
+
+/// A request body decoded according to its `Content-Type`, as JSON, MessagePack or CBOR. Empty
+/// bodies and empty arrays are both `Empty`.
 #[derive(Debug)]
-pub enum MaybeJson<T> {
+pub enum MaybeBody<T> {
     Empty,
     Valid(T),
     Invalid(JsonPayloadError),
 }
 
-impl<T: DeserializeOwned> FromRequest for MaybeJson<T> {
+impl<T: DeserializeOwned> FromRequest for MaybeBody<T> {
     type Error = Infallible;
     type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
 
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        let json_config = req
-            .app_data::<MaybeJsonConfig>()
+        let body_config = req
+            .app_data::<MaybeBodyConfig>()
             .cloned()
             .unwrap_or_default();
 
-        let limit = json_config.get_limit();
-        let content_length = match ContentLength::parse(req) {
-            Ok(cl) => cl.0,
-            Err(_) => 0, // This isn't the right solution, this is really a parse error
-        };
-        if content_length <= 0 {
-            return Box::pin(async { Ok(MaybeJson::Empty) });
-        } else if content_length > limit {
-            return Box::pin(async move {
-                Ok(MaybeJson::Invalid(JsonPayloadError::OverflowKnownLength {
-                    length: content_length,
-                    limit,
-                }))
-            });
+        let limit = body_config.get_limit();
+        match declared_length(req, limit) {
+            Ok(0) => return Box::pin(async { Ok(MaybeBody::Empty) }),
+            Ok(_) => {}
+            Err(e) => return Box::pin(async move { Ok(MaybeBody::Invalid(e)) }),
         }
 
-        let content_type_required = json_config.get_content_type_required();
-        let check_content_type = json_config.get_content_type();
-        match (content_type_required, req.mime_type()) {
-            (false, _) => {}
+        let content_type_required = body_config.get_content_type_required();
+        let check_content_type = body_config.get_content_type();
+        let format = match (content_type_required, req.mime_type()) {
+            (false, Ok(Some(mime))) => BodyFormat::from_mime(&mime).unwrap_or(BodyFormat::Json),
+            (false, _) => BodyFormat::Json,
             (true, Ok(None) | Err(_)) => {
                 return Box::pin(
-                    async move { Ok(MaybeJson::Invalid(JsonPayloadError::ContentType)) },
+                    async move { Ok(MaybeBody::Invalid(JsonPayloadError::ContentType)) },
                 );
             }
-            (true, Ok(Some(mime))) => {
-                let can_parse_json = mime.subtype() == mime::JSON
-                    || mime.suffix() == Some(mime::JSON)
-                    || check_content_type.is_some_and(|check| check(mime));
-                if !can_parse_json {
+            (true, Ok(Some(mime))) => match BodyFormat::from_mime(&mime) {
+                Some(format) => format,
+                None if check_content_type.is_some_and(|check| check(mime)) => BodyFormat::Json,
+                None => {
                     return Box::pin(async move {
-                        Ok(MaybeJson::Invalid(JsonPayloadError::ContentType))
+                        Ok(MaybeBody::Invalid(JsonPayloadError::ContentType))
                     });
                 }
-            }
-        }
+            },
+        };
 
-        let mut payload = payload.take();
+        let payload = payload.take();
         Box::pin(async move {
-            let mut req_body = BytesMut::new();
-            while let Some(chunk) = payload.next().await {
-                let chunk = match chunk {
-                    Ok(new_bytes) => new_bytes,
-                    Err(e) => return Ok(MaybeJson::Invalid(JsonPayloadError::Payload(e))),
-                };
-
-                if req_body.len() + chunk.len() > limit {
-                    return Ok(MaybeJson::Invalid(JsonPayloadError::Overflow { limit }));
-                }
-
-                req_body.extend_from_slice(&chunk);
-            }
+            let req_body = match read_payload(payload, limit).await {
+                Ok(req_body) => req_body,
+                Err(e) => return Ok(MaybeBody::Invalid(e)),
+            };
 
             if req_body.is_empty() {
-                return Ok(MaybeJson::Empty);
+                return Ok(MaybeBody::Empty);
             }
 
-            let trimmed: Vec<u8> = req_body
-                .iter()
-                .copied()
-                .filter(|b| !b.is_ascii_whitespace())
-                .collect();
-
-            match json::from_slice::<T>(&req_body) {
-                Ok(json) => {
-                    if trimmed == b"[]" {
-                        return Ok(MaybeJson::Empty);
+            match format.decode::<T>(&req_body) {
+                Ok(body) => {
+                    if format.is_empty_array(&req_body) {
+                        return Ok(MaybeBody::Empty);
                     }
-                    Ok(MaybeJson::Valid(json))
+                    Ok(MaybeBody::Valid(body))
                 }
-                Err(e) => Ok(MaybeJson::Invalid(JsonPayloadError::Deserialize(e))),
+                Err(e) => Ok(MaybeBody::Invalid(JsonPayloadError::Deserialize(e))),
             }
         })
     }
 }
 
+/// The `Content-Length` of a request, checked against the limit. A missing one counts as no body.
+pub(crate) fn declared_length(req: &HttpRequest, limit: usize) -> Result<usize, JsonPayloadError> {
+    let content_length = match ContentLength::parse(req) {
+        Ok(cl) => cl.0,
+        Err(_) => 0, // This isn't the right solution, this is really a parse error
+    };
+    if content_length > limit {
+        return Err(JsonPayloadError::OverflowKnownLength {
+            length: content_length,
+            limit,
+        });
+    }
+    Ok(content_length)
+}
+
+/// Reads a whole payload, failing once it grows past the limit.
+pub(crate) async fn read_payload(
+    mut payload: Payload,
+    limit: usize,
+) -> Result<BytesMut, JsonPayloadError> {
+    let mut req_body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(JsonPayloadError::Payload)?;
+
+        if req_body.len() + chunk.len() > limit {
+            return Err(JsonPayloadError::Overflow { limit });
+        }
+
+        req_body.extend_from_slice(&chunk);
+    }
+    Ok(req_body)
+}
+
 #[derive(Clone)]
-pub struct MaybeJsonConfig {
+pub struct MaybeBodyConfig {
     limit: usize,
     content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
     content_type_required: bool,
 }
 
 #[allow(dead_code)]
-impl MaybeJsonConfig {
+impl MaybeBodyConfig {
     /// Set maximum accepted payload size. By default this limit is 2MB.
     pub fn limit(mut self, limit: usize) -> Self {
         self.limit = limit;
@@ -124,7 +136,7 @@ impl MaybeJsonConfig {
         self.limit
     }
 
-    /// Set predicate for allowed content types.
+    /// Set predicate for other content types to parse as JSON.
     pub fn content_type<F>(mut self, predicate: F) -> Self
     where
         F: Fn(mime::Mime) -> bool + Send + Sync + 'static,
@@ -148,9 +160,9 @@ impl MaybeJsonConfig {
     }
 }
 
-impl Default for MaybeJsonConfig {
+impl Default for MaybeBodyConfig {
     fn default() -> Self {
-        MaybeJsonConfig {
+        MaybeBodyConfig {
             limit: 2_097_152, // 2 mb
             content_type: None,
             content_type_required: true,
@@ -178,11 +190,11 @@ mod test {
         bool_field: bool,
     }
 
-    async fn test_handler(req: MaybeJson<ValidJson>) -> HttpResponse {
+    async fn test_handler(req: MaybeBody<ValidJson>) -> HttpResponse {
         match req {
-            MaybeJson::Empty => HttpResponse::NoContent().finish(),
-            MaybeJson::Valid(data) => HttpResponse::Ok().json(data),
-            MaybeJson::Invalid(e) => match e {
+            MaybeBody::Empty => HttpResponse::NoContent().finish(),
+            MaybeBody::Valid(data) => HttpResponse::Ok().json(data),
+            MaybeBody::Invalid(e) => match e {
                 JsonPayloadError::OverflowKnownLength { length, limit } => {
                     HttpResponse::PayloadTooLarge().body(format!(
                         "Payload overflow: {} bytes exceeds limit of {} bytes",
@@ -206,11 +218,11 @@ mod test {
         }
     }
 
-    async fn test_collection_handler(req: MaybeJson<Vec<ValidJson>>) -> HttpResponse {
+    async fn test_collection_handler(req: MaybeBody<Vec<ValidJson>>) -> HttpResponse {
         match req {
-            MaybeJson::Empty => HttpResponse::NoContent().finish(),
-            MaybeJson::Valid(data) => HttpResponse::Ok().json(data),
-            MaybeJson::Invalid(e) => match e {
+            MaybeBody::Empty => HttpResponse::NoContent().finish(),
+            MaybeBody::Valid(data) => HttpResponse::Ok().json(data),
+            MaybeBody::Invalid(e) => match e {
                 JsonPayloadError::OverflowKnownLength { length, limit } => {
                     HttpResponse::PayloadTooLarge().body(format!(
                         "Payload overflow: {} bytes exceeds limit of {} bytes",
@@ -234,7 +246,7 @@ mod test {
         }
     }
 
-    // TEST MaybeJson START
+    // TEST MaybeBody START
 
     #[actix_web::test]
     async fn valid_on_valid_input() {
@@ -327,9 +339,55 @@ mod test {
         assert_eq!(resp3.status().as_u16(), 204); // NoContent
     }
 
-    // TEST MaybeJson END
+    #[actix_web::test]
+    async fn decodes_by_content_type() {
+        let app = test::init_service(App::new().route("/", web::to(test_handler))).await;
+        let alice = ValidJson {
+            name: "Alice".to_string(),
+            age: 30,
+        };
+
+        for format in [BodyFormat::MessagePack, BodyFormat::Cbor] {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .insert_header((header::CONTENT_TYPE, format.mime()))
+                .set_payload(format.encode(&alice).unwrap())
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status().as_u16(), 200);
+            let resp_json: ValidJson = test::read_body_json(resp).await;
+            assert_eq!(resp_json, alice);
+
+            // JSON sent under a binary content type doesn't decode.
+            let req = test::TestRequest::post()
+                .uri("/")
+                .insert_header((header::CONTENT_TYPE, format.mime()))
+                .set_payload(r#"{"name":"Alice","age":30}"#)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status().as_u16(), 400);
+        }
+
+        let app2 =
+            test::init_service(App::new().route("/", web::to(test_collection_handler))).await;
+
+        for format in [BodyFormat::MessagePack, BodyFormat::Cbor] {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .insert_header((header::CONTENT_TYPE, format.mime()))
+                .set_payload(format.encode(&Vec::<ValidJson>::new()).unwrap())
+                .to_request();
+
+            let resp = test::call_service(&app2, req).await;
+            assert_eq!(resp.status().as_u16(), 204); // NoContent
+        }
+    }
+
+    // TEST MaybeBody END
 
-    // TEST MaybeJsonConfig START
+    // TEST MaybeBodyConfig START
 
     #[actix_web::test]
     async fn invalid_with_wrong_header() {
@@ -358,7 +416,7 @@ mod test {
         let limit = 100;
         let app = test::init_service(
             App::new()
-                .app_data(MaybeJsonConfig::default().limit(limit)) // 100 bytes limit
+                .app_data(MaybeBodyConfig::default().limit(limit)) // 100 bytes limit
                 .route("/", web::to(test_handler)),
         )
         .await;
@@ -380,7 +438,7 @@ mod test {
             str::from_utf8(&test::read_body(resp).await).unwrap(),
             format!(
                 "Payload overflow: {} bytes exceeds limit of {} bytes",
-                payload.as_bytes().len(),
+                payload.len(),
                 limit
             )
         );
@@ -391,7 +449,7 @@ mod test {
         let limit = 100;
         let app = test::init_service(
             App::new()
-                .app_data(MaybeJsonConfig::default().limit(limit)) // 100 bytes limit
+                .app_data(MaybeBodyConfig::default().limit(limit)) // 100 bytes limit
                 .route("/", web::to(test_handler)),
         )
         .await;
@@ -419,7 +477,7 @@ mod test {
     async fn can_extend_acceptable_content_types() {
         let app = test::init_service(
             App::new()
-                .app_data(MaybeJsonConfig::default().content_type(|mime: mime::Mime| {
+                .app_data(MaybeBodyConfig::default().content_type(|mime: mime::Mime| {
                     mime.type_() == mime::TEXT && mime.subtype() == mime::PLAIN
                 }))
                 .route("/", web::to(test_handler)),
@@ -458,7 +516,7 @@ mod test {
     async fn can_turn_off_content_type_check() {
         let app = test::init_service(
             App::new()
-                .app_data(MaybeJsonConfig::default().content_type_required(false))
+                .app_data(MaybeBodyConfig::default().content_type_required(false))
                 .route("/", web::to(test_handler)),
         )
         .await;
@@ -477,5 +535,5 @@ mod test {
         assert_eq!(resp.status().as_u16(), 200);
     }
 
-    // TEST MaybeJsonConfig END
+    // TEST MaybeBodyConfig END
 }
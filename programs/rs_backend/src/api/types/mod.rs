@@ -1,5 +1,17 @@
+mod body;
+mod caching;
 mod error;
 mod extractor;
+mod protobuf;
+mod rate_limit;
+mod session;
+mod targets;
 
+pub use body::*;
+pub use caching::*;
 pub use error::*;
 pub use extractor::*;
+pub use protobuf::*;
+pub use rate_limit::*;
+pub use session::*;
+pub use targets::*;
@@ -0,0 +1,250 @@
+use std::{convert::Infallible, marker::PhantomData};
+
+use actix_web::{
+    FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder,
+    body::BoxBody,
+    dev::Payload,
+    error::JsonPayloadError,
+    http::header::{self, Accept, Header},
+    mime,
+};
+use futures_util::future::LocalBoxFuture;
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{
+    Body, BodyFormat, MaybeBody, MaybeBodyConfig,
+    extractor::{declared_length, read_payload},
+};
+
+pub const PROTOBUF: &str = "application/x-protobuf";
+
+/// Whether a media type is protobuf, under its common name or the newer unprefixed one.
+pub fn is_protobuf(mime: &mime::Mime) -> bool {
+    mime.type_() == mime::APPLICATION
+        && matches!(mime.subtype().as_str(), "x-protobuf" | "protobuf")
+}
+
+/// Whether protobuf ranks above every other format in `Accept`. Wildcards go to JSON, so clients
+/// have to ask for protobuf by name.
+pub fn accepts_protobuf(req: &HttpRequest) -> bool {
+    Accept::parse(req)
+        .map(|accept| accept.ranked())
+        .unwrap_or_default()
+        .iter()
+        .find_map(|mime| match (mime.type_(), mime.subtype()) {
+            _ if is_protobuf(mime) => Some(true),
+            (mime::STAR, _) | (mime::APPLICATION, mime::STAR) => Some(false),
+            _ => BodyFormat::from_mime(mime).map(|_| false),
+        })
+        .unwrap_or(false)
+}
+
+/// A request body that can also be sent as `M`, its message in `schema.proto`. Anything other than
+/// protobuf is decoded as a [`MaybeBody`]. Messages with nothing in them are `Empty`, the same as
+/// an empty array.
+#[derive(Debug)]
+pub struct MaybeMessage<T, M>(MaybeBody<T>, PhantomData<M>);
+
+impl<T, M> MaybeMessage<T, M> {
+    pub fn into_inner(self) -> MaybeBody<T> {
+        self.0
+    }
+}
+
+impl<T, M> FromRequest for MaybeMessage<T, M>
+where
+    T: DeserializeOwned + TryFrom<M, Error = String> + 'static,
+    M: prost::Message + Default + 'static,
+{
+    type Error = Infallible;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        if !matches!(req.mime_type(), Ok(Some(mime)) if is_protobuf(&mime)) {
+            let body = MaybeBody::<T>::from_request(req, payload);
+            return Box::pin(async move { Ok(MaybeMessage(body.await?, PhantomData)) });
+        }
+
+        let limit = req
+            .app_data::<MaybeBodyConfig>()
+            .cloned()
+            .unwrap_or_default()
+            .get_limit();
+        let body = match declared_length(req, limit) {
+            Ok(0) => Err(None),
+            Ok(_) => Ok(payload.take()),
+            Err(e) => Err(Some(e)),
+        };
+
+        Box::pin(async move {
+            let req_body = match body {
+                Ok(payload) => read_payload(payload, limit).await.map_err(Some),
+                Err(e) => Err(e),
+            };
+            let body = match req_body {
+                Ok(req_body) if req_body.is_empty() => MaybeBody::Empty,
+                Ok(req_body) => match M::decode(req_body.freeze())
+                    .map_err(|e| e.to_string())
+                    .and_then(T::try_from)
+                {
+                    Ok(body) => MaybeBody::Valid(body),
+                    Err(e) => MaybeBody::Invalid(JsonPayloadError::Deserialize(
+                        serde::de::Error::custom(e),
+                    )),
+                },
+                Err(None) => MaybeBody::Empty,
+                Err(Some(e)) => MaybeBody::Invalid(e),
+            };
+            Ok(MaybeMessage(body, PhantomData))
+        })
+    }
+}
+
+/// A response body sent as `M`, its message in `schema.proto`, to clients that prefer protobuf,
+/// and as a [`Body`] to everyone else.
+#[derive(Debug)]
+pub struct Message<T, M>(pub T, PhantomData<M>);
+
+impl<T, M> From<Body<T>> for Message<T, M> {
+    fn from(Body(body): Body<T>) -> Self {
+        Message(body, PhantomData)
+    }
+}
+
+impl<T, M> Responder for Message<T, M>
+where
+    T: Serialize,
+    M: prost::Message + From<T>,
+{
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        if !accepts_protobuf(req) {
+            return Body(self.0).respond_to(req);
+        }
+
+        HttpResponse::Ok()
+            .content_type(PROTOBUF)
+            .insert_header((header::VARY, "Accept"))
+            .body(M::from(self.0).encode_to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{App, test, web};
+    use prost::Message as _;
+
+    use super::*;
+    use crate::types::{CreateList, ListID, protobuf};
+
+    async fn read_lists(
+        req: MaybeMessage<Vec<ListID>, protobuf::ReadListsRequest>,
+    ) -> HttpResponse {
+        match req.into_inner() {
+            MaybeBody::Empty => HttpResponse::NoContent().finish(),
+            MaybeBody::Valid(ids) => HttpResponse::Ok().json(ids),
+            MaybeBody::Invalid(_) => HttpResponse::BadRequest().finish(),
+        }
+    }
+
+    async fn created() -> Message<Vec<CreateList>, protobuf::CreateListsRequest> {
+        Message::from(Body(vec![CreateList {
+            title: "Chores".to_string(),
+        }]))
+    }
+
+    impl TryFrom<protobuf::ReadListsRequest> for Vec<ListID> {
+        type Error = String;
+
+        fn try_from(req: protobuf::ReadListsRequest) -> Result<Self, String> {
+            req.lids
+                .iter()
+                .map(|lid| ListID::parse(lid).ok_or_else(|| format!("Invalid lid: {}", lid)))
+                .collect()
+        }
+    }
+
+    impl From<Vec<CreateList>> for protobuf::CreateListsRequest {
+        fn from(lists: Vec<CreateList>) -> Self {
+            protobuf::CreateListsRequest {
+                lists: lists.into_iter().map(Into::into).collect(),
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn decodes_protobuf_requests() {
+        let app = test::init_service(App::new().route("/", web::to(read_lists))).await;
+
+        let message = protobuf::ReadListsRequest {
+            lids: vec![ListID(3).public(), "4".to_string()],
+        };
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((header::CONTENT_TYPE, PROTOBUF))
+            .set_payload(message.encode_to_vec())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 200);
+        let ids: Vec<ListID> = test::read_body_json(resp).await;
+        assert_eq!(ids, vec![ListID(3), ListID(4)]);
+
+        // A message without any lids encodes to nothing at all.
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((header::CONTENT_TYPE, "application/protobuf"))
+            .set_payload(protobuf::ReadListsRequest::default().encode_to_vec())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 204);
+
+        for payload in [b"\x0a\x05list".to_vec(), b"\xff\xff".to_vec()] {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .insert_header((header::CONTENT_TYPE, PROTOBUF))
+                .set_payload(payload)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status().as_u16(), 400);
+        }
+
+        // Other formats still go through `MaybeBody`.
+        let req = test::TestRequest::post()
+            .uri("/")
+            .set_json(vec![ListID(5)])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 200);
+    }
+
+    #[actix_web::test]
+    async fn responds_with_protobuf_when_preferred() {
+        let app = test::init_service(App::new().route("/", web::to(created))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((
+                header::ACCEPT,
+                "application/x-protobuf, application/json;q=0.5",
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 200);
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), PROTOBUF);
+        let message = protobuf::CreateListsRequest::decode(test::read_body(resp).await).unwrap();
+        assert_eq!(message.lists[0].title, "Chores");
+
+        for accept in ["*/*", "application/json, application/x-protobuf;q=0.5"] {
+            let req = test::TestRequest::get()
+                .uri("/")
+                .insert_header((header::ACCEPT, accept))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(
+                resp.headers().get(header::CONTENT_TYPE).unwrap(),
+                "application/json"
+            );
+        }
+    }
+}
@@ -0,0 +1,542 @@
+use std::{
+    collections::HashMap,
+    env,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    Error, HttpMessage, HttpRequest, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        Method,
+        header::{self, HeaderValue},
+    },
+    middleware::Next,
+    web::Data,
+};
+
+use crate::api::types::{CurrentUser, JsonError, SESSION_COOKIE};
+
+/// Past this many buckets, the ones that have refilled completely are dropped. A full bucket
+/// behaves exactly like a missing one, so nothing is lost.
+const PRUNE_AT: usize = 4096;
+
+/// How requests are told apart when handing out budgets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// The address the connection comes from.
+    Ip,
+    /// The session token the request carries, as a bearer token or cookie. Requests without one
+    /// fall back to their address.
+    ApiKey,
+    /// The logged in user, so every session of a user shares one budget. Requests without a
+    /// user, like logging in, fall back to their address.
+    User,
+}
+
+impl FromStr for RateLimitKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "ip" => Ok(RateLimitKey::Ip),
+            "api_key" => Ok(RateLimitKey::ApiKey),
+            "user" => Ok(RateLimitKey::User),
+            other => Err(format!(
+                "Unknown rate limit key '{}', expected ip, api_key or user",
+                other
+            )),
+        }
+    }
+}
+
+/// A token bucket: up to `burst` requests at once, refilled at `per_second`.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    burst: f64,
+    per_second: f64,
+}
+
+impl Budget {
+    /// A budget has to allow at least one request and refill at some rate, or clients that ran
+    /// through it would wait forever.
+    pub fn new(burst: u32, per_second: f64) -> Result<Self, String> {
+        if burst == 0 {
+            return Err("A rate limit budget needs a burst of at least 1".to_string());
+        }
+        if !(per_second.is_finite() && per_second > 0.0) {
+            return Err(format!(
+                "A rate limit budget can't refill at {} per second",
+                per_second
+            ));
+        }
+        Ok(Budget {
+            burst: f64::from(burst),
+            per_second,
+        })
+    }
+}
+
+impl FromStr for Budget {
+    type Err = String;
+
+    /// Reads a budget written as `burst/per_second`, like `30/2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Can't read the rate limit budget '{}', expected burst/per_second",
+                s
+            )
+        };
+        let (burst, per_second) = s.split_once('/').ok_or_else(invalid)?;
+        Budget::new(
+            burst.trim().parse().map_err(|_| invalid())?,
+            per_second.trim().parse().map_err(|_| invalid())?,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    key: RateLimitKey,
+    reads: Budget,
+    writes: Budget,
+    failures: Budget,
+}
+
+impl RateLimitConfig {
+    /// Reads the configuration from the environment, keeping the default of anything unset:
+    ///
+    /// - `RATE_LIMIT_KEY`: `ip`, `api_key` or `user`, see [`RateLimitKey`].
+    /// - `RATE_LIMIT_READS`, `RATE_LIMIT_WRITES` and `RATE_LIMIT_FAILURES`: budgets written as
+    ///   `burst/per_second`, like `30/2`.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let mut config = RateLimitConfig::default();
+        if let Some(key) = var("RATE_LIMIT_KEY") {
+            config = config.key(key.parse()?);
+        }
+        if let Some(reads) = var("RATE_LIMIT_READS") {
+            config = config.reads(reads.parse()?);
+        }
+        if let Some(writes) = var("RATE_LIMIT_WRITES") {
+            config = config.writes(writes.parse()?);
+        }
+        if let Some(failures) = var("RATE_LIMIT_FAILURES") {
+            config = config.failures(failures.parse()?);
+        }
+        Ok(config)
+    }
+
+    /// Set how clients are told apart. By default it's by user.
+    pub fn key(mut self, key: RateLimitKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Set the budget of `GET`, `HEAD` and `OPTIONS` requests, and of GraphQL queries. By default
+    /// a client can make 120 at once and 20 more every second.
+    pub fn reads(mut self, budget: Budget) -> Self {
+        self.reads = budget;
+        self
+    }
+
+    /// Set the budget of every other request. Writes all go through the single SQLite writer, so
+    /// by default a client can make 30 at once and 2 more every second.
+    pub fn writes(mut self, budget: Budget) -> Self {
+        self.writes = budget;
+        self
+    }
+
+    /// Set the budget of failed logins and requests with an unknown session token, which is
+    /// always kept by address. Once it runs out, nothing from the address is authenticated until
+    /// it refills, so tokens and passwords can't be guessed at the pace of the other budgets. By
+    /// default an address can fail 10 times at once and once more every 10 seconds.
+    pub fn failures(mut self, budget: Budget) -> Self {
+        self.failures = budget;
+        self
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            key: RateLimitKey::User,
+            reads: Budget {
+                burst: 120.0,
+                per_second: 20.0,
+            },
+            writes: Budget {
+                burst: 30.0,
+                per_second: 2.0,
+            },
+            failures: Budget {
+                burst: 10.0,
+                per_second: 0.1,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Access {
+    Read,
+    Write,
+    Failure,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, budget: Budget, now: Instant) {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * budget.per_second).min(budget.burst);
+        self.refilled_at = now;
+    }
+}
+
+/// The buckets of every client, shared by all workers. Register it as app data for
+/// [`rate_limit`] to find.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(String, Access), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        RateLimiter {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes `tokens` more from the bucket [`rate_limit`] charged for `req`, for requests that do
+    /// the work of several, like a batch of JSON-RPC calls. Either they're all taken or none are.
+    pub fn charge(&self, req: &HttpRequest, tokens: usize) -> Result<(), JsonError> {
+        let client = self.client(req);
+        self.take(client, access(req), tokens as f64, Instant::now())
+            .map_err(|wait| too_many_requests(wait).0)
+    }
+
+    /// Takes one token from the write budget of whoever sent `req`, for requests [`rate_limit`]
+    /// charged as reads that turn out to write, like GraphQL mutations.
+    pub fn charge_write(&self, req: &HttpRequest) -> Result<(), JsonError> {
+        let client = self.client(req);
+        self.take(client, Access::Write, 1.0, Instant::now())
+            .map_err(|wait| too_many_requests(wait).0)
+    }
+
+    /// Refuses `req` if its address ran through its budget of failures, without taking from it.
+    pub fn check_failures(&self, req: &HttpRequest) -> Result<(), JsonError> {
+        let wait = self.with_bucket(
+            ip(req),
+            Access::Failure,
+            Instant::now(),
+            |bucket, budget| {
+                (bucket.tokens < 1.0)
+                    .then(|| Duration::from_secs_f64((1.0 - bucket.tokens) / budget.per_second))
+            },
+        );
+        match wait {
+            Some(wait) => Err(too_many_requests(wait).0),
+            None => Ok(()),
+        }
+    }
+
+    /// Counts a failed authentication against the address of `req`.
+    pub fn fail(&self, req: &HttpRequest) {
+        // Running out only matters to the next check.
+        let _ = self.take(ip(req), Access::Failure, 1.0, Instant::now());
+    }
+
+    /// The bucket key of whoever sent `req`.
+    fn client(&self, req: &HttpRequest) -> String {
+        let ip = || ip(req);
+        match self.config.key {
+            RateLimitKey::Ip => ip(),
+            RateLimitKey::ApiKey => req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(|token| token.trim().to_string())
+                .or_else(|| {
+                    req.cookie(SESSION_COOKIE)
+                        .map(|cookie| cookie.value().to_string())
+                })
+                .map_or_else(ip, |token| format!("token {}", token)),
+            RateLimitKey::User => req
+                .extensions()
+                .get::<CurrentUser>()
+                .map_or_else(ip, |user| format!("user {}", user.id.key())),
+        }
+    }
+
+    /// Takes tokens from the client's bucket, or tells how long until there are enough.
+    fn take(
+        &self,
+        client: String,
+        access: Access,
+        tokens: f64,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        self.with_bucket(client, access, now, |bucket, budget| {
+            if bucket.tokens >= tokens {
+                bucket.tokens -= tokens;
+                Ok(())
+            } else {
+                Err(Duration::from_secs_f64(
+                    (tokens - bucket.tokens) / budget.per_second,
+                ))
+            }
+        })
+    }
+
+    /// Runs `f` on the client's bucket, refilled up to `now`.
+    fn with_bucket<R>(
+        &self,
+        client: String,
+        access: Access,
+        now: Instant,
+        f: impl FnOnce(&mut Bucket, Budget) -> R,
+    ) -> R {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+
+        if buckets.len() >= PRUNE_AT {
+            buckets.retain(|(_, access), bucket| {
+                let budget = self.budget(*access);
+                bucket.refill(budget, now);
+                bucket.tokens < budget.burst
+            });
+        }
+
+        let budget = self.budget(access);
+        let bucket = buckets.entry((client, access)).or_insert(Bucket {
+            tokens: budget.burst,
+            refilled_at: now,
+        });
+        bucket.refill(budget, now);
+        f(bucket, budget)
+    }
+
+    fn budget(&self, access: Access) -> Budget {
+        match access {
+            Access::Read => self.config.reads,
+            Access::Write => self.config.writes,
+            Access::Failure => self.config.failures,
+        }
+    }
+}
+
+fn ip(req: &HttpRequest) -> String {
+    match req.peer_addr() {
+        Some(addr) => format!("ip {}", addr.ip()),
+        None => "ip unknown".to_string(),
+    }
+}
+
+/// GraphQL queries are posted like mutations, so every GraphQL request is charged as a read and
+/// the handler charges mutations to the write budget as well.
+fn access(req: &HttpRequest) -> Access {
+    match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => Access::Read,
+        Method::POST if req.path() == "/graphql" => Access::Read,
+        _ => Access::Write,
+    }
+}
+
+/// The error for a client that has to wait, with the whole seconds to put in `Retry-After`.
+fn too_many_requests(wait: Duration) -> (JsonError, u64) {
+    let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+    let err =
+        JsonError::TooManyRequests(format!("Slow down, try again in {} seconds", retry_after));
+    (err, retry_after)
+}
+
+/// Middleware that turns away clients that ran through their budget with a 429. Requests pass
+/// untouched when no [`RateLimiter`] is registered.
+pub async fn rate_limit<B: MessageBody + 'static>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let Some(limiter) = req.app_data::<Data<RateLimiter>>().cloned() else {
+        return next.call(req).await.map(|res| res.map_into_left_body());
+    };
+
+    let client = limiter.client(req.request());
+    match limiter.take(client, access(req.request()), 1.0, Instant::now()) {
+        Ok(()) => next.call(req).await.map(|res| res.map_into_left_body()),
+        Err(wait) => {
+            let (err, retry_after) = too_many_requests(wait);
+            let mut res = err.error_response();
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            Ok(req.into_response(res).map_into_right_body())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{App, HttpResponse, middleware::from_fn, test, web};
+
+    use crate::{api::types::authenticate, db::sqlx::setup_test_db};
+
+    #[actix_web::test]
+    async fn reads_and_writes_have_separate_budgets() {
+        let config = RateLimitConfig::default()
+            .key(RateLimitKey::Ip)
+            .reads(Budget::new(2, 0.001).unwrap())
+            .writes(Budget::new(1, 0.001).unwrap());
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RateLimiter::new(config)))
+                .wrap(from_fn(rate_limit))
+                .route("/", web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let from = |ip: &str| ip.parse().unwrap();
+
+        for _ in 0..2 {
+            let req = test::TestRequest::get()
+                .peer_addr(from("10.0.0.1:1000"))
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status().as_u16(), 200);
+        }
+
+        let req = test::TestRequest::post()
+            .peer_addr(from("10.0.0.1:1000"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status().as_u16(), 200);
+
+        let req = test::TestRequest::get()
+            .peer_addr(from("10.0.0.1:1001"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 429);
+        assert!(resp.headers().contains_key(header::RETRY_AFTER));
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Too Many Requests")
+        );
+
+        let req = test::TestRequest::get()
+            .peer_addr(from("10.0.0.2:1000"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status().as_u16(), 200);
+    }
+
+    #[actix_web::test]
+    async fn graphql_is_charged_as_a_read() {
+        let graphql = test::TestRequest::post().uri("/graphql").to_http_request();
+        let rpc = test::TestRequest::post().uri("/rpc").to_http_request();
+
+        assert_eq!(access(&graphql), Access::Read);
+        assert_eq!(access(&rpc), Access::Write);
+    }
+
+    #[actix_web::test]
+    async fn buckets_refill_over_time() {
+        let limiter =
+            RateLimiter::new(RateLimitConfig::default().writes(Budget::new(1, 2.0).unwrap()));
+        let start = Instant::now();
+
+        assert!(
+            limiter
+                .take("a".to_string(), Access::Write, 1.0, start)
+                .is_ok()
+        );
+        let wait = limiter
+            .take("a".to_string(), Access::Write, 1.0, start)
+            .unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+        assert!(
+            limiter
+                .take("a".to_string(), Access::Write, 1.0, start + wait)
+                .is_ok()
+        );
+    }
+
+    #[actix_web::test]
+    async fn unknown_tokens_run_through_the_budget_of_failures() {
+        let config = RateLimitConfig::default().failures(Budget::new(2, 0.001).unwrap());
+        let app = test::init_service(
+            App::new()
+                .app_data(setup_test_db().await)
+                .app_data(Data::new(RateLimiter::new(config)))
+                .wrap(from_fn(authenticate))
+                .route("/api/lists", web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let guess = |ip: &str, token: &str| {
+            test::TestRequest::get()
+                .uri("/api/lists")
+                .peer_addr(ip.parse().unwrap())
+                .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+                .to_request()
+        };
+        let status = |req| async {
+            match test::try_call_service(&app, req).await {
+                Ok(resp) => resp.status().as_u16(),
+                Err(err) => err.as_response_error().status_code().as_u16(),
+            }
+        };
+
+        assert_eq!(status(guess("10.0.0.1:1000", "a")).await, 401);
+        assert_eq!(status(guess("10.0.0.1:1001", "b")).await, 401);
+        assert_eq!(status(guess("10.0.0.1:1002", "c")).await, 429);
+        assert_eq!(status(guess("10.0.0.2:1000", "d")).await, 401);
+    }
+
+    #[actix_web::test]
+    async fn charges_take_all_their_tokens_or_none() {
+        let limiter = RateLimiter::new(
+            RateLimitConfig::default()
+                .key(RateLimitKey::Ip)
+                .writes(Budget::new(5, 0.001).unwrap()),
+        );
+        let req = test::TestRequest::post()
+            .peer_addr("10.0.0.1:1000".parse().unwrap())
+            .to_http_request();
+
+        assert!(limiter.charge(&req, 3).is_ok());
+        let err = limiter.charge(&req, 3).unwrap_err();
+        assert!(matches!(err, JsonError::TooManyRequests(_)));
+        assert!(limiter.charge(&req, 2).is_ok());
+        assert!(limiter.charge(&req, 1).is_err());
+    }
+
+    #[actix_web::test]
+    async fn config_is_read_from_the_environment() {
+        let vars = HashMap::from([("RATE_LIMIT_KEY", "ip"), ("RATE_LIMIT_WRITES", " 5 / 0.5 ")]);
+        let config =
+            RateLimitConfig::from_vars(|name| vars.get(name).map(|value| value.to_string()))
+                .unwrap();
+        assert_eq!(config.key, RateLimitKey::Ip);
+        assert_eq!((config.writes.burst, config.writes.per_second), (5.0, 0.5));
+        assert_eq!(config.reads.burst, RateLimitConfig::default().reads.burst);
+
+        for bad in ["5", "5/0", "5/-1", "0/1", "5/inf", "five/1"] {
+            assert!(bad.parse::<Budget>().is_err(), "{} was accepted", bad);
+        }
+        let unknown_key = RateLimitConfig::from_vars(|name| {
+            (name == "RATE_LIMIT_KEY").then(|| "cookie".to_string())
+        });
+        assert!(unknown_key.is_err());
+    }
+}
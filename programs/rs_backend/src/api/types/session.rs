@@ -0,0 +1,101 @@
+use std::future::{Ready, ready};
+
+use actix_web::{
+    Error, FromRequest, HttpMessage, HttpRequest,
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{Method, header},
+    middleware::Next,
+    web::Data,
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::types::{JsonError, RateLimiter},
+    db::sqlx::find_session_user,
+    types::UserID,
+};
+
+/// The name of the cookie a login is stored in.
+pub const SESSION_COOKIE: &str = "session";
+
+/// The logged in user of a request, put in place by [`authenticate`].
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub id: UserID,
+    pub token: String,
+}
+
+impl FromRequest for CurrentUser {
+    type Error = JsonError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<CurrentUser>()
+                .cloned()
+                .ok_or_else(|| JsonError::Unauthorized("You need to log in".to_string())),
+        )
+    }
+}
+
+/// Middleware that turns away `/api`, GraphQL and JSON-RPC requests without a valid session. The
+/// token is read from an `Authorization: Bearer` header, falling back to the session cookie.
+/// Registering, logging in and the API docs are the only routes left open. Unknown tokens count
+/// against the [`RateLimiter`]'s budget of failures, and addresses that ran through it aren't
+/// authenticated at all until it refills.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let path = req.path();
+    let is_protected = path.starts_with("/api") || path == "/graphql" || path == "/rpc";
+    let is_open = match *req.method() {
+        Method::POST => path == "/api/users" || path == "/api/sessions",
+        Method::GET => {
+            path == "/graphql" || path == "/api/openapi.json" || path.starts_with("/api/docs")
+        }
+        _ => false,
+    };
+    if !is_protected || is_open {
+        return next.call(req).await;
+    }
+
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .or_else(|| {
+            req.cookie(SESSION_COOKIE)
+                .map(|cookie| cookie.value().to_string())
+        })
+        .ok_or_else(|| JsonError::Unauthorized("You need to log in".to_string()))?;
+
+    let limiter = req.app_data::<Data<RateLimiter>>().cloned();
+    if let Some(limiter) = &limiter {
+        limiter.check_failures(req.request())?;
+    }
+
+    let db_conn_pool = req
+        .app_data::<Data<Pool<Sqlite>>>()
+        .cloned()
+        .ok_or_else(|| JsonError::ServerError("No database configured".to_string()))?;
+    let Some(user_id) = find_session_user(db_conn_pool, &token)
+        .await
+        .map_err(|err| JsonError::ServerError(format!("Database Query Error: {}", err)))?
+    else {
+        if let Some(limiter) = &limiter {
+            limiter.fail(req.request());
+        }
+        return Err(
+            JsonError::Unauthorized("Your session is invalid or has expired".to_string()).into(),
+        );
+    };
+
+    req.extensions_mut()
+        .insert(CurrentUser { id: user_id, token });
+    next.call(req).await
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::HashSet,
+    future::{Ready, ready},
+    hash::Hash,
+};
+
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web::Query};
+use len_trait::Empty;
+
+use crate::types::{
+    ListID, SetAddress, SetID, SetQueryTarget, TO_DO_READ_OPTIONS, TagID, ToDoAddress, ToDoID,
+    ToDoQueryTarget,
+};
+
+use super::{JsonError, MaybeBody};
+
+/// Read targets that can be given in the query string. Each key names a kind of target and can be
+/// repeated, e.g. `?list=1&set=4&todo=9`. Addresses separate their parts with `/`, and leave the
+/// set out of a to do outside of any set: `?address=1/2` is set 2 of list 1, or the to dos in it,
+/// and `?address=1//7` is the loose to do 7 of list 1. Any other key is refused, except for the
+/// query parameters the route reads besides its targets, like the to do read options.
+pub trait FromQueryTargets: Sized {
+    fn from_pairs(pairs: &[(String, String)]) -> Result<Self, JsonError>;
+}
+
+/// Read targets taken from the query string, for clients that can't send a body with a `GET`.
+/// It's `None` when the query string names no targets.
+#[derive(Debug)]
+pub struct QueryTargets<T>(pub Option<T>);
+
+impl<T> QueryTargets<T> {
+    /// The targets to read: the query string's, or else the body's. Sending both is an error,
+    /// rather than picking one or merging them behind the client's back.
+    pub fn or_body(self, body: MaybeBody<T>) -> Result<MaybeBody<T>, JsonError> {
+        match (self.0, body) {
+            (None, body) => Ok(body),
+            (Some(targets), MaybeBody::Empty) => Ok(MaybeBody::Valid(targets)),
+            (Some(_), _) => Err(JsonError::BadRequest(
+                "Targets can be sent in the query string or in the body, but not in both"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+impl<T: FromQueryTargets + Empty> FromRequest for QueryTargets<T> {
+    type Error = JsonError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let targets = Query::<Vec<(String, String)>>::from_query(req.query_string())
+            .map_err(|err| {
+                JsonError::BadRequest(format!("Error reading your query string: {}", err))
+            })
+            .and_then(|pairs| T::from_pairs(&pairs))
+            .map(|targets| QueryTargets((!targets.is_empty()).then_some(targets)));
+        ready(targets)
+    }
+}
+
+impl FromQueryTargets for HashSet<ListID> {
+    fn from_pairs(pairs: &[(String, String)]) -> Result<Self, JsonError> {
+        collect(pairs, &[], |key, value| match key {
+            "list" => Some(id(key, value, ListID::parse)),
+            _ => None,
+        })
+    }
+}
+
+impl FromQueryTargets for HashSet<SetQueryTarget> {
+    fn from_pairs(pairs: &[(String, String)]) -> Result<Self, JsonError> {
+        collect(pairs, &[], |key, value| match key {
+            "list" => Some(id(key, value, ListID::parse).map(SetQueryTarget::List)),
+            "set" => Some(id(key, value, SetID::parse).map(SetQueryTarget::Set)),
+            "subtree" => Some(id(key, value, SetID::parse).map(SetQueryTarget::Subtree)),
+            "address" => Some(set_address(key, value).map(SetQueryTarget::Address)),
+            _ => None,
+        })
+    }
+}
+
+impl FromQueryTargets for HashSet<ToDoQueryTarget> {
+    fn from_pairs(pairs: &[(String, String)]) -> Result<Self, JsonError> {
+        collect(pairs, TO_DO_READ_OPTIONS, |key, value| match key {
+            "list" => Some(id(key, value, ListID::parse).map(ToDoQueryTarget::List)),
+            "set" => Some(id(key, value, SetID::parse).map(ToDoQueryTarget::Set)),
+            "todo" => Some(id(key, value, ToDoID::parse).map(ToDoQueryTarget::ToDo)),
+            "subtree" => Some(id(key, value, SetID::parse).map(ToDoQueryTarget::Subtree)),
+            "tag" => Some(id(key, value, TagID::parse).map(ToDoQueryTarget::Tag)),
+            "address" => Some(todo_address(key, value).map(ToDoQueryTarget::Address)),
+            _ => None,
+        })
+    }
+}
+
+/// Reads the targets in `pairs`, skipping the keys in `others`, which the route reads for
+/// something else.
+fn collect<T: Eq + Hash>(
+    pairs: &[(String, String)],
+    others: &[&str],
+    target: impl Fn(&str, &str) -> Option<Result<T, JsonError>>,
+) -> Result<HashSet<T>, JsonError> {
+    pairs
+        .iter()
+        .filter(|(key, _)| !others.contains(&key.as_str()))
+        .map(|(key, value)| {
+            target(key, value).unwrap_or_else(|| {
+                Err(JsonError::BadRequest(format!(
+                    "Invalid Argument Provided: `{}` isn't a query parameter of this route",
+                    key
+                )))
+            })
+        })
+        .collect()
+}
+
+fn id<T>(key: &str, value: &str, parse: fn(&str) -> Option<T>) -> Result<T, JsonError> {
+    parse(value).ok_or_else(|| {
+        JsonError::BadRequest(format!(
+            "Invalid Argument Provided: `{}={}` doesn't hold a valid id",
+            key, value
+        ))
+    })
+}
+
+fn set_address(key: &str, value: &str) -> Result<SetAddress, JsonError> {
+    match value.split('/').collect::<Vec<&str>>()[..] {
+        [list] => Ok(SetAddress::WholeList(id(key, list, ListID::parse)?)),
+        [list, set] => Ok(SetAddress::Singular(
+            id(key, list, ListID::parse)?,
+            number(key, set)?,
+        )),
+        _ => Err(invalid_address(key, value)),
+    }
+}
+
+fn todo_address(key: &str, value: &str) -> Result<ToDoAddress, JsonError> {
+    match value.split('/').collect::<Vec<&str>>()[..] {
+        [list] => Ok(ToDoAddress::WholeList(id(key, list, ListID::parse)?)),
+        [list, set] => Ok(ToDoAddress::WholeSet(
+            id(key, list, ListID::parse)?,
+            number(key, set)?,
+        )),
+        [list, "", todo] => Ok(ToDoAddress::Singular(
+            id(key, list, ListID::parse)?,
+            None,
+            number(key, todo)?,
+        )),
+        [list, set, todo] => Ok(ToDoAddress::Singular(
+            id(key, list, ListID::parse)?,
+            Some(number(key, set)?),
+            number(key, todo)?,
+        )),
+        _ => Err(invalid_address(key, value)),
+    }
+}
+
+fn number(key: &str, value: &str) -> Result<i64, JsonError> {
+    value
+        .parse()
+        .ok()
+        .filter(|number| *number > 0)
+        .ok_or_else(|| {
+            JsonError::BadRequest(format!(
+                "Invalid Argument Provided: `{}` holds `{}`, which isn't a valid number",
+                key, value
+            ))
+        })
+}
+
+fn invalid_address(key: &str, value: &str) -> JsonError {
+    JsonError::BadRequest(format!(
+        "Invalid Argument Provided: `{}={}` doesn't hold a valid address",
+        key, value
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+    use crate::types::ToDoReadOptions;
+
+    async fn extract<T: FromQueryTargets + Empty>(uri: &str) -> Result<Option<T>, JsonError> {
+        let (req, mut payload) = TestRequest::get().uri(uri).to_http_parts();
+        QueryTargets::<T>::from_request(&req, &mut payload)
+            .await
+            .map(|targets| targets.0)
+    }
+
+    #[actix_web::test]
+    async fn reads_repeated_targets_and_skips_read_options() {
+        let targets = extract::<HashSet<ToDoQueryTarget>>(&format!(
+            "/api/to_dos?list=1&set=4&todo={}&todo=9&sort=title&complete=false",
+            ToDoID(2).public()
+        ))
+        .await
+        .unwrap();
+        assert_eq!(
+            targets,
+            Some(HashSet::from([
+                ToDoQueryTarget::List(ListID(1)),
+                ToDoQueryTarget::Set(SetID(4)),
+                ToDoQueryTarget::ToDo(ToDoID(2)),
+                ToDoQueryTarget::ToDo(ToDoID(9)),
+            ]))
+        );
+
+        let targets = extract::<HashSet<SetQueryTarget>>("/api/sets?subtree=3&address=1/2")
+            .await
+            .unwrap();
+        assert_eq!(
+            targets,
+            Some(HashSet::from([
+                SetQueryTarget::Subtree(SetID(3)),
+                SetQueryTarget::Address(SetAddress::Singular(ListID(1), 2)),
+            ]))
+        );
+
+        // Only the to do read options, so no targets at all.
+        let targets = extract::<HashSet<ToDoQueryTarget>>("/api/to_dos?sort=title")
+            .await
+            .unwrap();
+        assert_eq!(targets, None);
+        let targets = extract::<HashSet<ListID>>("/api/lists").await.unwrap();
+        assert_eq!(targets, None);
+    }
+
+    #[actix_web::test]
+    async fn reads_addresses() {
+        let targets = extract::<HashSet<ToDoQueryTarget>>(&format!(
+            "/api/to_dos?address={0}&address={0}/2&address={0}/2/5&address={0}//7",
+            ListID(1).public()
+        ))
+        .await
+        .unwrap();
+        assert_eq!(
+            targets,
+            Some(HashSet::from([
+                ToDoQueryTarget::Address(ToDoAddress::WholeList(ListID(1))),
+                ToDoQueryTarget::Address(ToDoAddress::WholeSet(ListID(1), 2)),
+                ToDoQueryTarget::Address(ToDoAddress::Singular(ListID(1), Some(2), 5)),
+                ToDoQueryTarget::Address(ToDoAddress::Singular(ListID(1), None, 7)),
+            ]))
+        );
+
+        for address in ["1/two", "1/0", "1/2/3/4", "", "1//"] {
+            let uri = format!("/api/to_dos?address={}", address);
+            let err = extract::<HashSet<ToDoQueryTarget>>(&uri).await.unwrap_err();
+            assert!(matches!(err, JsonError::BadRequest(_)), "{}", address);
+        }
+        let err = extract::<HashSet<SetQueryTarget>>("/api/sets?address=1/2/3")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, JsonError::BadRequest(_)));
+    }
+
+    #[test]
+    fn read_options_are_all_allowed_next_to_to_do_targets() {
+        let options = serde_json::to_value(ToDoReadOptions::default()).unwrap();
+        let mut keys = options.as_object().unwrap().keys().collect::<Vec<_>>();
+        let mut allowed = TO_DO_READ_OPTIONS.to_vec();
+        keys.sort();
+        allowed.sort();
+        assert_eq!(keys, allowed);
+    }
+
+    #[actix_web::test]
+    async fn rejects_keys_the_route_doesnt_read() {
+        // A to do isn't a target of a set read, and the read options only go with to dos.
+        for uri in [
+            "/api/sets?subtree=3&todo=9",
+            "/api/sets?sort=title",
+            "/api/sets?lsit=1",
+        ] {
+            let err = extract::<HashSet<SetQueryTarget>>(uri).await.unwrap_err();
+            assert!(matches!(err, JsonError::BadRequest(_)), "{}", uri);
+        }
+        let err = extract::<HashSet<ListID>>("/api/lists?set=1")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, JsonError::BadRequest(_)));
+        let err = extract::<HashSet<ToDoQueryTarget>>("/api/to_dos?list=1&sortt=title")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, JsonError::BadRequest(_)));
+    }
+
+    #[actix_web::test]
+    async fn rejects_ids_that_dont_parse() {
+        let err = extract::<HashSet<ListID>>(&format!("/api/lists?list={}", SetID(1).public()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, JsonError::BadRequest(_)));
+    }
+
+    #[test]
+    fn query_and_body_cant_be_mixed() {
+        let query = || QueryTargets(Some(HashSet::from([ListID(1)])));
+
+        assert!(matches!(
+            query().or_body(MaybeBody::Empty),
+            Ok(MaybeBody::Valid(targets)) if targets == HashSet::from([ListID(1)])
+        ));
+        assert!(matches!(
+            query().or_body(MaybeBody::Valid(HashSet::from([ListID(2)]))),
+            Err(JsonError::BadRequest(_))
+        ));
+        assert!(matches!(
+            QueryTargets(None).or_body(MaybeBody::Valid(HashSet::from([ListID(2)]))),
+            Ok(MaybeBody::Valid(targets)) if targets == HashSet::from([ListID(2)])
+        ));
+        assert!(matches!(
+            QueryTargets::<HashSet<ListID>>(None).or_body(MaybeBody::Empty),
+            Ok(MaybeBody::Empty)
+        ));
+    }
+}
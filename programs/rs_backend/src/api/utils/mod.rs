@@ -1,6 +1,9 @@
 mod query_all;
+mod query_cached;
 mod query_shared;
 mod query_some;
 
 pub use query_all::*;
+pub use query_cached::*;
+pub(crate) use query_shared::{input_err, map_input_err, map_query_err, query_err};
 pub use query_some::*;
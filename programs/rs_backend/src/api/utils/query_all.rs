@@ -1,17 +1,31 @@
-use actix_web::web::{Data, Json};
+use actix_web::web::Data;
 use len_trait::Len;
 use sqlx::{Error as SQLXError, Pool, Sqlite};
 
-use crate::api::types::{JsonError, MaybeJson};
+use crate::api::types::{Body, JsonError, MaybeBody};
 
 use super::query_shared::{map_input_err, map_query_err};
 
+pub async fn query_all<Out, Qall, Fall>(
+    db: Data<Pool<Sqlite>>,
+    query_all: Qall,
+) -> Result<Body<Out>, JsonError>
+where
+    Fall: Future<Output = Result<Out, SQLXError>>,
+    Qall: Fn(Data<Pool<Sqlite>>) -> Fall,
+{
+    match query_all(db).await {
+        Ok(result) => Ok(Body(result)),
+        Err(err) => map_query_err(err),
+    }
+}
+
 pub async fn query_all_or_some<In, Out, Qall, Qsome, Fall, Fsome>(
-    req: MaybeJson<In>,
+    req: MaybeBody<In>,
     db: Data<Pool<Sqlite>>,
     query_all: Qall,
     query_some: Qsome,
-) -> Result<Json<Out>, JsonError>
+) -> Result<Body<Out>, JsonError>
 where
     In: Len,
     Fall: Future<Output = Result<Out, SQLXError>>,
@@ -20,15 +34,15 @@ where
     Qsome: Fn(Data<Pool<Sqlite>>, In) -> Fsome,
 {
     match req {
-        MaybeJson::Empty => match query_all(db).await {
-            Ok(result) => Ok(Json(result)),
+        MaybeBody::Empty => match query_all(db).await {
+            Ok(result) => Ok(Body(result)),
             Err(err) => map_query_err(err),
         },
-        MaybeJson::Valid(json) => match query_some(db, json).await {
-            Ok(result) => Ok(Json(result)),
+        MaybeBody::Valid(json) => match query_some(db, json).await {
+            Ok(result) => Ok(Body(result)),
             Err(err) => map_query_err(err),
         },
-        MaybeJson::Invalid(err) => map_input_err(err),
+        MaybeBody::Invalid(err) => map_input_err(err),
     }
 }
 
@@ -46,7 +60,7 @@ mod test {
     use sqlx::{Error, Pool, Sqlite, SqlitePool};
 
     use crate::api::{
-        types::{JsonError, MaybeJson},
+        types::{JsonError, MaybeBody},
         utils::query_all_or_some,
     };
 
@@ -114,7 +128,7 @@ mod test {
     async fn test_query_all_fail() {
         // this isn't complete. We need a case for invalid arguments
         let db1 = Data::new(setup_test_db().await);
-        let req1 = MaybeJson::Empty;
+        let req1 = MaybeBody::Empty;
 
         let result1 = query_all_or_some(req1, db1, query_all_fail, query_some_fail).await;
 
@@ -124,7 +138,7 @@ mod test {
             JsonError::ServerError("".to_string()).status_code()
         );
 
-        let req2 = MaybeJson::Valid(vec![TestInput {
+        let req2 = MaybeBody::Valid(vec![TestInput {
             caller: "query some".to_string(),
         }]);
         let db2 = Data::new(setup_test_db().await);
@@ -139,7 +153,7 @@ mod test {
 
         set_invalid_argument(true);
 
-        let req3 = MaybeJson::Empty;
+        let req3 = MaybeBody::Empty;
         let db3 = Data::new(setup_test_db().await);
 
         let result3 = query_all_or_some(req3, db3, query_all_fail, query_some_fail).await;
@@ -150,7 +164,7 @@ mod test {
             JsonError::BadRequest("test".to_string()).status_code()
         );
 
-        let req4 = MaybeJson::Valid(vec![TestInput {
+        let req4 = MaybeBody::Valid(vec![TestInput {
             caller: "query some".to_string(),
         }]);
         let db4 = Data::new(setup_test_db().await);
@@ -166,11 +180,33 @@ mod test {
         set_invalid_argument(false);
     }
 
+    // TEST query all without a request body
+    #[actix_web::test]
+    async fn test_query_all_without_request() {
+        let db1 = Data::new(setup_test_db().await);
+
+        let result1 = super::query_all(db1, query_all).await;
+
+        assert!(result1.is_ok());
+        assert_eq!(
+            result1.unwrap().to_vec(),
+            vec![TestInput {
+                caller: "query all".to_string(),
+            }]
+        );
+
+        let db2 = Data::new(setup_test_db().await);
+
+        let result2 = super::query_all(db2, query_all_fail).await;
+
+        assert!(result2.is_err());
+    }
+
     // TEST if empty query all
     #[actix_web::test]
     async fn test_empty_request_calls_query_all() {
         let db = Data::new(setup_test_db().await);
-        let req = MaybeJson::Empty;
+        let req = MaybeBody::Empty;
 
         let result = query_all_or_some(req, db, query_all, query_some).await;
 
@@ -187,7 +223,7 @@ mod test {
     #[actix_web::test]
     async fn test_valid_request_calls_query_some() {
         let db = Data::new(setup_test_db().await);
-        let req1 = MaybeJson::Valid(vec![TestInput {
+        let req1 = MaybeBody::Valid(vec![TestInput {
             caller: "query some".to_string(),
         }]);
 
@@ -207,7 +243,7 @@ mod test {
     async fn test_invalid_json_fails() {
         // Test Payload error
         let db1 = Data::new(setup_test_db().await);
-        let req1 = MaybeJson::Invalid(JsonPayloadError::Payload(PayloadError::Overflow));
+        let req1 = MaybeBody::Invalid(JsonPayloadError::Payload(PayloadError::Overflow));
 
         let result1 = query_all_or_some(req1, db1, query_all, query_some).await;
 
@@ -219,7 +255,7 @@ mod test {
 
         // Test Overflow error
         let db2 = Data::new(setup_test_db().await);
-        let req2 = MaybeJson::Invalid(JsonPayloadError::Overflow { limit: 1024 });
+        let req2 = MaybeBody::Invalid(JsonPayloadError::Overflow { limit: 1024 });
 
         let result2 = query_all_or_some(req2, db2, query_all, query_some).await;
 
@@ -231,7 +267,7 @@ mod test {
 
         // Test OverflowKnownLength error
         let db3 = Data::new(setup_test_db().await);
-        let req3 = MaybeJson::Invalid(JsonPayloadError::OverflowKnownLength {
+        let req3 = MaybeBody::Invalid(JsonPayloadError::OverflowKnownLength {
             length: 2048,
             limit: 1024,
         });
@@ -246,7 +282,7 @@ mod test {
 
         // Test ContentType error
         let db4 = Data::new(setup_test_db().await);
-        let req4 = MaybeJson::Invalid(JsonPayloadError::ContentType);
+        let req4 = MaybeBody::Invalid(JsonPayloadError::ContentType);
 
         let result4 = query_all_or_some(req4, db4, query_all, query_some).await;
 
@@ -258,7 +294,7 @@ mod test {
 
         // Test Deserialize error
         let db5 = Data::new(setup_test_db().await);
-        let req5 = MaybeJson::Invalid(JsonPayloadError::Deserialize(serde_json::Error::io(
+        let req5 = MaybeBody::Invalid(JsonPayloadError::Deserialize(serde_json::Error::io(
             std::io::Error::new(std::io::ErrorKind::InvalidData, "test deserialize error"),
         )));
 
@@ -0,0 +1,148 @@
+use std::{collections::HashSet, hash::Hash};
+
+use actix_web::web::Data;
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    api::types::{Cached, JsonError, MaybeBody, Preconditions, Unordered},
+    db::sqlx::query_changes,
+    types::UserID,
+};
+
+use super::query_shared::{input_err, query_err};
+
+/// Runs `read` on `req`, unless the client already holds what it would return. That's judged by
+/// the change counters of `tables`, which have to cover everything the read draws on, so when
+/// the client's copy is current neither the query nor the encoding of its result take place.
+pub async fn query_cached<In, Out, Qread, Fread>(
+    preconditions: Preconditions,
+    req: MaybeBody<HashSet<In>>,
+    db: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    tables: &[&str],
+    read: Qread,
+) -> Result<Cached<Out>, JsonError>
+where
+    In: Hash,
+    Fread: Future<Output = Result<Out, JsonError>>,
+    Qread: FnOnce(Data<Pool<Sqlite>>, MaybeBody<HashSet<In>>) -> Fread,
+{
+    let req = match req {
+        MaybeBody::Invalid(err) => return Err(input_err(err)),
+        req => req,
+    };
+    let targets = match &req {
+        MaybeBody::Valid(targets) => Some(Unordered(targets)),
+        _ => None,
+    };
+
+    // The counters are read before the query, so a write in between only makes the validators
+    // older than the result, and the next request reads it again.
+    let changes = query_changes(db.clone(), tables).await.map_err(query_err)?;
+    let validators = preconditions.validators(user_id, changes, targets);
+    if preconditions.is_fresh(&validators) {
+        return Ok(Cached::NotModified(validators));
+    }
+
+    read(db, req)
+        .await
+        .map(|result| Cached::Modified(result, validators))
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use actix_web::{
+        FromRequest,
+        http::header::{EntityTag, IfNoneMatch},
+        test::TestRequest,
+    };
+
+    use super::*;
+    use crate::{
+        api::{types::Validators, utils::query_all_or_some},
+        db::sqlx::{LISTS_TABLES, query_all_lists, query_lists, setup_test_db, update_lists},
+        types::{ListID, UpdateList},
+    };
+
+    async fn preconditions(etag: Option<&EntityTag>) -> Preconditions {
+        let req = match etag {
+            Some(etag) => TestRequest::get().insert_header(IfNoneMatch::Items(vec![etag.clone()])),
+            None => TestRequest::get(),
+        };
+        let (req, mut payload) = req.uri("/api/lists").to_http_parts();
+        Preconditions::from_request(&req, &mut payload)
+            .await
+            .unwrap()
+    }
+
+    /// Reads the lists of alice, counting how often the query actually runs.
+    async fn read(
+        db: Data<Pool<Sqlite>>,
+        etag: Option<&EntityTag>,
+        targets: MaybeBody<HashSet<ListID>>,
+        runs: &Cell<usize>,
+    ) -> Cached<usize> {
+        query_cached(
+            preconditions(etag).await,
+            targets,
+            db,
+            UserID(1),
+            LISTS_TABLES,
+            |db, req| async move {
+                runs.set(runs.get() + 1);
+                query_all_or_some(
+                    req,
+                    db,
+                    |db| query_all_lists(db, UserID(1)),
+                    |db, ids| query_lists(db, UserID(1), ids),
+                )
+                .await
+                .map(|lists| lists.len())
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    fn validators(cached: &Cached<usize>) -> &Validators {
+        match cached {
+            Cached::Modified(_, validators) | Cached::NotModified(validators) => validators,
+        }
+    }
+
+    #[actix_web::test]
+    async fn current_copies_skip_the_query() {
+        let db = setup_test_db().await;
+        let runs = Cell::new(0);
+
+        let first = read(db.clone(), None, MaybeBody::Empty, &runs).await;
+        assert!(matches!(first, Cached::Modified(2, _)));
+        let etag = validators(&first).etag.clone();
+
+        let again = read(db.clone(), Some(&etag), MaybeBody::Empty, &runs).await;
+        assert!(matches!(again, Cached::NotModified(_)));
+        assert_eq!(runs.get(), 1);
+
+        // Other targets are another representation, even at the same URL.
+        let one = MaybeBody::Valid(HashSet::from([ListID(1)]));
+        let other = read(db.clone(), Some(&etag), one, &runs).await;
+        assert!(matches!(other, Cached::Modified(1, _)));
+
+        update_lists(
+            db.clone(),
+            UserID(1),
+            vec![UpdateList {
+                list_id: ListID(2),
+                title: "Hobbies".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        let changed = read(db, Some(&etag), MaybeBody::Empty, &runs).await;
+        assert!(matches!(changed, Cached::Modified(2, _)));
+        assert_ne!(validators(&changed).etag, etag);
+        assert_eq!(runs.get(), 3);
+    }
+}
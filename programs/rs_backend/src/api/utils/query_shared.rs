@@ -1,47 +1,54 @@
-use actix_web::{error::JsonPayloadError, web::Json};
+use actix_web::error::JsonPayloadError;
 use sqlx::Error as SQLXError;
 
-use crate::api::types::JsonError;
+use crate::api::types::{Body, JsonError};
 
-pub(super) fn map_input_err<Out>(err: JsonPayloadError) -> Result<Json<Out>, JsonError> {
+pub(crate) fn map_input_err<Out>(err: JsonPayloadError) -> Result<Body<Out>, JsonError> {
+    Err(input_err(err))
+}
+
+/// The error an unreadable request body is reported as.
+pub(crate) fn input_err(err: JsonPayloadError) -> JsonError {
     match err {
-        JsonPayloadError::Overflow { limit } => Err(JsonError::PayloadTooLarge(format!(
+        JsonPayloadError::Overflow { limit } => JsonError::PayloadTooLarge(format!(
             "You're payload is greater than the limit for {} bytes",
             limit
-        ))),
+        )),
         JsonPayloadError::OverflowKnownLength { length, limit } => {
-            Err(JsonError::PayloadTooLarge(format!(
+            JsonError::PayloadTooLarge(format!(
                 "You're payload length of {} bytes is greater than the limit for {} bytes",
                 length, limit
-            )))
+            ))
         }
-        JsonPayloadError::ContentType => Err(JsonError::UnsupportedMediaType(
+        JsonPayloadError::ContentType => JsonError::UnsupportedMediaType(
             "Unsupported 'Content-Type' header or missing 'Content-Type' header".to_string(),
-        )),
-        JsonPayloadError::Payload(e) => Err(JsonError::BadRequest(format!(
-            "Error processing your payload: {}",
-            e
-        ))),
-        JsonPayloadError::Deserialize(e) => Err(JsonError::BadRequest(format!(
-            "Error deserializing your payload: {}",
-            e
-        ))),
-        _ => Err(JsonError::Unknown(format!(
-            "Unknown JSON payload error: {}",
-            err
-        ))),
+        ),
+        JsonPayloadError::Payload(e) => {
+            JsonError::BadRequest(format!("Error processing your payload: {}", e))
+        }
+        JsonPayloadError::Deserialize(e) => {
+            JsonError::BadRequest(format!("Error deserializing your payload: {}", e))
+        }
+        _ => JsonError::Unknown(format!("Unknown JSON payload error: {}", err)),
     }
 }
 
-pub(super) fn map_query_err<Out>(err: SQLXError) -> Result<Json<Out>, JsonError> {
+pub(crate) fn map_query_err<Out>(err: SQLXError) -> Result<Body<Out>, JsonError> {
+    Err(query_err(err))
+}
+
+/// The error a failed query is reported as.
+pub(crate) fn query_err(err: SQLXError) -> JsonError {
     match err {
-        SQLXError::InvalidArgument(msg) => Err(JsonError::BadRequest(format!(
-            "Invalid Argument Provided: {}",
-            msg
-        ))),
-        _ => Err(JsonError::ServerError(format!(
-            "Database Insertion Error: {}",
-            err
-        ))),
+        SQLXError::InvalidArgument(msg) => {
+            JsonError::BadRequest(format!("Invalid Argument Provided: {}", msg))
+        }
+        SQLXError::Database(e) if e.is_foreign_key_violation() => {
+            JsonError::BadRequest(format!("Invalid Reference Provided: {}", e))
+        }
+        SQLXError::Database(e) if e.is_unique_violation() => {
+            JsonError::BadRequest(format!("Duplicate Value Provided: {}", e))
+        }
+        _ => JsonError::ServerError(format!("Database Insertion Error: {}", err)),
     }
 }
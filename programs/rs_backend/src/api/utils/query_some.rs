@@ -1,30 +1,30 @@
-use actix_web::web::{Data, Json};
+use actix_web::web::Data;
 use len_trait::Len;
 use sqlx::{Error as SQLXError, Pool, Sqlite};
 
-use crate::api::types::{JsonError, MaybeJson};
+use crate::api::types::{Body, JsonError, MaybeBody};
 
 use super::query_shared::{map_input_err, map_query_err};
 
 pub async fn query_some<In, Out, Qsome, Fut>(
-    req: MaybeJson<In>,
+    req: MaybeBody<In>,
     db: Data<Pool<Sqlite>>,
     query_some: Qsome,
-) -> Result<Json<Out>, JsonError>
+) -> Result<Body<Out>, JsonError>
 where
     In: Len,
     Fut: Future<Output = Result<Out, SQLXError>>,
     Qsome: Fn(Data<Pool<Sqlite>>, In) -> Fut,
 {
     match req {
-        MaybeJson::Valid(json) => match query_some(db, json).await {
-            Ok(result) => Ok(Json(result)),
+        MaybeBody::Valid(json) => match query_some(db, json).await {
+            Ok(result) => Ok(Body(result)),
             Err(err) => map_query_err(err),
         },
-        MaybeJson::Empty => Err(JsonError::BadRequest(
+        MaybeBody::Empty => Err(JsonError::BadRequest(
             "Empty request not allowed".to_string(),
         )),
-        MaybeJson::Invalid(err) => map_input_err(err),
+        MaybeBody::Invalid(err) => map_input_err(err),
     }
 }
 
@@ -42,7 +42,7 @@ mod test {
     use sqlx::{Error, Pool, Sqlite, SqlitePool};
 
     use crate::api::{
-        types::{JsonError, MaybeJson},
+        types::{JsonError, MaybeBody},
         utils::query_some,
     };
 
@@ -91,7 +91,7 @@ mod test {
     #[actix_web::test]
     async fn test_valid_request_calls_query_some() {
         let db = Data::new(setup_test_db().await);
-        let req = MaybeJson::Valid(vec![TestInput {
+        let req = MaybeBody::Valid(vec![TestInput {
             caller: "test input".to_string(),
         }]);
 
@@ -111,7 +111,7 @@ mod test {
     async fn test_query_some_fail() {
         // Test generic database error
         let db1 = Data::new(setup_test_db().await);
-        let req1 = MaybeJson::Valid(vec![TestInput {
+        let req1 = MaybeBody::Valid(vec![TestInput {
             caller: "test input".to_string(),
         }]);
 
@@ -127,7 +127,7 @@ mod test {
         set_invalid_argument(true);
 
         let db2 = Data::new(setup_test_db().await);
-        let req2 = MaybeJson::Valid(vec![TestInput {
+        let req2 = MaybeBody::Valid(vec![TestInput {
             caller: "test input".to_string(),
         }]);
 
@@ -147,7 +147,7 @@ mod test {
     #[actix_web::test]
     async fn test_empty_request_returns_error() {
         let db = Data::new(setup_test_db().await);
-        let req = MaybeJson::Empty;
+        let req = MaybeBody::Empty;
 
         let result = query_some(req, db, query_success).await;
 
@@ -163,7 +163,7 @@ mod test {
     async fn test_invalid_json_fails() {
         // Test Payload error
         let db1 = Data::new(setup_test_db().await);
-        let req1 = MaybeJson::Invalid(JsonPayloadError::Payload(PayloadError::Overflow));
+        let req1 = MaybeBody::Invalid(JsonPayloadError::Payload(PayloadError::Overflow));
 
         let result1 = query_some(req1, db1, query_success).await;
 
@@ -175,7 +175,7 @@ mod test {
 
         // Test Overflow error
         let db2 = Data::new(setup_test_db().await);
-        let req2 = MaybeJson::Invalid(JsonPayloadError::Overflow { limit: 1024 });
+        let req2 = MaybeBody::Invalid(JsonPayloadError::Overflow { limit: 1024 });
 
         let result2 = query_some(req2, db2, query_success).await;
 
@@ -187,7 +187,7 @@ mod test {
 
         // Test OverflowKnownLength error
         let db3 = Data::new(setup_test_db().await);
-        let req3 = MaybeJson::Invalid(JsonPayloadError::OverflowKnownLength {
+        let req3 = MaybeBody::Invalid(JsonPayloadError::OverflowKnownLength {
             length: 2048,
             limit: 1024,
         });
@@ -202,7 +202,7 @@ mod test {
 
         // Test ContentType error
         let db4 = Data::new(setup_test_db().await);
-        let req4 = MaybeJson::Invalid(JsonPayloadError::ContentType);
+        let req4 = MaybeBody::Invalid(JsonPayloadError::ContentType);
 
         let result4 = query_some(req4, db4, query_success).await;
 
@@ -214,7 +214,7 @@ mod test {
 
         // Test Deserialize error
         let db5 = Data::new(setup_test_db().await);
-        let req5 = MaybeJson::Invalid(JsonPayloadError::Deserialize(serde_json::Error::io(
+        let req5 = MaybeBody::Invalid(JsonPayloadError::Deserialize(serde_json::Error::io(
             std::io::Error::new(std::io::ErrorKind::InvalidData, "test deserialize error"),
         )));
 
@@ -0,0 +1,293 @@
+use std::collections::BTreeSet;
+
+use sqlx::{
+    Error::{self as SQLXError, InvalidArgument},
+    Row, SqliteConnection,
+};
+
+use crate::types::{ListID, PublicId, Role, TagID, ToDoID, UserID};
+
+/// A query selecting the ids of the lists `user_id` owns, for use in `list_id IN (...)`. Only the
+/// owner can rename, delete or share a list.
+pub(super) fn owned_lists(user_id: UserID) -> String {
+    format!("SELECT id FROM Lists WHERE owner_id = {}", user_id.key())
+}
+
+/// Like [`owned_lists`], adding the lists shared with `user_id` in any role.
+pub(super) fn readable_lists(user_id: UserID) -> String {
+    format!(
+        "SELECT id FROM Lists WHERE owner_id = {0} UNION SELECT list_id FROM ListMembers WHERE user_id = {0}",
+        user_id.key()
+    )
+}
+
+/// Like [`owned_lists`], adding the lists shared with `user_id` as an editor. The sets, to dos and
+/// checklist items of these lists can be changed.
+pub(super) fn writable_lists(user_id: UserID) -> String {
+    format!(
+        "SELECT id FROM Lists WHERE owner_id = {0} UNION SELECT list_id FROM ListMembers WHERE user_id = {0} AND role = '{1}'",
+        user_id.key(),
+        Role::Editor.name()
+    )
+}
+
+/// A query selecting the ids of the to dos in lists `user_id` can read, for use in
+/// `todo_id IN (...)`.
+pub(super) fn readable_todos(user_id: UserID) -> String {
+    format!(
+        "SELECT id FROM Todos WHERE list_id IN ({})",
+        readable_lists(user_id)
+    )
+}
+
+pub(super) fn writable_todos(user_id: UserID) -> String {
+    format!(
+        "SELECT id FROM Todos WHERE list_id IN ({})",
+        writable_lists(user_id)
+    )
+}
+
+/// Makes sure `user_id` owns every one of `list_ids`. Lists of other users are reported as
+/// missing, so their ids don't give anything away.
+pub(super) async fn check_owned_lists(
+    db_conn: &mut SqliteConnection,
+    user_id: UserID,
+    list_ids: impl IntoIterator<Item = ListID>,
+) -> Result<(), SQLXError> {
+    let scope = format!("owner_id = {}", user_id.key());
+    check_ids(db_conn, "Lists", "List", &scope, list_ids).await
+}
+
+/// Makes sure `user_id` can change every one of `list_ids`, as their owner or an editor.
+pub(super) async fn check_lists(
+    db_conn: &mut SqliteConnection,
+    user_id: UserID,
+    list_ids: impl IntoIterator<Item = ListID>,
+) -> Result<(), SQLXError> {
+    let scope = format!("id IN ({})", writable_lists(user_id));
+    check_ids(db_conn, "Lists", "List", &scope, list_ids).await
+}
+
+pub(super) async fn check_todos(
+    db_conn: &mut SqliteConnection,
+    user_id: UserID,
+    todo_ids: impl IntoIterator<Item = ToDoID>,
+) -> Result<(), SQLXError> {
+    let scope = format!("list_id IN ({})", writable_lists(user_id));
+    check_ids(db_conn, "Todos", "To do", &scope, todo_ids).await
+}
+
+pub(super) async fn check_tags(
+    db_conn: &mut SqliteConnection,
+    user_id: UserID,
+    tag_ids: impl IntoIterator<Item = TagID>,
+) -> Result<(), SQLXError> {
+    let scope = format!("owner_id = {}", user_id.key());
+    check_ids(db_conn, "Tags", "Tag", &scope, tag_ids).await
+}
+
+async fn check_ids<I: PublicId>(
+    db_conn: &mut SqliteConnection,
+    table: &str,
+    noun: &str,
+    scope: &str,
+    ids: impl IntoIterator<Item = I>,
+) -> Result<(), SQLXError> {
+    let mut missing = ids.into_iter().collect::<BTreeSet<I>>();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let query = format!(
+        "SELECT id FROM {} WHERE id IN ({}) AND {};",
+        table,
+        missing
+            .iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        scope
+    );
+    for row in sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await? {
+        missing.remove(&I::from_key(row.get("id")));
+    }
+
+    match missing.first() {
+        Some(id) => Err(InvalidArgument(format!(
+            "{} {} doesn't exist",
+            noun,
+            id.public()
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::{
+        db::sqlx::{
+            delete_lists, delete_todos, insert_memberships, insert_sets, query_all_lists,
+            query_all_todos, query_shared_lists, update_lists, update_memberships,
+        },
+        types::{
+            CreateMembership, CreateSet, ListID, Role, ToDoQueryTarget, ToDoReadOptions,
+            UpdateList, UpdateMembership, UserID,
+        },
+    };
+
+    use super::super::test_db::setup_test_db;
+
+    #[actix_web::test]
+    async fn users_only_reach_their_own_data() {
+        let db = setup_test_db().await;
+
+        assert!(
+            query_all_lists(db.clone(), UserID(2))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            query_all_todos(db.clone(), UserID(2), ToDoReadOptions::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let renamed = update_lists(
+            db.clone(),
+            UserID(2),
+            vec![UpdateList {
+                list_id: ListID(1),
+                title: "Mine Now".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        assert!(renamed.is_empty());
+
+        let foreign_list = insert_sets(
+            db.clone(),
+            UserID(2),
+            vec![CreateSet {
+                list_id: ListID(1),
+                parent_id: None,
+                title: "Sneaky".to_string(),
+            }],
+        )
+        .await;
+        assert!(foreign_list.is_err());
+
+        let deleted = delete_todos(
+            db.clone(),
+            UserID(2),
+            HashSet::from([ToDoQueryTarget::List(ListID(1))]),
+        )
+        .await
+        .unwrap();
+        assert!(deleted.is_empty());
+
+        let lists = query_all_lists(db.clone(), UserID(1)).await.unwrap();
+        assert_eq!(lists.len(), 2);
+        assert!(lists.iter().all(|list| list.title != "Mine Now"));
+        assert_eq!(
+            query_all_todos(db, UserID(1), ToDoReadOptions::default())
+                .await
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+
+    #[actix_web::test]
+    async fn unknown_users_are_refused_like_the_owner() {
+        let db = setup_test_db().await;
+        let share_with = |username: &str| {
+            insert_memberships(
+                db.clone(),
+                UserID(1),
+                vec![CreateMembership {
+                    list_id: ListID(1),
+                    username: username.to_string(),
+                    role: Role::Viewer,
+                }],
+            )
+        };
+
+        let unknown = share_with("carol").await.unwrap_err().to_string();
+        let owner = share_with("alice").await.unwrap_err().to_string();
+        assert_eq!(unknown, owner);
+    }
+
+    #[actix_web::test]
+    async fn members_read_or_write_by_role() {
+        let db = setup_test_db().await;
+        let whole_list = || HashSet::from([ToDoQueryTarget::List(ListID(1))]);
+
+        insert_memberships(
+            db.clone(),
+            UserID(1),
+            vec![CreateMembership {
+                list_id: ListID(1),
+                username: "bob".to_string(),
+                role: Role::Viewer,
+            }],
+        )
+        .await
+        .unwrap();
+
+        let shared = query_shared_lists(db.clone(), UserID(2)).await.unwrap();
+        assert_eq!(shared.len(), 1);
+        assert_eq!(
+            (shared[0].list.id, shared[0].role),
+            (ListID(1), Role::Viewer)
+        );
+        assert_eq!(
+            query_all_todos(db.clone(), UserID(2), ToDoReadOptions::default())
+                .await
+                .unwrap()
+                .len(),
+            4
+        );
+
+        let viewer_deleted = delete_todos(db.clone(), UserID(2), whole_list())
+            .await
+            .unwrap();
+        assert!(viewer_deleted.is_empty());
+
+        let not_owner = update_memberships(
+            db.clone(),
+            UserID(2),
+            vec![UpdateMembership {
+                list_id: ListID(1),
+                user_id: UserID(2),
+                role: Role::Editor,
+            }],
+        )
+        .await
+        .unwrap();
+        assert!(not_owner.is_empty());
+        update_memberships(
+            db.clone(),
+            UserID(1),
+            vec![UpdateMembership {
+                list_id: ListID(1),
+                user_id: UserID(2),
+                role: Role::Editor,
+            }],
+        )
+        .await
+        .unwrap();
+
+        let list_deleted = delete_lists(db.clone(), UserID(2), HashSet::from([ListID(1)]))
+            .await
+            .unwrap();
+        assert!(list_deleted.is_empty());
+        let editor_deleted = delete_todos(db.clone(), UserID(2), whole_list())
+            .await
+            .unwrap();
+        assert_eq!(editor_deleted.len(), 4);
+    }
+}
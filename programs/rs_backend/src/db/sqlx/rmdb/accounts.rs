@@ -0,0 +1,245 @@
+use std::sync::LazyLock;
+
+use actix_web::web::{self, Data};
+use argon2::{
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{
+    Error::{self as SQLXError, InvalidArgument},
+    Pool, Row, Sqlite,
+};
+use uuid::Uuid;
+
+use crate::types::{CreateSession, CreateUser, Session, User, UserID};
+
+use super::rows::user_from_row;
+
+/// How long a login lasts.
+const SESSION_LIFETIME: Duration = Duration::days(30);
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// The hash of a password nobody knows, checked when there's no user to check, so a login takes
+/// as long whether or not the username exists.
+static DUMMY_HASH: LazyLock<String> = LazyLock::new(|| {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(Uuid::new_v4().as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .unwrap_or_default()
+});
+
+/// What session and share link tokens are stored and looked up as.
+pub(super) fn token_hash(token: &str) -> Vec<u8> {
+    Sha256::digest(token.as_bytes()).to_vec()
+}
+
+pub async fn insert_user(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    entry: CreateUser,
+) -> Result<User, SQLXError> {
+    if entry.username.trim().is_empty() {
+        return Err(InvalidArgument("Username can't be empty".to_string()));
+    }
+    if entry.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(InvalidArgument(format!(
+            "Password must be at least {} characters long",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+
+    // Hashing is slow on purpose, so it's kept off the async workers.
+    let password_hash = web::block(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(entry.password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await
+    .map_err(|err| SQLXError::Protocol(err.to_string()))?
+    .map_err(|err| SQLXError::Protocol(err.to_string()))?;
+
+    let mut db_conn = db_conn_pool.acquire().await?;
+    let row = sqlx::query("INSERT INTO Users (username, password_hash) VALUES (?, ?) RETURNING *;")
+        .bind(entry.username.trim())
+        .bind(password_hash)
+        .fetch_one(&mut *db_conn)
+        .await?;
+
+    Ok(user_from_row(&row))
+}
+
+/// Logs a user in. Returns `None` when the username or password is wrong, without telling
+/// which.
+pub async fn insert_session(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    entry: CreateSession,
+) -> Result<Option<Session>, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let row = sqlx::query("SELECT * FROM Users WHERE username = ?;")
+        .bind(entry.username.trim())
+        .fetch_optional(&mut *db_conn)
+        .await?;
+    let (user, password_hash) = match row {
+        Some(row) => (Some(user_from_row(&row)), row.get("password_hash")),
+        None => (None, String::new()),
+    };
+
+    let verified = web::block(move || {
+        let verify = |hash| {
+            Argon2::default()
+                .verify_password(entry.password.as_bytes(), &hash)
+                .is_ok()
+        };
+        match PasswordHash::new(&password_hash) {
+            Ok(hash) => verify(hash),
+            // Unknown users, and users without a usable hash, are turned away only after the
+            // same work as a wrong password.
+            Err(_) => {
+                if let Ok(hash) = PasswordHash::new(&DUMMY_HASH) {
+                    verify(hash);
+                }
+                false
+            }
+        }
+    })
+    .await
+    .map_err(|err| SQLXError::Protocol(err.to_string()))?;
+    let Some(user) = user.filter(|_| verified) else {
+        return Ok(None);
+    };
+
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let expires_at = Utc::now() + SESSION_LIFETIME;
+    sqlx::query("INSERT INTO Sessions (token_hash, user_id, expires_at) VALUES (?, ?, ?);")
+        .bind(token_hash(&token))
+        .bind(user.id)
+        .bind(expires_at)
+        .execute(&mut *db_conn)
+        .await?;
+
+    Ok(Some(Session {
+        token,
+        user,
+        expires_at,
+    }))
+}
+
+/// Logs out. Expired sessions of any user are cleaned up along the way.
+pub async fn delete_session(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    token: String,
+) -> Result<(), SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    sqlx::query(
+        "DELETE FROM Sessions WHERE token_hash = ? OR julianday(expires_at) <= julianday('now');",
+    )
+    .bind(token_hash(&token))
+    .execute(&mut *db_conn)
+    .await?;
+
+    Ok(())
+}
+
+/// The user logged in with `token`, if the session exists and hasn't expired.
+pub async fn find_session_user(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    token: &str,
+) -> Result<Option<UserID>, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    Ok(sqlx::query(
+        "SELECT user_id FROM Sessions WHERE token_hash = ? AND julianday(expires_at) > julianday('now');",
+    )
+    .bind(token_hash(token))
+    .fetch_optional(&mut *db_conn)
+    .await?
+    .map(|row| row.get("user_id")))
+}
+
+pub async fn query_user(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<User, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let row = sqlx::query("SELECT * FROM Users WHERE id = ?;")
+        .bind(user_id)
+        .fetch_one(&mut *db_conn)
+        .await?;
+
+    Ok(user_from_row(&row))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use super::super::test_db::setup_test_db;
+
+    #[actix_web::test]
+    async fn sessions_need_the_right_password() {
+        let db = setup_test_db().await;
+        let credentials = |password: &str| CreateSession {
+            username: "carol".to_string(),
+            password: password.to_string(),
+        };
+
+        let too_short = insert_user(
+            db.clone(),
+            CreateUser {
+                username: "carol".to_string(),
+                password: "short".to_string(),
+            },
+        )
+        .await;
+        assert!(too_short.is_err());
+
+        let carol = insert_user(
+            db.clone(),
+            CreateUser {
+                username: "carol".to_string(),
+                password: "correct horse".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let wrong = insert_session(db.clone(), credentials("wrong horse"))
+            .await
+            .unwrap();
+        assert!(wrong.is_none());
+        let unknown = CreateSession {
+            username: "dave".to_string(),
+            password: "correct horse".to_string(),
+        };
+        assert!(insert_session(db.clone(), unknown).await.unwrap().is_none());
+
+        let session = insert_session(db.clone(), credentials("correct horse"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.user, carol);
+        assert_eq!(
+            find_session_user(db.clone(), &session.token).await.unwrap(),
+            Some(carol.id)
+        );
+
+        // Only the hash of the token is kept.
+        let stored: Vec<u8> = sqlx::query_scalar("SELECT token_hash FROM Sessions;")
+            .fetch_one(&**db)
+            .await
+            .unwrap();
+        assert_eq!(stored, Sha256::digest(session.token.as_bytes()).to_vec());
+
+        delete_session(db.clone(), session.token.clone())
+            .await
+            .unwrap();
+        assert_eq!(find_session_user(db, &session.token).await.unwrap(), None);
+    }
+}
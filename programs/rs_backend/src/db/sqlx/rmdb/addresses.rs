@@ -0,0 +1,339 @@
+use sqlx::{Error as SQLXError, Row, SqliteConnection};
+
+use crate::types::{ListID, PublicId, Set, SetAddress, SetID, ToDo, ToDoAddress, ToDoID};
+
+use super::{
+    nesting::subtree_ids,
+    rows::{set_from_row, todo_from_row},
+};
+
+/// A query selecting the ids of the sets at `address`, for use in `id IN (...)`.
+pub(super) fn set_address_ids(address: &SetAddress) -> String {
+    match address {
+        SetAddress::WholeList(list_id) => {
+            format!("SELECT id FROM Sets WHERE list_id = {}", list_id.key())
+        }
+        SetAddress::Singular(list_id, sid) => format!(
+            "SELECT id FROM Sets WHERE list_id = {} AND sid = {}",
+            list_id.key(),
+            sid
+        ),
+    }
+}
+
+/// A query selecting the ids of the to dos at `address`, for use in `id IN (...)`.
+pub(super) fn todo_address_ids(address: &ToDoAddress) -> String {
+    match address {
+        ToDoAddress::WholeList(list_id) => {
+            format!("SELECT id FROM Todos WHERE list_id = {}", list_id.key())
+        }
+        ToDoAddress::WholeSet(list_id, sid) => format!(
+            "SELECT id FROM Todos WHERE set_id IN ({})",
+            set_address_ids(&SetAddress::Singular(*list_id, *sid))
+        ),
+        ToDoAddress::Singular(list_id, Some(sid), tdid) => format!(
+            "SELECT id FROM Todos WHERE list_id = {} AND tdid = {} AND set_id IN ({})",
+            list_id.key(),
+            tdid,
+            set_address_ids(&SetAddress::Singular(*list_id, *sid))
+        ),
+        ToDoAddress::Singular(list_id, None, tdid) => format!(
+            "SELECT id FROM Todos WHERE list_id = {} AND tdid = {} AND set_id IS NULL",
+            list_id.key(),
+            tdid
+        ),
+    }
+}
+
+/// Turns `condition` (a `WHERE` condition on `table`) into one matching the ids it matches now, so
+/// it keeps matching the same rows once their numbers change.
+pub(super) async fn pin_rows(
+    db_conn: &mut SqliteConnection,
+    table: &str,
+    condition: &str,
+) -> Result<String, SQLXError> {
+    let query = format!("SELECT id FROM {} WHERE {};", table, condition);
+    let ids = sqlx::query(&query)
+        .fetch_all(&mut *db_conn)
+        .await?
+        .iter()
+        .map(|row| row.get::<i64, _>("id").to_string())
+        .collect::<Vec<String>>();
+    Ok(format!("id IN ({})", ids.join(", ")))
+}
+
+/// Clears the numbers of the sets matching `moved` (a `WHERE` condition on Sets) that are about to
+/// move to `list_id`, of the sets nested inside them and of the to dos in any of them. They all
+/// move along, and until the triggers number them in their new list, their old numbers could
+/// collide with the ones already taken there.
+pub(super) async fn unnumber_sets(
+    db_conn: &mut SqliteConnection,
+    moved: &str,
+    list_id: ListID,
+) -> Result<(), SQLXError> {
+    let subtree = subtree_ids(&format!(
+        "SELECT id FROM Sets WHERE {} AND list_id != {}",
+        moved,
+        list_id.key()
+    ));
+    for query in [
+        format!(
+            "UPDATE Todos SET tdid = NULL WHERE set_id IN ({});",
+            subtree
+        ),
+        format!("UPDATE Sets SET sid = NULL WHERE id IN ({});", subtree),
+    ] {
+        sqlx::query(&query).execute(&mut *db_conn).await?;
+    }
+    Ok(())
+}
+
+/// Like [`unnumber_sets`], for the to dos matching `moved` (a `WHERE` condition on Todos).
+pub(super) async fn unnumber_todos(
+    db_conn: &mut SqliteConnection,
+    moved: &str,
+    list_id: ListID,
+) -> Result<(), SQLXError> {
+    let query = format!(
+        "UPDATE Todos SET tdid = NULL WHERE {} AND list_id != {};",
+        moved,
+        list_id.key()
+    );
+    sqlx::query(&query).execute(&mut *db_conn).await?;
+    Ok(())
+}
+
+/// Reads sets again after they were made or moved to another list. Their numbers are handed out
+/// by triggers, and `RETURNING` only sees the rows from before those ran.
+pub(super) async fn reread_sets(
+    db_conn: &mut SqliteConnection,
+    set_ids: &[SetID],
+) -> Result<Vec<Set>, SQLXError> {
+    let query = format!(
+        "SELECT * FROM Sets WHERE id IN ({}) ORDER BY id;",
+        keys(set_ids)
+    );
+    let rows = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+    Ok(rows.iter().map(set_from_row).collect())
+}
+
+/// Like [`reread_sets`], for to dos. Their tags and blockers aren't read.
+pub(super) async fn reread_todos(
+    db_conn: &mut SqliteConnection,
+    todo_ids: &[ToDoID],
+) -> Result<Vec<ToDo>, SQLXError> {
+    let query = format!(
+        "SELECT * FROM Todos WHERE id IN ({}) ORDER BY id;",
+        keys(todo_ids)
+    );
+    let rows = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+    Ok(rows.iter().map(todo_from_row).collect())
+}
+
+fn keys(ids: &[impl PublicId]) -> String {
+    ids.iter()
+        .map(|id| id.key().to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use sqlx::Row;
+
+    use crate::{
+        db::sqlx::{
+            delete_sets, delete_todos, insert_sets, insert_todos, move_todos, query_sets,
+            query_todos, update_sets,
+        },
+        types::{
+            CreateSet, CreateToDo, MoveToDo, Placement, SetQueryTarget, ToDoQueryTarget,
+            ToDoReadOptions, UpdateSet, UserID,
+        },
+    };
+
+    use super::super::test_db::setup_test_db;
+    use super::*;
+
+    #[actix_web::test]
+    async fn addresses_find_entries_by_their_numbers_within_a_list() {
+        let db = setup_test_db().await;
+        let todos_at = |address| {
+            let db = db.clone();
+            async move {
+                query_todos(
+                    db,
+                    UserID(1),
+                    HashSet::from([ToDoQueryTarget::Address(address)]),
+                    ToDoReadOptions::default(),
+                )
+                .await
+                .unwrap()
+                .iter()
+                .map(|todo| todo.id)
+                .collect::<Vec<ToDoID>>()
+            }
+        };
+
+        let sets = query_sets(
+            db.clone(),
+            UserID(1),
+            HashSet::from([SetQueryTarget::Address(SetAddress::Singular(ListID(1), 2))]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            sets.iter().map(|set| set.id).collect::<Vec<_>>(),
+            vec![SetID(3)]
+        );
+
+        let singular = ToDoAddress::Singular(ListID(1), Some(1), 2);
+        assert_eq!(todos_at(singular).await, vec![ToDoID(2)]);
+        let loose = ToDoAddress::Singular(ListID(1), None, 3);
+        assert_eq!(todos_at(loose).await, vec![ToDoID(3)]);
+        let wrong_set = ToDoAddress::Singular(ListID(1), None, 1);
+        assert!(todos_at(wrong_set).await.is_empty());
+        assert_eq!(
+            todos_at(ToDoAddress::WholeSet(ListID(1), 2)).await,
+            vec![ToDoID(4)]
+        );
+
+        let new = |title: &str| CreateToDo {
+            list_id: ListID(2),
+            set_id: None,
+            title: title.to_string(),
+            complete: None,
+            due_date: None,
+            tags: Default::default(),
+            blocked_by: Default::default(),
+            notes: None,
+            priority: None,
+            auto_complete: None,
+        };
+        let created = insert_todos(db.clone(), UserID(1), vec![new("Dig"), new("Plant")])
+            .await
+            .unwrap();
+        let mut numbers = created.iter().map(|todo| todo.tdid).collect::<Vec<_>>();
+        numbers.sort();
+        assert_eq!(numbers, vec![1, 2]);
+
+        // Set 1 takes set 3 and to dos 1, 2 and 4 along to list 2, where they're numbered again.
+        let moved = update_sets(
+            db.clone(),
+            UserID(1),
+            vec![UpdateSet {
+                target: SetQueryTarget::Set(SetID(1)),
+                list_id: Some(ListID(2)),
+                parent_id: None,
+                title: None,
+            }],
+        )
+        .await
+        .unwrap();
+        assert!(moved.iter().all(|set| set.sid > 1));
+        let numbers = sqlx::query(
+            "SELECT (SELECT COUNT(DISTINCT sid) FROM Sets WHERE list_id = 2) AS sids, \
+                    (SELECT COUNT(DISTINCT tdid) FROM Todos WHERE list_id = 2) AS tdids;",
+        )
+        .fetch_one(&**db)
+        .await
+        .unwrap();
+        assert_eq!(numbers.get::<i64, _>("sids"), 3);
+        assert_eq!(numbers.get::<i64, _>("tdids"), 5);
+
+        let deleted = delete_todos(
+            db.clone(),
+            UserID(1),
+            HashSet::from([ToDoQueryTarget::Address(ToDoAddress::WholeList(ListID(2)))]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(deleted.len(), 5);
+        assert_eq!(
+            todos_at(ToDoAddress::WholeList(ListID(1))).await,
+            vec![ToDoID(3)]
+        );
+    }
+
+    fn new_todo(list_id: ListID, title: &str) -> CreateToDo {
+        CreateToDo {
+            list_id,
+            set_id: None,
+            title: title.to_string(),
+            complete: None,
+            due_date: None,
+            tags: Default::default(),
+            blocked_by: Default::default(),
+            notes: None,
+            priority: None,
+            auto_complete: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn numbers_arent_handed_out_twice() {
+        let db = setup_test_db().await;
+
+        // The highest numbers of list 1 go away, and aren't given to what's made next.
+        delete_todos(
+            db.clone(),
+            UserID(1),
+            [ToDoQueryTarget::ToDo(ToDoID(4))].into(),
+        )
+        .await
+        .unwrap();
+        delete_sets(
+            db.clone(),
+            UserID(1),
+            [SetQueryTarget::Set(SetID(3))].into(),
+        )
+        .await
+        .unwrap();
+        let todo = insert_todos(db.clone(), UserID(1), vec![new_todo(ListID(1), "Dust")])
+            .await
+            .unwrap();
+        assert_eq!(todo.iter().map(|todo| todo.tdid).collect::<Vec<_>>(), [5]);
+        let set = insert_sets(
+            db.clone(),
+            UserID(1),
+            vec![CreateSet {
+                list_id: ListID(1),
+                parent_id: None,
+                title: "Kitchen".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        assert_eq!(set.iter().map(|set| set.sid).collect::<Vec<_>>(), [3]);
+
+        // A to do moved away and back again is numbered anew each time.
+        let dig = insert_todos(db.clone(), UserID(1), vec![new_todo(ListID(2), "Dig")])
+            .await
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap()
+            .id;
+        let move_after = |todo_id, anchor| {
+            move_todos(
+                db.clone(),
+                UserID(1),
+                vec![MoveToDo {
+                    todo_id,
+                    to: Placement::After(anchor),
+                }],
+            )
+        };
+        let moved = move_after(ToDoID(3), dig).await.unwrap();
+        assert_eq!(moved.iter().map(|todo| todo.tdid).collect::<Vec<_>>(), [2]);
+        let moved = move_after(ToDoID(3), ToDoID(1)).await.unwrap();
+        assert_eq!(moved.iter().map(|todo| todo.tdid).collect::<Vec<_>>(), [6]);
+
+        let taken = sqlx::query("UPDATE Todos SET tdid = 1 WHERE id = 2;")
+            .execute(&**db)
+            .await;
+        assert!(taken.is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use actix_web::web::Data;
+use chrono::{DateTime, Utc};
+use sqlx::{Error as SQLXError, Pool, Row, Sqlite};
+
+/// The tables each read route draws on, including the ones that decide which rows a user can
+/// read. A change to any of them can change what the read returns.
+pub const LISTS_TABLES: &[&str] = &["Lists", "ListMembers"];
+pub const SETS_TABLES: &[&str] = &["Sets", "ListMembers"];
+pub const TODOS_TABLES: &[&str] = &[
+    "Todos",
+    "Sets",
+    "TodoTags",
+    "TodoDependencies",
+    "ChecklistItems",
+    "ListMembers",
+];
+pub const TAGS_TABLES: &[&str] = &["Tags"];
+pub const CHECKLIST_ITEMS_TABLES: &[&str] = &["ChecklistItems", "Todos", "ListMembers"];
+
+/// How often a group of tables has changed, and when they last did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Changes {
+    /// The sum of the tables' counters. Counters only go up, so it changes whenever any of them
+    /// does.
+    pub version: i64,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Reads the change counters of `tables`, which are kept by triggers on each of them.
+pub async fn query_changes(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    tables: &[&str],
+) -> Result<Changes, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "SELECT COALESCE(SUM(version), 0) AS version, MAX(changed_at) AS changed_at FROM Changes WHERE name IN ({});",
+        vec!["?"; tables.len()].join(", ")
+    );
+    let row = tables
+        .iter()
+        .fold(sqlx::query(&query), |query, table| query.bind(*table))
+        .fetch_one(&mut *db_conn)
+        .await?;
+
+    Ok(Changes {
+        version: row.get("version"),
+        changed_at: row
+            .get::<Option<DateTime<Utc>>, _>("changed_at")
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        db::sqlx::{delete_todos, setup_test_db, update_lists},
+        types::{ListID, ToDoID, ToDoQueryTarget, UpdateList, UserID},
+    };
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn writes_count_as_changes_of_their_tables() {
+        let db = setup_test_db().await;
+        let lists = query_changes(db.clone(), LISTS_TABLES).await.unwrap();
+        let todos = query_changes(db.clone(), TODOS_TABLES).await.unwrap();
+
+        update_lists(
+            db.clone(),
+            UserID(1),
+            vec![UpdateList {
+                list_id: ListID(1),
+                title: "House".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        let renamed = query_changes(db.clone(), LISTS_TABLES).await.unwrap();
+        assert!(renamed.version > lists.version);
+        assert!(renamed.changed_at >= lists.changed_at);
+        assert_eq!(
+            query_changes(db.clone(), TODOS_TABLES).await.unwrap(),
+            todos
+        );
+
+        // Deleting a to do takes its checklist and links along, which count as well.
+        delete_todos(
+            db.clone(),
+            UserID(1),
+            [ToDoQueryTarget::ToDo(ToDoID(1))].into(),
+        )
+        .await
+        .unwrap();
+        assert!(
+            query_changes(db.clone(), TODOS_TABLES)
+                .await
+                .unwrap()
+                .version
+                > todos.version
+        );
+        assert_eq!(query_changes(db, LISTS_TABLES).await.unwrap(), renamed);
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use sqlx::{Error as SQLXError, SqliteConnection};
+
+use crate::types::{ChecklistItem, ToDo, ToDoID};
+
+use super::rows::{NOW, checklist_item_from_row};
+
+/// Embeds the checklist of each of `todos`, keeping their order.
+pub(super) async fn with_checklists<Out: FromIterator<ToDo>>(
+    db_conn: &mut SqliteConnection,
+    todos: impl IntoIterator<Item = ToDo>,
+) -> Result<Out, SQLXError> {
+    let mut todos: Vec<ToDo> = todos.into_iter().collect();
+    if todos.is_empty() {
+        return Ok(todos.into_iter().collect());
+    }
+
+    let query = format!(
+        "SELECT * FROM ChecklistItems WHERE todo_id IN ({}) ORDER BY position, id;",
+        joined_ids(todos.iter().map(|todo| todo.id))
+    );
+    let mut items: HashMap<ToDoID, Vec<ChecklistItem>> = HashMap::new();
+    for row in sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await? {
+        let item = checklist_item_from_row(&row);
+        items.entry(item.todo_id).or_default().push(item);
+    }
+
+    for todo in &mut todos {
+        todo.checklist = Some(items.remove(&todo.id).unwrap_or_default());
+    }
+
+    Ok(todos.into_iter().collect())
+}
+
+/// Completes those of `todo_ids` that asked to be auto completed and whose checklist is now
+/// entirely done. An empty checklist never completes its to do.
+pub(super) async fn auto_complete(
+    db_conn: &mut SqliteConnection,
+    todo_ids: impl IntoIterator<Item = ToDoID>,
+) -> Result<(), SQLXError> {
+    let todo_ids = joined_ids(todo_ids);
+    if todo_ids.is_empty() {
+        return Ok(());
+    }
+
+    let query = format!(
+        "UPDATE Todos SET (complete, completed_at, updated_at) = (1, {now}, {now}) \
+         WHERE id IN ({}) AND auto_complete AND NOT complete \
+         AND EXISTS (SELECT 1 FROM ChecklistItems WHERE todo_id = Todos.id) \
+         AND NOT EXISTS (SELECT 1 FROM ChecklistItems WHERE todo_id = Todos.id AND NOT done);",
+        todo_ids,
+        now = NOW
+    );
+    sqlx::query(query.as_str()).execute(&mut *db_conn).await?;
+    Ok(())
+}
+
+fn joined_ids(ids: impl IntoIterator<Item = ToDoID>) -> String {
+    ids.into_iter()
+        .map(|id| id.key().to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -8,17 +8,26 @@ use sqlx::{
 
 use crate::{
     api::{
-        DeleteListsRequest, DeleteListsResponse, DeleteSetsRequest, DeleteSetsResponse,
-        DeleteToDosRequest, DeleteToDosResponse,
+        DeleteChecklistItemsRequest, DeleteChecklistItemsResponse, DeleteListsRequest,
+        DeleteListsResponse, DeleteSetsRequest, DeleteSetsResponse, DeleteTagsRequest,
+        DeleteTagsResponse, DeleteToDosRequest, DeleteToDosResponse,
     },
-    types::{SetQueryTarget, ToDoQueryTarget},
+    types::{SetQueryTarget, ToDoID, ToDoQueryTarget, UserID},
+};
+
+use super::{
+    access::{writable_lists, writable_todos},
+    addresses::{set_address_ids, todo_address_ids},
+    checklists::auto_complete,
+    nesting::subtree_ids,
 };
 
 pub async fn delete_lists(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     adds: DeleteListsRequest,
 ) -> Result<DeleteListsResponse, SQLXError> {
-    if adds.len() == 0 {
+    if adds.is_empty() {
         return Err(InvalidArgument(
             "Caller Provided no entries to the database".to_string(),
         ));
@@ -27,11 +36,12 @@ pub async fn delete_lists(
     let mut db_conn = db_conn_pool.acquire().await?;
 
     let query = format!(
-        "DELETE FROM Lists WHERE id IN ({}) RETURNING id;",
+        "DELETE FROM Lists WHERE id IN ({}) AND owner_id = {} RETURNING id;",
         adds.into_iter()
-            .map(|id| id.to_string())
+            .map(|id| id.key().to_string())
             .collect::<Vec<String>>()
-            .join(", ")
+            .join(", "),
+        user_id.key()
     );
 
     let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
@@ -46,9 +56,10 @@ pub async fn delete_lists(
 
 pub async fn delete_sets(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     adds: DeleteSetsRequest,
 ) -> Result<DeleteSetsResponse, SQLXError> {
-    if adds.len() == 0 {
+    if adds.is_empty() {
         return Err(InvalidArgument(
             "Caller Provided no entries to the database".to_string(),
         ));
@@ -56,33 +67,48 @@ pub async fn delete_sets(
 
     let mut db_conn = db_conn_pool.acquire().await?;
 
-    let (whole_list_ids, singular_ids) = {
-        let mut acc =
-            adds.into_iter()
-                .fold((String::new(), String::new()), |(mut wl, mut s), ele| {
-                    match ele {
-                        SetQueryTarget::List(list_id) => {
-                            wl.push_str(&list_id.to_string());
-                            wl.push_str(", ");
-                        }
-                        SetQueryTarget::Set(set_id) => {
-                            s.push_str(&set_id.to_string());
-                            s.push_str(", ");
-                        }
+    let (whole_list_ids, singular_ids, subtree_root_ids, addressed_ids) = {
+        let mut acc = adds.into_iter().fold(
+            (String::new(), String::new(), String::new(), String::new()),
+            |(mut wl, mut s, mut st, mut a), ele| {
+                match ele {
+                    SetQueryTarget::List(list_id) => {
+                        wl.push_str(&list_id.key().to_string());
+                        wl.push_str(", ");
+                    }
+                    SetQueryTarget::Set(set_id) => {
+                        s.push_str(&set_id.key().to_string());
+                        s.push_str(", ");
+                    }
+                    SetQueryTarget::Subtree(set_id) => {
+                        st.push_str(&set_id.key().to_string());
+                        st.push_str(", ");
                     }
+                    SetQueryTarget::Address(address) => {
+                        a.push_str(&set_address_ids(&address));
+                        a.push_str(" UNION ");
+                    }
+                }
 
-                    (wl, s)
-                });
+                (wl, s, st, a)
+            },
+        );
 
         acc.0 = acc.0.trim_end_matches(", ").to_string();
         acc.1 = acc.1.trim_end_matches(", ").to_string();
+        acc.2 = acc.2.trim_end_matches(", ").to_string();
+        acc.3 = acc.3.trim_end_matches(" UNION ").to_string();
 
         acc
     };
 
     let query = format!(
-        "DELETE FROM Sets WHERE list_id IN ({}) OR id IN ({}) RETURNING id;",
-        whole_list_ids, singular_ids
+        "DELETE FROM Sets WHERE (list_id IN ({}) OR id IN ({}) OR id IN ({}) OR id IN ({})) AND list_id IN ({}) RETURNING id;",
+        whole_list_ids,
+        singular_ids,
+        subtree_ids(&subtree_root_ids),
+        addressed_ids,
+        writable_lists(user_id)
     );
 
     let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
@@ -97,9 +123,10 @@ pub async fn delete_sets(
 
 pub async fn delete_todos(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     adds: DeleteToDosRequest,
 ) -> Result<DeleteToDosResponse, SQLXError> {
-    if adds.len() == 0 {
+    if adds.is_empty() {
         return Err(InvalidArgument(
             "Caller Provided no entries to the database".to_string(),
         ));
@@ -107,39 +134,99 @@ pub async fn delete_todos(
 
     let mut db_conn = db_conn_pool.acquire().await?;
 
-    let (whole_list_ids, whole_set_ids, singular_ids) = {
+    let (whole_list_ids, whole_set_ids, singular_ids, subtree_root_ids, tag_ids, addressed_ids) = {
         let mut acc = adds.into_iter().fold(
-            (String::new(), String::new(), String::new()),
-            |(mut wl, mut ws, mut s), ele| {
+            (
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ),
+            |(mut wl, mut ws, mut s, mut st, mut t, mut a), ele| {
                 match ele {
                     ToDoQueryTarget::List(list_id) => {
-                        wl.push_str(&list_id.to_string());
+                        wl.push_str(&list_id.key().to_string());
                         wl.push_str(", ");
                     }
                     ToDoQueryTarget::Set(set_id) => {
-                        ws.push_str(&set_id.to_string());
+                        ws.push_str(&set_id.key().to_string());
                         ws.push_str(", ");
                     }
                     ToDoQueryTarget::ToDo(todo_id) => {
-                        s.push_str(&todo_id.to_string());
+                        s.push_str(&todo_id.key().to_string());
                         s.push_str(", ");
                     }
+                    ToDoQueryTarget::Subtree(set_id) => {
+                        st.push_str(&set_id.key().to_string());
+                        st.push_str(", ");
+                    }
+                    ToDoQueryTarget::Tag(tag_id) => {
+                        t.push_str(&tag_id.key().to_string());
+                        t.push_str(", ");
+                    }
+                    ToDoQueryTarget::Address(address) => {
+                        a.push_str(&todo_address_ids(&address));
+                        a.push_str(" UNION ");
+                    }
                 }
 
-                (wl, ws, s)
+                (wl, ws, s, st, t, a)
             },
         );
 
         acc.0 = acc.0.trim_end_matches(", ").to_string();
         acc.1 = acc.1.trim_end_matches(", ").to_string();
         acc.2 = acc.2.trim_end_matches(", ").to_string();
+        acc.3 = acc.3.trim_end_matches(", ").to_string();
+        acc.4 = acc.4.trim_end_matches(", ").to_string();
+        acc.5 = acc.5.trim_end_matches(" UNION ").to_string();
 
         acc
     };
 
     let query: String = format!(
-        "DELETE FROM Todos WHERE list_id IN ({}) OR set_id IN ({}) OR id IN ({}) RETURNING id;",
-        whole_list_ids, whole_set_ids, singular_ids
+        "DELETE FROM Todos WHERE (list_id IN ({}) OR set_id IN ({}) OR id IN ({}) OR set_id IN ({}) OR id IN (SELECT todo_id FROM TodoTags WHERE tag_id IN ({})) OR id IN ({})) AND list_id IN ({}) RETURNING id;",
+        whole_list_ids,
+        whole_set_ids,
+        singular_ids,
+        subtree_ids(&subtree_root_ids),
+        tag_ids,
+        addressed_ids,
+        writable_lists(user_id)
+    );
+
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut deleted_ids = HashSet::new();
+    for row in query_result {
+        deleted_ids.insert(row.get("id"));
+    }
+
+    Ok(deleted_ids)
+}
+
+pub async fn delete_tags(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: DeleteTagsRequest,
+) -> Result<DeleteTagsResponse, SQLXError> {
+    if adds.is_empty() {
+        return Err(InvalidArgument(
+            "Caller Provided no entries to the database".to_string(),
+        ));
+    }
+
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "DELETE FROM Tags WHERE id IN ({}) AND owner_id = {} RETURNING id;",
+        adds.into_iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        user_id.key()
     );
 
     let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
@@ -151,3 +238,42 @@ pub async fn delete_todos(
 
     Ok(deleted_ids)
 }
+
+pub async fn delete_checklist_items(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: DeleteChecklistItemsRequest,
+) -> Result<DeleteChecklistItemsResponse, SQLXError> {
+    if adds.is_empty() {
+        return Err(InvalidArgument(
+            "Caller Provided no entries to the database".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+
+    let query = format!(
+        "DELETE FROM ChecklistItems WHERE id IN ({}) AND todo_id IN ({}) RETURNING id, todo_id;",
+        adds.into_iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        writable_todos(user_id)
+    );
+
+    let query_result = sqlx::query(query.as_str())
+        .fetch_all(&mut *transaction)
+        .await?;
+
+    let mut deleted_ids = HashSet::new();
+    let mut todo_ids: HashSet<ToDoID> = HashSet::new();
+    for row in query_result {
+        deleted_ids.insert(row.get("id"));
+        todo_ids.insert(row.get("todo_id"));
+    }
+
+    // Removing the last unfinished item leaves a checklist that's entirely done.
+    auto_complete(&mut transaction, todo_ids).await?;
+    transaction.commit().await?;
+    Ok(deleted_ids)
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeSet;
+
+use sqlx::{
+    Error::{self as SQLXError, InvalidArgument},
+    Row, SqliteConnection,
+};
+
+use crate::types::ToDoID;
+
+/// Blocks every one of `todo_ids` on every one of `blocker_ids`. Dependencies that already exist
+/// are left alone, and ones that would close a cycle are rejected.
+pub(super) async fn add_blockers(
+    db_conn: &mut SqliteConnection,
+    todo_ids: &[ToDoID],
+    blocker_ids: &BTreeSet<ToDoID>,
+) -> Result<(), SQLXError> {
+    for todo_id in todo_ids {
+        for blocker_id in blocker_ids {
+            check_dependency(db_conn, *todo_id, *blocker_id).await?;
+            sqlx::query(
+                "INSERT OR IGNORE INTO TodoDependencies (todo_id, blocker_id) VALUES (?, ?);",
+            )
+            .bind(todo_id)
+            .bind(blocker_id)
+            .execute(&mut *db_conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) async fn remove_blockers(
+    db_conn: &mut SqliteConnection,
+    todo_ids: &[ToDoID],
+    blocker_ids: &BTreeSet<ToDoID>,
+) -> Result<(), SQLXError> {
+    if todo_ids.is_empty() || blocker_ids.is_empty() {
+        return Ok(());
+    }
+
+    let query = format!(
+        "DELETE FROM TodoDependencies WHERE todo_id IN ({}) AND blocker_id IN ({});",
+        todo_ids
+            .iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        blocker_ids
+            .iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    sqlx::query(query.as_str()).execute(&mut *db_conn).await?;
+    Ok(())
+}
+
+/// Makes sure that blocking `todo_id` on `blocker_id` wouldn't make a to do wait on itself,
+/// which is the case when the blocker already waits on the to do, directly or not.
+async fn check_dependency(
+    db_conn: &mut SqliteConnection,
+    todo_id: ToDoID,
+    blocker_id: ToDoID,
+) -> Result<(), SQLXError> {
+    let cycles: i64 = sqlx::query(
+        "WITH RECURSIVE waits_on(id) AS (\
+            SELECT ? \
+            UNION SELECT TodoDependencies.blocker_id FROM TodoDependencies JOIN waits_on ON TodoDependencies.todo_id = waits_on.id\
+         ) SELECT COUNT(*) AS cycles FROM waits_on WHERE id = ?;",
+    )
+    .bind(blocker_id)
+    .bind(todo_id)
+    .fetch_one(&mut *db_conn)
+    .await?
+    .get("cycles");
+
+    if cycles > 0 {
+        return Err(InvalidArgument(format!(
+            "Blocking to do {} on to do {} would make it wait on itself",
+            todo_id.public(),
+            blocker_id.public()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        db::sqlx::{delete_todos, query_all_todos, update_todos},
+        types::{ToDoQueryTarget, ToDoReadOptions, UpdateToDo, UserID},
+    };
+
+    use super::super::test_db::{setup_test_db, update_todo};
+
+    #[actix_web::test]
+    async fn dependencies_reject_cycles_and_gate_actionable_reads() {
+        let db = setup_test_db().await;
+        let block = |todo_id, blocker_id| UpdateToDo {
+            add_blockers: BTreeSet::from([ToDoID(blocker_id)]),
+            ..update_todo(ToDoQueryTarget::ToDo(ToDoID(todo_id)))
+        };
+        let actionable = || {
+            let db = db.clone();
+            async move {
+                let options = ToDoReadOptions {
+                    actionable: true,
+                    ..Default::default()
+                };
+                query_all_todos(db, UserID(1), options)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|todo| todo.id)
+                    .collect::<Vec<ToDoID>>()
+            }
+        };
+
+        // 1 waits on 2, which waits on 3, in another set.
+        let blocked = update_todos(db.clone(), UserID(1), vec![block(1, 2), block(2, 3)])
+            .await
+            .unwrap();
+        assert_eq!(
+            blocked.first().unwrap().blocked_by,
+            BTreeSet::from([ToDoID(2)])
+        );
+
+        let cycle = update_todos(db.clone(), UserID(1), vec![block(3, 1)]).await;
+        assert!(matches!(cycle, Err(SQLXError::InvalidArgument(_))));
+        let itself = update_todos(db.clone(), UserID(1), vec![block(4, 4)]).await;
+        assert!(matches!(itself, Err(SQLXError::InvalidArgument(_))));
+
+        assert_eq!(actionable().await, vec![ToDoID(3), ToDoID(4)]);
+
+        sqlx::query("UPDATE Todos SET complete = 1 WHERE id = 3;")
+            .execute(&**db)
+            .await
+            .unwrap();
+        assert_eq!(actionable().await, vec![ToDoID(2), ToDoID(4)]);
+
+        // Deleting a blocker drops the dependencies on it.
+        delete_todos(
+            db.clone(),
+            UserID(1),
+            HashSet::from([ToDoQueryTarget::ToDo(ToDoID(2))]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(actionable().await, vec![ToDoID(1), ToDoID(4)]);
+    }
+
+    #[actix_web::test]
+    async fn removing_blockers_leaves_the_others() {
+        let db = setup_test_db().await;
+        let update = |add_blockers, remove_blockers| UpdateToDo {
+            add_blockers,
+            remove_blockers,
+            ..update_todo(ToDoQueryTarget::ToDo(ToDoID(1)))
+        };
+
+        let blocked = update_todos(
+            db.clone(),
+            UserID(1),
+            vec![update(
+                BTreeSet::from([ToDoID(2), ToDoID(3)]),
+                BTreeSet::new(),
+            )],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            blocked.first().unwrap().blocked_by,
+            BTreeSet::from([ToDoID(2), ToDoID(3)])
+        );
+
+        let unblocked = update_todos(
+            db.clone(),
+            UserID(1),
+            vec![update(
+                BTreeSet::new(),
+                BTreeSet::from([ToDoID(2), ToDoID(4)]),
+            )],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            unblocked.first().unwrap().blocked_by,
+            BTreeSet::from([ToDoID(3)])
+        );
+    }
+}
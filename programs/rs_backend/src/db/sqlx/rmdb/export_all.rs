@@ -0,0 +1,173 @@
+use actix_web::web::Data;
+use chrono::Utc;
+use sqlx::{Error as SQLXError, Pool, Sqlite};
+
+use crate::{
+    api::ExportResponse,
+    types::{EXPORT_VERSION, ExportDocument, ToDo, UserID},
+};
+
+use super::{
+    access::owned_lists,
+    checklists::with_checklists,
+    links::with_links,
+    rows::{list_from_row, set_from_row, tag_from_row, todo_from_row},
+};
+
+pub async fn export_all(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ExportResponse, SQLXError> {
+    // A transaction keeps the reads on the same snapshot of the database.
+    let mut transaction = db_conn_pool.begin().await?;
+
+    let list_rows = sqlx::query("SELECT * FROM Lists WHERE owner_id = ? ORDER BY id;")
+        .bind(user_id)
+        .fetch_all(&mut *transaction)
+        .await?;
+    let mut lists = Vec::new();
+    for row in list_rows {
+        lists.push(list_from_row(&row));
+    }
+
+    let query = format!(
+        "SELECT * FROM Sets WHERE list_id IN ({}) ORDER BY id;",
+        owned_lists(user_id)
+    );
+    let set_rows = sqlx::query(query.as_str())
+        .fetch_all(&mut *transaction)
+        .await?;
+    let mut sets = Vec::new();
+    for row in set_rows {
+        sets.push(set_from_row(&row));
+    }
+
+    let query = format!(
+        "SELECT * FROM Todos WHERE list_id IN ({}) ORDER BY id;",
+        owned_lists(user_id)
+    );
+    let todo_rows = sqlx::query(query.as_str())
+        .fetch_all(&mut *transaction)
+        .await?;
+    let mut todos = Vec::new();
+    for row in todo_rows {
+        todos.push(todo_from_row(&row));
+    }
+    let todos: Vec<ToDo> = with_links(&mut transaction, todos).await?;
+    let todos = with_checklists(&mut transaction, todos).await?;
+
+    let tag_rows = sqlx::query("SELECT * FROM Tags WHERE owner_id = ? ORDER BY id;")
+        .bind(user_id)
+        .fetch_all(&mut *transaction)
+        .await?;
+    let mut tags = Vec::new();
+    for row in tag_rows {
+        tags.push(tag_from_row(&row));
+    }
+
+    transaction.commit().await?;
+
+    Ok(ExportDocument {
+        version: EXPORT_VERSION,
+        exported_at: Utc::now(),
+        lists,
+        sets,
+        todos,
+        tags,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        db::sqlx::import_all,
+        types::{ImportMode, ListID, SetID, TagID, ToDoID},
+    };
+
+    use super::super::test_db::setup_test_db;
+    use super::*;
+
+    #[actix_web::test]
+    async fn exports_import_back_into_another_database() {
+        let db = setup_test_db().await;
+        sqlx::raw_sql(
+            "INSERT INTO Tags (owner_id, name) VALUES (1, 'Weekly');
+             INSERT INTO TodoTags (todo_id, tag_id) VALUES (4, 1);
+             INSERT INTO TodoDependencies (todo_id, blocker_id) VALUES (2, 4);
+             INSERT INTO ChecklistItems (todo_id, title, done) VALUES (4, 'Rinse', 1);
+             UPDATE Todos SET notes = 'With vinegar', priority = 2 WHERE id = 4;",
+        )
+        .execute(&**db)
+        .await
+        .unwrap();
+        let exported = export_all(db, UserID(1)).await.unwrap();
+
+        // The document goes through JSON, as it does between servers, into a database where
+        // the same ids are already taken.
+        let json = serde_json::to_string(&exported).unwrap();
+        let other = setup_test_db().await;
+        let summary = import_all(
+            other.clone(),
+            UserID(2),
+            serde_json::from_str(&json).unwrap(),
+            ImportMode::Replace,
+        )
+        .await
+        .unwrap();
+        let imported = export_all(other, UserID(2)).await.unwrap();
+
+        let list = |id: &ListID| summary.lists[id];
+        let set = |id: &SetID| summary.sets[id];
+        let todo = |id: &ToDoID| summary.todos[id];
+        let tag = |id: &TagID| summary.tags[id];
+        assert_eq!(imported.lists.len(), exported.lists.len());
+        for original in &exported.lists {
+            let copy = imported.lists.iter().find(|l| l.id == list(&original.id));
+            assert_eq!(copy.unwrap().title, original.title);
+        }
+        assert_eq!(imported.sets.len(), exported.sets.len());
+        for original in &exported.sets {
+            let copy = imported.sets.iter().find(|s| s.id == set(&original.id));
+            let copy = copy.unwrap();
+            assert_eq!(copy.title, original.title);
+            assert_eq!(copy.list_id, list(&original.list_id));
+            assert_eq!(copy.parent_id, original.parent_id.as_ref().map(set));
+        }
+        assert_eq!(imported.tags.len(), exported.tags.len());
+        for original in &exported.tags {
+            let copy = imported.tags.iter().find(|t| t.id == tag(&original.id));
+            assert_eq!(copy.unwrap().name, original.name);
+        }
+        assert_eq!(imported.todos.len(), exported.todos.len());
+        for original in &exported.todos {
+            let copy = imported.todos.iter().find(|t| t.id == todo(&original.id));
+            let copy = copy.unwrap();
+            assert_eq!(
+                (&copy.title, copy.complete, &copy.notes, copy.priority),
+                (
+                    &original.title,
+                    original.complete,
+                    &original.notes,
+                    original.priority
+                )
+            );
+            assert_eq!(copy.list_id, list(&original.list_id));
+            assert_eq!(copy.set_id, original.set_id.as_ref().map(set));
+            assert!(original.uuid.is_some());
+            assert_eq!(copy.uuid, original.uuid);
+            assert_eq!(copy.tags, original.tags.iter().map(tag).collect());
+            assert_eq!(
+                copy.blocked_by,
+                original.blocked_by.iter().map(todo).collect()
+            );
+            let items = |todo: &ToDo| {
+                todo.checklist
+                    .iter()
+                    .flatten()
+                    .map(|item| (item.title.clone(), item.done))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(items(copy), items(original));
+        }
+    }
+}
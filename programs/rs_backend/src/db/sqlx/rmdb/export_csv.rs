@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use actix_web::web::Data;
+use sqlx::{Error as SQLXError, Pool, Row, Sqlite};
+
+use crate::{
+    api::{ExportToDosCsvResponse, ReadToDosRequest, ReadToDosResponse},
+    db::sqlx::{query_all_todos, query_todos},
+    types::{CsvToDoRecord, ListID, SetID, ToDoReadOptions, UserID},
+};
+
+pub async fn query_all_todos_csv(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ExportToDosCsvResponse, SQLXError> {
+    let todos = query_all_todos(db_conn_pool.clone(), user_id, ToDoReadOptions::default()).await?;
+    resolve_titles(db_conn_pool, todos).await
+}
+
+pub async fn query_todos_csv(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: ReadToDosRequest,
+) -> Result<ExportToDosCsvResponse, SQLXError> {
+    let todos = query_todos(
+        db_conn_pool.clone(),
+        user_id,
+        adds,
+        ToDoReadOptions::default(),
+    )
+    .await?;
+    resolve_titles(db_conn_pool, todos).await
+}
+
+async fn resolve_titles(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    todos: ReadToDosResponse,
+) -> Result<ExportToDosCsvResponse, SQLXError> {
+    if todos.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let list_ids = todos
+        .iter()
+        .map(|todo| todo.list_id.key().to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    let query = format!("SELECT id, title FROM Lists WHERE id IN ({});", list_ids);
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut list_titles: HashMap<ListID, String> = HashMap::new();
+    for row in query_result {
+        list_titles.insert(row.get("id"), row.get("title"));
+    }
+
+    let set_ids = todos
+        .iter()
+        .filter_map(|todo| todo.set_id)
+        .map(|id| id.key().to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    let query = format!("SELECT id, title FROM Sets WHERE id IN ({});", set_ids);
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut set_titles: HashMap<SetID, String> = HashMap::new();
+    for row in query_result {
+        set_titles.insert(row.get("id"), row.get("title"));
+    }
+
+    let mut records = Vec::new();
+    for todo in todos {
+        records.push(CsvToDoRecord {
+            id: todo.id,
+            list_id: todo.list_id,
+            list: list_titles.get(&todo.list_id).cloned().unwrap_or_default(),
+            set_id: todo.set_id,
+            set: todo.set_id.and_then(|id| set_titles.get(&id).cloned()),
+            title: todo.title,
+            complete: todo.complete,
+            due_date: todo.due_date,
+            priority: todo.priority,
+            notes: todo.notes,
+            created_at: todo.created_at,
+            updated_at: todo.updated_at,
+            completed_at: todo.completed_at,
+        });
+    }
+
+    Ok(records)
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use actix_web::web::Data;
+use sqlx::{Error as SQLXError, Pool, Sqlite};
+
+use crate::{
+    api::{ExportMarkdownRequest, ExportMarkdownResponse, ReadListsResponse},
+    db::sqlx::{query_all_lists, query_lists, query_sets, query_todos},
+    types::{
+        ListID, MarkdownDocument, MarkdownList, MarkdownSet, MarkdownToDo, Set, SetID,
+        SetQueryTarget, ToDoQueryTarget, ToDoReadOptions, UserID,
+    },
+};
+
+pub async fn query_all_markdown(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ExportMarkdownResponse, SQLXError> {
+    let lists = query_all_lists(db_conn_pool.clone(), user_id).await?;
+    build_document(db_conn_pool, user_id, lists).await
+}
+
+pub async fn query_markdown(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: ExportMarkdownRequest,
+) -> Result<ExportMarkdownResponse, SQLXError> {
+    let lists = query_lists(db_conn_pool.clone(), user_id, adds).await?;
+    build_document(db_conn_pool, user_id, lists).await
+}
+
+async fn build_document(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    lists: ReadListsResponse,
+) -> Result<ExportMarkdownResponse, SQLXError> {
+    if lists.is_empty() {
+        return Ok(MarkdownDocument::default());
+    }
+
+    let sets = query_sets(
+        db_conn_pool.clone(),
+        user_id,
+        lists
+            .iter()
+            .map(|list| SetQueryTarget::List(list.id))
+            .collect(),
+    )
+    .await?;
+    let todos = query_todos(
+        db_conn_pool,
+        user_id,
+        lists
+            .iter()
+            .map(|list| ToDoQueryTarget::List(list.id))
+            .collect(),
+        ToDoReadOptions::default(),
+    )
+    .await?;
+
+    let mut document = MarkdownDocument::default();
+    let mut list_index: HashMap<ListID, usize> = HashMap::new();
+    for list in lists {
+        list_index.insert(list.id, document.lists.len());
+        document.lists.push(MarkdownList {
+            title: list.title,
+            ..Default::default()
+        });
+    }
+
+    let mut todos_of_sets: HashMap<SetID, Vec<MarkdownToDo>> =
+        sets.iter().map(|set| (set.id, Vec::new())).collect();
+    for todo in todos {
+        let markdown_todo = MarkdownToDo {
+            title: todo.title,
+            complete: todo.complete,
+            due_date: todo.due_date,
+        };
+        match todo
+            .set_id
+            .and_then(|set_id| todos_of_sets.get_mut(&set_id))
+        {
+            Some(set_todos) => set_todos.push(markdown_todo),
+            None => document.lists[list_index[&todo.list_id]]
+                .todos
+                .push(markdown_todo),
+        }
+    }
+
+    let mut children: HashMap<(ListID, Option<SetID>), Vec<Set>> = HashMap::new();
+    for set in sets {
+        children
+            .entry((set.list_id, set.parent_id))
+            .or_default()
+            .push(set);
+    }
+    for (&list_id, &index) in &list_index {
+        document.lists[index].sets = nest((list_id, None), &mut children, &mut todos_of_sets);
+    }
+
+    Ok(document)
+}
+
+/// Builds the sets under `parent` with everything nested in them.
+fn nest(
+    parent: (ListID, Option<SetID>),
+    children: &mut HashMap<(ListID, Option<SetID>), Vec<Set>>,
+    todos_of_sets: &mut HashMap<SetID, Vec<MarkdownToDo>>,
+) -> Vec<MarkdownSet> {
+    children
+        .remove(&parent)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|set| MarkdownSet {
+            sets: nest((parent.0, Some(set.id)), children, todos_of_sets),
+            todos: todos_of_sets.remove(&set.id).unwrap_or_default(),
+            title: set.title,
+        })
+        .collect()
+}
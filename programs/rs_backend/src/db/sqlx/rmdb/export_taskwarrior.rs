@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use actix_web::web::Data;
+use chrono::Utc;
+use sqlx::{Error as SQLXError, Pool, Row, Sqlite};
+use uuid::Uuid;
+
+use crate::{
+    api::{ExportTaskwarriorRequest, ExportTaskwarriorResponse, ExportToDosCsvResponse},
+    db::sqlx::{query_all_todos_csv, query_todos_csv},
+    types::{TaskwarriorPriority, TaskwarriorStatus, TaskwarriorTask, ToDoID, UserID},
+};
+
+pub async fn query_all_taskwarrior(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ExportTaskwarriorResponse, SQLXError> {
+    let records = query_all_todos_csv(db_conn_pool.clone(), user_id).await?;
+    build_tasks(db_conn_pool, records).await
+}
+
+pub async fn query_taskwarrior(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: ExportTaskwarriorRequest,
+) -> Result<ExportTaskwarriorResponse, SQLXError> {
+    let records = query_todos_csv(db_conn_pool.clone(), user_id, adds).await?;
+    build_tasks(db_conn_pool, records).await
+}
+
+/// Converts to dos to tasks. Every to do has had its UUID since it was made, so the same to do
+/// keeps its UUID across exports and exporting doesn't write anything.
+async fn build_tasks(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    records: ExportToDosCsvResponse,
+) -> Result<ExportTaskwarriorResponse, SQLXError> {
+    if records.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "SELECT id, uuid FROM Todos WHERE id IN ({});",
+        records
+            .iter()
+            .map(|record| record.id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut uuids: HashMap<ToDoID, Uuid> = HashMap::new();
+    for row in query_result {
+        let uuid = Uuid::parse_str(row.get("uuid")).map_err(|err| SQLXError::Decode(err.into()))?;
+        uuids.insert(row.get("id"), uuid);
+    }
+
+    let now = Utc::now();
+    let mut tasks = Vec::new();
+    for record in records {
+        let project = TaskwarriorTask::project(&record.list, record.set.as_deref());
+        tasks.push(TaskwarriorTask {
+            uuid: uuids[&record.id],
+            description: record.title,
+            status: if record.complete {
+                TaskwarriorStatus::Completed
+            } else {
+                TaskwarriorStatus::Pending
+            },
+            project: Some(project),
+            priority: TaskwarriorPriority::from_priority(record.priority),
+            entry: Some(record.created_at),
+            due: record.due_date,
+            // Taskwarrior requires an end date on completed tasks.
+            end: record.complete.then(|| record.completed_at.unwrap_or(now)),
+        });
+    }
+
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        db::sqlx::{import_taskwarrior, insert_lists, insert_sets},
+        types::{CreateList, CreateSet, ListID, SetID},
+    };
+
+    use super::super::test_db::setup_test_db;
+    use super::*;
+
+    async fn versions(db: &Data<Pool<Sqlite>>) -> Vec<(String, i64)> {
+        sqlx::query_as("SELECT name, version FROM Changes ORDER BY name;")
+            .fetch_all(&***db)
+            .await
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn exports_only_read_and_import_back_into_the_same_sets() {
+        let db = setup_test_db().await;
+        insert_lists(
+            db.clone(),
+            UserID(1),
+            vec![CreateList {
+                title: "v1.2 Release".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        insert_sets(
+            db.clone(),
+            UserID(1),
+            vec![CreateSet {
+                list_id: ListID(3),
+                parent_id: None,
+                title: "Docs.rs".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO Todos (list_id, set_id, title) VALUES (3, 4, 'Write Guide');")
+            .execute(&**db)
+            .await
+            .unwrap();
+
+        let before = versions(&db).await;
+        let tasks = query_all_taskwarrior(db.clone(), UserID(1)).await.unwrap();
+        assert_eq!(versions(&db).await, before);
+        assert_eq!(tasks.len(), 5);
+        assert!(tasks.iter().all(|task| !task.uuid.is_nil()));
+        let again = query_all_taskwarrior(db.clone(), UserID(1)).await.unwrap();
+        assert_eq!(again, tasks);
+
+        let guide = tasks
+            .iter()
+            .find(|task| task.description == "Write Guide")
+            .unwrap();
+        assert_eq!(guide.project.as_deref(), Some("v1\\.2 Release.Docs\\.rs"));
+
+        let summary = import_taskwarrior(db.clone(), UserID(1), tasks)
+            .await
+            .unwrap();
+        assert!(summary.created.is_empty());
+        let guide = summary
+            .updated
+            .iter()
+            .find(|todo| todo.title == "Write Guide")
+            .unwrap();
+        assert_eq!((guide.list_id, guide.set_id), (ListID(3), Some(SetID(4))));
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::{BTreeSet, HashMap};
+
+use sqlx::{Error as SQLXError, Row, SqliteConnection};
+
+use crate::{
+    db::sqlx::{insert_lists_on, insert_sets_on},
+    types::{CreateList, CreateSet, ListID, SetID, UserID},
+};
+
+/// List and set ids keyed by title, as resolved by [`find_or_create_by_title`].
+#[derive(Debug, Default)]
+pub struct ContainerIds {
+    lists: HashMap<String, ListID>,
+    sets: HashMap<(ListID, String), SetID>,
+}
+
+impl ContainerIds {
+    /// Looks up a container that was passed to [`find_or_create_by_title`].
+    pub fn get(&self, list: &str, set: Option<&str>) -> (ListID, Option<SetID>) {
+        let list_id = self.lists[list];
+        let set_id = set.map(|set| self.sets[&(list_id, set.to_string())]);
+        (list_id, set_id)
+    }
+}
+
+/// Finds the lists of `user_id` and their sets with the given titles, creating the ones that don't exist yet.
+/// Titles are matched exactly, and when several lists (or sets within a list) share a title the
+/// oldest one is used.
+pub async fn find_or_create_by_title<'a>(
+    db_conn: &mut SqliteConnection,
+    user_id: UserID,
+    containers: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+) -> Result<ContainerIds, SQLXError> {
+    let containers = containers.into_iter().collect::<BTreeSet<_>>();
+    let mut ids = ContainerIds::default();
+
+    let query_result =
+        sqlx::query("SELECT id, title FROM Lists WHERE owner_id = ? ORDER BY id DESC;")
+            .bind(user_id)
+            .fetch_all(&mut *db_conn)
+            .await?;
+    for row in query_result {
+        ids.lists.insert(row.get("title"), row.get("id"));
+    }
+
+    let missing_lists = containers
+        .iter()
+        .filter(|(list, _)| !ids.lists.contains_key(*list))
+        .map(|(list, _)| list.to_string())
+        .collect::<BTreeSet<String>>();
+    if !missing_lists.is_empty() {
+        let entries = missing_lists
+            .into_iter()
+            .map(|title| CreateList { title })
+            .collect();
+        for list in insert_lists_on(&mut *db_conn, user_id, entries).await? {
+            ids.lists.insert(list.title, list.id);
+        }
+    }
+
+    let wanted_list_ids = containers
+        .iter()
+        .filter(|(_, set)| set.is_some())
+        .map(|(list, _)| ids.lists[*list].key().to_string())
+        .collect::<BTreeSet<String>>();
+    if !wanted_list_ids.is_empty() {
+        let query = format!(
+            "SELECT id, list_id, title FROM Sets WHERE list_id IN ({}) ORDER BY id DESC;",
+            wanted_list_ids
+                .into_iter()
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+        for row in query_result {
+            ids.sets
+                .insert((row.get("list_id"), row.get("title")), row.get("id"));
+        }
+    }
+
+    let missing_sets = containers
+        .iter()
+        .filter_map(|(list, set)| {
+            let key = (ids.lists[*list], (*set)?.to_string());
+            (!ids.sets.contains_key(&key)).then_some(key)
+        })
+        .collect::<BTreeSet<(ListID, String)>>();
+    if !missing_sets.is_empty() {
+        let entries = missing_sets
+            .into_iter()
+            .map(|(list_id, title)| CreateSet {
+                list_id,
+                parent_id: None,
+                title,
+            })
+            .collect();
+        for set in insert_sets_on(&mut *db_conn, user_id, entries).await? {
+            ids.sets.insert((set.list_id, set.title), set.id);
+        }
+    }
+
+    Ok(ids)
+}
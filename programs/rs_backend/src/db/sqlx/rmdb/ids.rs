@@ -0,0 +1,64 @@
+use sqlx::{
+    Database, Decode, Encode, Error as SQLXError, Pool, Row, Sqlite, Type, encode::IsNull,
+    error::BoxDynError,
+};
+
+use crate::types::{
+    ChecklistItemID, ListID, PublicIdKey, SetID, ShareLinkID, TagID, ToDoID, UserID,
+};
+
+/// The key public ids are made with, made the first time the server runs on a database.
+pub async fn load_public_id_key(db_conn_pool: &Pool<Sqlite>) -> Result<PublicIdKey, SQLXError> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO ServerSecrets (name, value) VALUES ('public_ids', randomblob(32));",
+    )
+    .execute(db_conn_pool)
+    .await?;
+
+    let key: Vec<u8> = sqlx::query("SELECT value FROM ServerSecrets WHERE name = 'public_ids';")
+        .fetch_one(db_conn_pool)
+        .await?
+        .get("value");
+    key.try_into()
+        .map_err(|_| SQLXError::Protocol("The public id key isn't 32 bytes long".to_string()))
+}
+
+/// Public ids are stored as their row id, so they bind and read like the `INTEGER` they wrap.
+macro_rules! row_id {
+    ($($name:ident),*) => {$(
+        impl Type<Sqlite> for $name {
+            fn type_info() -> <Sqlite as Database>::TypeInfo {
+                <i64 as Type<Sqlite>>::type_info()
+            }
+
+            fn compatible(ty: &<Sqlite as Database>::TypeInfo) -> bool {
+                <i64 as Type<Sqlite>>::compatible(ty)
+            }
+        }
+
+        impl<'q> Encode<'q, Sqlite> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <Sqlite as Database>::ArgumentBuffer<'q>,
+            ) -> Result<IsNull, BoxDynError> {
+                <i64 as Encode<'q, Sqlite>>::encode_by_ref(&self.key(), buf)
+            }
+        }
+
+        impl<'r> Decode<'r, Sqlite> for $name {
+            fn decode(value: <Sqlite as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+                <i64 as Decode<'r, Sqlite>>::decode(value).map($name)
+            }
+        }
+    )*};
+}
+
+row_id!(
+    ChecklistItemID,
+    ListID,
+    SetID,
+    ShareLinkID,
+    TagID,
+    ToDoID,
+    UserID
+);
@@ -0,0 +1,362 @@
+use std::collections::BTreeSet;
+
+use actix_web::web::Data;
+use sqlx::{
+    Error::{self as SQLXError, InvalidArgument},
+    Pool, Row, Sqlite,
+};
+
+use crate::{
+    api::{ImportRequest, ImportResponse},
+    types::{EXPORT_VERSION, ImportMode, ImportSummary, UserID},
+};
+
+use super::{dependencies::add_blockers, nesting::check_parent, tagging::attach_tags};
+
+/// Restores an export document. Every entity is stored under a freshly allocated id, and the
+/// `list_id`/`parent_id`/`set_id`/tag/blocker references are rewritten to point at the new rows.
+/// Checklist items are restored with the to do that carries them.
+pub async fn import_all(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    document: ImportRequest,
+    mode: ImportMode,
+) -> Result<ImportResponse, SQLXError> {
+    if document.version != EXPORT_VERSION {
+        return Err(InvalidArgument(format!(
+            "Unsupported export version {}, this server reads version {}",
+            document.version, EXPORT_VERSION
+        )));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+
+    if mode == ImportMode::Replace {
+        // Sets, to dos and their links go with the lists that hold them.
+        sqlx::query("DELETE FROM Lists WHERE owner_id = ?; DELETE FROM Tags WHERE owner_id = ?;")
+            .bind(user_id)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+    }
+
+    let mut summary = ImportSummary::default();
+
+    // Tag names are unique per user, so an imported tag merges into an existing one of the same
+    // name.
+    let mut tags = document.tags;
+    tags.sort();
+    for tag in tags {
+        let row = sqlx::query(
+            "INSERT INTO Tags (owner_id, name) VALUES (?, ?) ON CONFLICT (owner_id, name) DO UPDATE SET name = excluded.name RETURNING id;",
+        )
+        .bind(user_id)
+        .bind(&tag.name)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        if summary.tags.insert(tag.id, row.get("id")).is_some() {
+            return Err(InvalidArgument(format!(
+                "Tag {} appears more than once in the import",
+                tag.id.public()
+            )));
+        }
+    }
+
+    let mut lists = document.lists;
+    lists.sort();
+    for list in lists {
+        let row = sqlx::query(
+            "INSERT INTO Lists (owner_id, title, created_at, updated_at) VALUES (?, ?, ?, ?) RETURNING id;",
+        )
+        .bind(user_id)
+        .bind(&list.title)
+        .bind(list.created_at)
+        .bind(list.updated_at)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        if summary.lists.insert(list.id, row.get("id")).is_some() {
+            return Err(InvalidArgument(format!(
+                "List {} appears more than once in the import",
+                list.id.public()
+            )));
+        }
+    }
+
+    let mut sets = document.sets;
+    sets.sort();
+    for set in &sets {
+        let list_id = *summary.lists.get(&set.list_id).ok_or_else(|| {
+            InvalidArgument(format!(
+                "Set {} belongs to list {}, which isn't in the import",
+                set.id.public(),
+                set.list_id.public()
+            ))
+        })?;
+
+        let row = sqlx::query(
+            "INSERT INTO Sets (list_id, title, position, created_at, updated_at) VALUES (?, ?, ?, ?, ?) RETURNING id;",
+        )
+        .bind(list_id)
+        .bind(&set.title)
+        .bind(set.position)
+        .bind(set.created_at)
+        .bind(set.updated_at)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        if summary.sets.insert(set.id, row.get("id")).is_some() {
+            return Err(InvalidArgument(format!(
+                "Set {} appears more than once in the import",
+                set.id.public()
+            )));
+        }
+    }
+
+    // Parents are linked once every set exists, since a set can be nested in one with a
+    // higher id.
+    for set in &sets {
+        let Some(parent_id) = set.parent_id else {
+            continue;
+        };
+        let parent_id = *summary.sets.get(&parent_id).ok_or_else(|| {
+            InvalidArgument(format!(
+                "Set {} is nested in set {}, which isn't in the import",
+                set.id.public(),
+                parent_id.public()
+            ))
+        })?;
+        let set_id = summary.sets[&set.id];
+
+        check_parent(
+            &mut transaction,
+            &format!("id = {}", set_id.key()),
+            parent_id,
+        )
+        .await?;
+        sqlx::query("UPDATE Sets SET parent_id = ? WHERE id = ?;")
+            .bind(parent_id)
+            .bind(set_id)
+            .execute(&mut *transaction)
+            .await?;
+    }
+
+    let mut todos = document.todos;
+    todos.sort();
+    let mut dependencies = Vec::new();
+    for todo in todos {
+        let list_id = *summary.lists.get(&todo.list_id).ok_or_else(|| {
+            InvalidArgument(format!(
+                "To do {} belongs to list {}, which isn't in the import",
+                todo.id.public(),
+                todo.list_id.public()
+            ))
+        })?;
+        let set_id = match todo.set_id {
+            Some(set_id) => Some(*summary.sets.get(&set_id).ok_or_else(|| {
+                InvalidArgument(format!(
+                    "To do {} belongs to set {}, which isn't in the import",
+                    todo.id.public(),
+                    set_id.public()
+                ))
+            })?),
+            None => None,
+        };
+
+        // A to do keeps its UUID unless it's taken, as when merging an export into the database
+        // it came from, in which case it gets a new one like any to do that's made.
+        let row = sqlx::query(
+            "INSERT INTO Todos (list_id, set_id, title, complete, due_date, uuid, position, notes, priority, created_at, updated_at, completed_at, auto_complete) VALUES (?, ?, ?, ?, ?, (SELECT ? WHERE NOT EXISTS (SELECT 1 FROM Todos WHERE uuid = ?)), ?, ?, ?, ?, ?, ?, ?) RETURNING id;",
+        )
+        .bind(list_id)
+        .bind(set_id)
+        .bind(&todo.title)
+        .bind(todo.complete)
+        .bind(todo.due_date)
+        .bind(todo.uuid.map(|uuid| uuid.to_string()))
+        .bind(todo.uuid.map(|uuid| uuid.to_string()))
+        .bind(todo.position)
+        .bind(&todo.notes)
+        .bind(todo.priority.level())
+        .bind(todo.created_at)
+        .bind(todo.updated_at)
+        .bind(todo.completed_at)
+        .bind(todo.auto_complete)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let todo_id = row.get("id");
+        let mut tag_ids = BTreeSet::new();
+        for tag_id in &todo.tags {
+            tag_ids.insert(*summary.tags.get(tag_id).ok_or_else(|| {
+                InvalidArgument(format!(
+                    "To do {} is tagged with tag {}, which isn't in the import",
+                    todo.id.public(),
+                    tag_id.public()
+                ))
+            })?);
+        }
+        attach_tags(&mut transaction, &[todo_id], &tag_ids).await?;
+
+        for item in todo.checklist.iter().flatten() {
+            sqlx::query(
+                "INSERT INTO ChecklistItems (todo_id, title, done, position) VALUES (?, ?, ?, ?);",
+            )
+            .bind(todo_id)
+            .bind(&item.title)
+            .bind(item.done)
+            .bind(item.position)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        if summary.todos.insert(todo.id, todo_id).is_some() {
+            return Err(InvalidArgument(format!(
+                "To do {} appears more than once in the import",
+                todo.id.public()
+            )));
+        }
+        dependencies.push((todo.id, todo.blocked_by));
+    }
+
+    // Like parents, blockers are linked once every to do exists.
+    for (todo_id, blocked_by) in dependencies {
+        let mut blocker_ids = BTreeSet::new();
+        for blocker_id in &blocked_by {
+            blocker_ids.insert(*summary.todos.get(blocker_id).ok_or_else(|| {
+                InvalidArgument(format!(
+                    "To do {} is blocked by to do {}, which isn't in the import",
+                    todo_id.public(),
+                    blocker_id.public()
+                ))
+            })?);
+        }
+        add_blockers(&mut transaction, &[summary.todos[&todo_id]], &blocker_ids).await?;
+    }
+
+    transaction.commit().await?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        db::sqlx::export_all,
+        types::{ExportDocument, ListID, SetID, TagID, ToDoID},
+    };
+
+    use super::super::test_db::setup_test_db;
+    use super::*;
+
+    /// Tags to do 1, blocks to do 2 on it, gives it a checklist item, and adds a list of bob's.
+    async fn seed_links(db: &Pool<Sqlite>) {
+        sqlx::raw_sql(
+            "INSERT INTO Tags (owner_id, name) VALUES (1, 'Weekly');
+             INSERT INTO TodoTags (todo_id, tag_id) VALUES (1, 1);
+             INSERT INTO TodoDependencies (todo_id, blocker_id) VALUES (2, 1);
+             INSERT INTO ChecklistItems (todo_id, title) VALUES (1, 'Brush');
+             INSERT INTO Lists (owner_id, title) VALUES (2, 'Errands');",
+        )
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    async fn list_ids(db: &Pool<Sqlite>) -> Vec<i64> {
+        sqlx::query("SELECT id FROM Lists ORDER BY id;")
+            .fetch_all(db)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get("id"))
+            .collect()
+    }
+
+    #[actix_web::test]
+    async fn merging_adds_to_the_data_and_replacing_drops_it() {
+        let db = setup_test_db().await;
+        seed_links(&db).await;
+
+        let document = export_all(db.clone(), UserID(1)).await.unwrap();
+        let merged = import_all(db.clone(), UserID(1), document, ImportMode::Merge)
+            .await
+            .unwrap();
+        assert_eq!(list_ids(&db).await, [1, 2, 3, 4, 5]);
+
+        // Everything is stored under new ids, and the references follow.
+        assert_eq!(merged.lists[&ListID(1)], ListID(4));
+        assert_eq!(merged.lists[&ListID(2)], ListID(5));
+        let tub = sqlx::query("SELECT list_id, parent_id FROM Sets WHERE id = ?;")
+            .bind(merged.sets[&SetID(3)])
+            .fetch_one(&**db)
+            .await
+            .unwrap();
+        assert_eq!(tub.get::<ListID, _>("list_id"), ListID(4));
+        assert_eq!(
+            tub.get::<Option<SetID>, _>("parent_id"),
+            Some(merged.sets[&SetID(1)])
+        );
+        let blocker: ToDoID =
+            sqlx::query("SELECT blocker_id FROM TodoDependencies WHERE todo_id = ?;")
+                .bind(merged.todos[&ToDoID(2)])
+                .fetch_one(&**db)
+                .await
+                .unwrap()
+                .get("blocker_id");
+        assert_eq!(blocker, merged.todos[&ToDoID(1)]);
+        // A tag of the same name is reused rather than made twice.
+        assert_eq!(merged.tags[&TagID(1)], TagID(1));
+        // The copies can't take the UUIDs of the to dos they were made from.
+        let uuids: i64 = sqlx::query_scalar("SELECT COUNT(DISTINCT uuid) FROM Todos;")
+            .fetch_one(&**db)
+            .await
+            .unwrap();
+        assert_eq!(uuids, 8);
+
+        let document = export_all(db.clone(), UserID(1)).await.unwrap();
+        let replaced = import_all(db.clone(), UserID(1), document, ImportMode::Replace)
+            .await
+            .unwrap();
+        assert_eq!(replaced.lists.len(), 4);
+        // Bob's list is left alone.
+        assert_eq!(list_ids(&db).await, [3, 6, 7, 8, 9]);
+        let counts = sqlx::query(
+            "SELECT (SELECT COUNT(*) FROM Todos) AS todos, (SELECT COUNT(*) FROM Tags) AS tags, \
+                    (SELECT COUNT(*) FROM ChecklistItems) AS items;",
+        )
+        .fetch_one(&**db)
+        .await
+        .unwrap();
+        assert_eq!(counts.get::<i64, _>("todos"), 8);
+        assert_eq!(counts.get::<i64, _>("tags"), 1);
+        assert_eq!(counts.get::<i64, _>("items"), 2);
+    }
+
+    #[actix_web::test]
+    async fn dangling_references_are_rejected() {
+        let db = setup_test_db().await;
+        seed_links(&db).await;
+
+        let corruptions: [fn(&mut ExportDocument); 5] = [
+            |document| document.sets[0].list_id = ListID(99),
+            |document| document.sets[0].parent_id = Some(SetID(99)),
+            |document| document.todos[0].set_id = Some(SetID(99)),
+            |document| {
+                document.todos[0].tags.insert(TagID(99));
+            },
+            |document| {
+                document.todos[0].blocked_by.insert(ToDoID(99));
+            },
+        ];
+        for corrupt in corruptions {
+            let mut document = export_all(db.clone(), UserID(1)).await.unwrap();
+            corrupt(&mut document);
+            let imported = import_all(db.clone(), UserID(1), document, ImportMode::Merge).await;
+            assert!(matches!(imported, Err(InvalidArgument(_))));
+        }
+
+        // Nothing of the rejected imports was kept.
+        assert_eq!(list_ids(&db).await, [1, 2, 3]);
+    }
+}
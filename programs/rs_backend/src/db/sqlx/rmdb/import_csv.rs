@@ -0,0 +1,115 @@
+use std::collections::{BTreeSet, HashSet};
+
+use actix_web::web::Data;
+use sqlx::{Error as SQLXError, Pool, Sqlite};
+
+use crate::{
+    api::{ImportToDosCsvRequest, ImportToDosCsvResponse},
+    db::sqlx::{find_or_create_by_title, insert_todos_on},
+    types::{CreateToDo, CsvImportReport, UserID},
+};
+
+/// Inserts the parsed rows of a CSV import, creating any list or set that doesn't exist yet.
+pub async fn import_todos_csv(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    import: ImportToDosCsvRequest,
+) -> Result<ImportToDosCsvResponse, SQLXError> {
+    let mut transaction = db_conn_pool.begin().await?;
+
+    let containers = find_or_create_by_title(
+        &mut transaction,
+        user_id,
+        import
+            .rows
+            .iter()
+            .map(|row| (row.list.as_str(), row.set.as_deref())),
+    )
+    .await?;
+
+    let entries = import
+        .rows
+        .into_iter()
+        .map(|row| {
+            let (list_id, set_id) = containers.get(&row.list, row.set.as_deref());
+            CreateToDo {
+                list_id,
+                set_id,
+                title: row.title,
+                complete: row.complete,
+                due_date: row.due_date,
+                tags: BTreeSet::new(),
+                blocked_by: BTreeSet::new(),
+                notes: row.notes,
+                priority: row.priority,
+                auto_complete: None,
+            }
+        })
+        .collect::<Vec<CreateToDo>>();
+
+    let created = if entries.is_empty() {
+        HashSet::new()
+    } else {
+        insert_todos_on(&mut transaction, user_id, entries).await?
+    };
+
+    transaction.commit().await?;
+
+    Ok(CsvImportReport {
+        created,
+        errors: import.errors,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::{CsvImport, ListID, SetID};
+
+    use super::super::test_db::setup_test_db;
+    use super::*;
+
+    #[actix_web::test]
+    async fn rows_go_into_lists_and_sets_found_or_made_by_title() {
+        let db = setup_test_db().await;
+        let import = CsvImport::parse(
+            b"list,set,title,complete\n\
+              Chores,Clean Bath Room,Polish Mirror,true\n\
+              Chores,,Dust Shelves,\n\
+              Errands,Shops,Buy Milk,\n\
+              Errands,,,\n",
+        )
+        .unwrap();
+
+        let report = import_todos_csv(db.clone(), UserID(1), import)
+            .await
+            .unwrap();
+        assert_eq!(report.errors.len(), 1);
+        let mut placed = report
+            .created
+            .iter()
+            .map(|todo| {
+                (
+                    todo.title.as_str(),
+                    todo.list_id,
+                    todo.set_id,
+                    todo.complete,
+                )
+            })
+            .collect::<Vec<_>>();
+        placed.sort_by_key(|(title, ..)| *title);
+        assert_eq!(
+            placed,
+            [
+                ("Buy Milk", ListID(3), Some(SetID(4)), false),
+                ("Dust Shelves", ListID(1), None, false),
+                ("Polish Mirror", ListID(1), Some(SetID(1)), true),
+            ]
+        );
+
+        // Bob's rows don't land in alice's lists of the same title.
+        let import = CsvImport::parse(b"list,title\nChores,Wash Car\n").unwrap();
+        let report = import_todos_csv(db, UserID(2), import).await.unwrap();
+        let todo = report.created.iter().next().unwrap();
+        assert_eq!(todo.list_id, ListID(4));
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::{BTreeSet, HashSet};
+
+use actix_web::web::Data;
+use sqlx::{Error as SQLXError, Pool, Sqlite};
+
+use crate::{
+    api::{ImportMarkdownRequest, ImportMarkdownResponse},
+    db::sqlx::{insert_lists_on, insert_sets_on, insert_todos_on},
+    types::{CreateList, CreateSet, CreateToDo, MarkdownImportSummary, UserID},
+};
+
+/// Creates a new list for every list in the document, with all of its sets and to dos, in a
+/// single transaction.
+pub async fn import_markdown(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    document: ImportMarkdownRequest,
+) -> Result<ImportMarkdownResponse, SQLXError> {
+    let mut transaction = db_conn_pool.begin().await?;
+    let mut summary = MarkdownImportSummary::default();
+    let mut entries = Vec::new();
+
+    for markdown_list in document.lists {
+        let list = insert_lists_on(
+            &mut transaction,
+            user_id,
+            vec![CreateList {
+                title: markdown_list.title,
+            }],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .ok_or(SQLXError::RowNotFound)?;
+
+        for todo in markdown_list.todos {
+            entries.push(CreateToDo {
+                list_id: list.id,
+                set_id: None,
+                title: todo.title,
+                complete: Some(todo.complete),
+                due_date: todo.due_date,
+                tags: BTreeSet::new(),
+                blocked_by: BTreeSet::new(),
+                notes: None,
+                priority: None,
+                auto_complete: None,
+            });
+        }
+
+        // Sets are made depth first, so every set is made after its parent and before its
+        // next sibling.
+        let mut pending = markdown_list
+            .sets
+            .into_iter()
+            .rev()
+            .map(|set| (None, set))
+            .collect::<Vec<_>>();
+        while let Some((parent_id, markdown_set)) = pending.pop() {
+            let set = insert_sets_on(
+                &mut transaction,
+                user_id,
+                vec![CreateSet {
+                    list_id: list.id,
+                    parent_id,
+                    title: markdown_set.title,
+                }],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(SQLXError::RowNotFound)?;
+
+            for todo in markdown_set.todos {
+                entries.push(CreateToDo {
+                    list_id: list.id,
+                    set_id: Some(set.id),
+                    title: todo.title,
+                    complete: Some(todo.complete),
+                    due_date: todo.due_date,
+                    tags: BTreeSet::new(),
+                    blocked_by: BTreeSet::new(),
+                    notes: None,
+                    priority: None,
+                    auto_complete: None,
+                });
+            }
+
+            pending.extend(
+                markdown_set
+                    .sets
+                    .into_iter()
+                    .rev()
+                    .map(|child| (Some(set.id), child)),
+            );
+            summary.sets.insert(set);
+        }
+
+        summary.lists.insert(list);
+    }
+
+    summary.todos = if entries.is_empty() {
+        HashSet::new()
+    } else {
+        insert_todos_on(&mut transaction, user_id, entries).await?
+    };
+
+    transaction.commit().await?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use crate::{db::sqlx::query_markdown, types::MarkdownDocument};
+
+    use super::super::test_db::setup_test_db;
+    use super::*;
+
+    #[actix_web::test]
+    async fn every_list_of_the_document_is_made_anew() {
+        let db = setup_test_db().await;
+        let document = MarkdownDocument::parse(
+            "# Chores\n- [ ] Water Plants\n## Kitchen\n- [x] Wipe Counters\n- [ ] Empty Bin\n# Errands\n",
+            NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+        )
+        .unwrap();
+
+        let summary = import_markdown(db.clone(), UserID(1), document)
+            .await
+            .unwrap();
+
+        // A list of the same title as an existing one is made next to it.
+        let mut lists = summary
+            .lists
+            .iter()
+            .map(|list| (list.id.key(), list.title.as_str()))
+            .collect::<Vec<_>>();
+        lists.sort();
+        assert_eq!(lists, [(3, "Chores"), (4, "Errands")]);
+        let kitchen = summary.sets.iter().next().unwrap();
+        assert_eq!(
+            (kitchen.list_id.key(), kitchen.title.as_str()),
+            (3, "Kitchen")
+        );
+
+        let mut todos = summary
+            .todos
+            .iter()
+            .map(|todo| {
+                (
+                    todo.title.as_str(),
+                    todo.list_id.key(),
+                    todo.set_id.map(|set_id| set_id == kitchen.id),
+                    todo.complete,
+                )
+            })
+            .collect::<Vec<_>>();
+        todos.sort();
+        assert_eq!(
+            todos,
+            [
+                ("Empty Bin", 3, Some(true), false),
+                ("Water Plants", 3, None, false),
+                ("Wipe Counters", 3, Some(true), true),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn nested_sets_round_trip_through_export() {
+        let db = setup_test_db().await;
+        let text = "# Chores\n\
+            \n\
+            - [ ] Water Plants\n\
+            \n\
+            ## Kitchen\n\
+            \n\
+            - [x] Wipe Counters\n\
+            \n\
+            ### Sink\n\
+            \n\
+            - [ ] Scrub Basin\n\
+            \n\
+            #### Drain\n\
+            \n\
+            ### Fridge\n\
+            \n\
+            ## Garage\n";
+        let document =
+            MarkdownDocument::parse(text, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()).unwrap();
+
+        let summary = import_markdown(db.clone(), UserID(1), document)
+            .await
+            .unwrap();
+
+        let set_named = |title: &str| {
+            summary
+                .sets
+                .iter()
+                .find(|set| set.title == title)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(set_named("Kitchen").parent_id, None);
+        assert_eq!(set_named("Sink").parent_id, Some(set_named("Kitchen").id));
+        assert_eq!(set_named("Drain").parent_id, Some(set_named("Sink").id));
+        assert_eq!(set_named("Fridge").parent_id, Some(set_named("Kitchen").id));
+
+        let list = summary.lists.iter().next().unwrap();
+        let exported = query_markdown(db, UserID(1), HashSet::from([list.id]))
+            .await
+            .unwrap();
+        assert_eq!(exported.render(), text);
+    }
+}
@@ -0,0 +1,266 @@
+use actix_web::web::Data;
+use chrono::Utc;
+use sqlx::{Error as SQLXError, Pool, Row, Sqlite};
+
+use crate::{
+    api::{ImportTaskwarriorRequest, ImportTaskwarriorResponse},
+    db::sqlx::find_or_create_by_title,
+    types::{TaskwarriorImportSummary, UserID},
+};
+
+use super::{
+    access::writable_lists,
+    addresses::{reread_todos, unnumber_todos},
+    links::with_links,
+};
+
+/// Imports tasks by UUID. A task whose UUID is already stored updates that to do, any other task
+/// creates a new one. Lists and sets named by a task's project are created as needed.
+///
+/// Tasks whose UUID belongs to a to do the user can't write are skipped like deleted tasks, which
+/// doesn't tell them apart from tasks that can't be imported at all.
+pub async fn import_taskwarrior(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    tasks: ImportTaskwarriorRequest,
+) -> Result<ImportTaskwarriorResponse, SQLXError> {
+    let mut transaction = db_conn_pool.begin().await?;
+    let mut summary = TaskwarriorImportSummary::default();
+
+    let (tasks, skipped): (Vec<_>, Vec<_>) =
+        tasks.into_iter().partition(|task| task.is_importable());
+    summary.skipped = skipped.into_iter().map(|task| task.uuid).collect();
+
+    // UUIDs are unique across all users, so another user's to do can be neither updated nor
+    // created anew.
+    let foreign = format!(
+        "SELECT 1 FROM Todos WHERE uuid = ? AND list_id NOT IN ({});",
+        writable_lists(user_id)
+    );
+    let mut importable = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        let taken = sqlx::query(foreign.as_str())
+            .bind(task.uuid.to_string())
+            .fetch_optional(&mut *transaction)
+            .await?;
+        match taken {
+            Some(_) => summary.skipped.push(task.uuid),
+            None => importable.push(task),
+        }
+    }
+    let tasks = importable;
+
+    let projects = tasks
+        .iter()
+        .map(|task| task.list_and_set())
+        .collect::<Vec<_>>();
+    let containers = find_or_create_by_title(
+        &mut transaction,
+        user_id,
+        projects
+            .iter()
+            .map(|(list, set)| (list.as_ref(), set.as_deref())),
+    )
+    .await?;
+
+    // Only the user's own to dos are matched by UUID.
+    let update = format!(
+        "UPDATE Todos SET (list_id, set_id, title, complete, due_date, priority, completed_at, updated_at) = (?, ?, ?, ?, ?, ?, ?, ?) WHERE uuid = ? AND list_id IN ({}) RETURNING id;",
+        writable_lists(user_id)
+    );
+    let now = Utc::now();
+    let (mut created, mut updated) = (Vec::new(), Vec::new());
+    for task in tasks {
+        let (list, set) = task.list_and_set();
+        let (list_id, set_id) = containers.get(&list, set.as_deref());
+        let completed_at = task.complete().then(|| task.end.unwrap_or(now));
+        let matching = format!(
+            "uuid = '{}' AND list_id IN ({})",
+            task.uuid,
+            writable_lists(user_id)
+        );
+        unnumber_todos(&mut transaction, &matching, list_id).await?;
+
+        let existing = sqlx::query(update.as_str())
+            .bind(list_id)
+            .bind(set_id)
+            .bind(&task.description)
+            .bind(task.complete())
+            .bind(task.due)
+            .bind(task.priority().level())
+            .bind(completed_at)
+            .bind(now)
+            .bind(task.uuid.to_string())
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        match existing {
+            Some(row) => updated.push(row.get("id")),
+            None => {
+                let row = sqlx::query(
+                    "INSERT INTO Todos (list_id, set_id, title, complete, due_date, uuid, priority, created_at, completed_at, position) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(position), 0) + 1 FROM Todos WHERE list_id = ? AND set_id IS ?)) RETURNING id;",
+                )
+                .bind(list_id)
+                .bind(set_id)
+                .bind(&task.description)
+                .bind(task.complete())
+                .bind(task.due)
+                .bind(task.uuid.to_string())
+                .bind(task.priority().level())
+                .bind(task.entry.unwrap_or(now))
+                .bind(completed_at)
+                .bind(list_id)
+                .bind(set_id)
+                .fetch_one(&mut *transaction)
+                .await?;
+                created.push(row.get("id"));
+            }
+        }
+    }
+
+    // New to dos, and ones that changed lists, were numbered by triggers `RETURNING` doesn't see.
+    let created = reread_todos(&mut transaction, &created).await?;
+    summary.created = with_links(&mut transaction, created).await?;
+    let updated = reread_todos(&mut transaction, &updated).await?;
+    summary.updated = with_links(&mut transaction, updated).await?;
+
+    transaction.commit().await?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use crate::types::{ListID, SetID, TaskwarriorStatus, TaskwarriorTask, ToDo};
+
+    use super::super::test_db::setup_test_db;
+    use super::*;
+
+    fn task(uuid: Uuid, description: &str, project: Option<&str>) -> TaskwarriorTask {
+        TaskwarriorTask {
+            uuid,
+            description: description.to_string(),
+            status: TaskwarriorStatus::Pending,
+            project: project.map(str::to_string),
+            priority: None,
+            entry: None,
+            due: None,
+            end: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn reimported_tasks_follow_their_project() {
+        let db = setup_test_db().await;
+        let uuid = Uuid::new_v4();
+
+        let first = import_taskwarrior(db.clone(), UserID(1), vec![task(uuid, "Dig", None)])
+            .await
+            .unwrap();
+        let todo: &ToDo = first.created.iter().next().unwrap();
+        assert_eq!(todo.tdid, 1);
+
+        // The task moves to a list whose first number is taken already, and is numbered there.
+        let second = import_taskwarrior(
+            db.clone(),
+            UserID(1),
+            vec![task(uuid, "Dig Deeper", Some("Chores"))],
+        )
+        .await
+        .unwrap();
+        assert!(second.created.is_empty());
+        let moved = second.updated.iter().next().unwrap();
+        assert_eq!((moved.id, moved.list_id), (todo.id, ListID(1)));
+        assert_eq!(moved.title, "Dig Deeper");
+        assert_eq!(moved.tdid, 5);
+    }
+
+    #[actix_web::test]
+    async fn tasks_are_filed_by_project_and_deleted_ones_skipped() {
+        let db = setup_test_db().await;
+        let burned = Uuid::new_v4();
+        let deleted = TaskwarriorTask {
+            status: TaskwarriorStatus::Deleted,
+            ..task(burned, "Burn Leaves", None)
+        };
+        let done = TaskwarriorTask {
+            status: TaskwarriorStatus::Completed,
+            ..task(Uuid::new_v4(), "Rinse Tub", Some("Chores.Clean Tub"))
+        };
+
+        let summary = import_taskwarrior(
+            db.clone(),
+            UserID(1),
+            vec![
+                task(Uuid::new_v4(), "Buy Soap", Some("Chores.Clean Bath Room")),
+                done,
+                deleted,
+            ],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.skipped, [burned]);
+        let mut created = summary
+            .created
+            .iter()
+            .map(|todo| {
+                (
+                    todo.title.as_str(),
+                    todo.list_id,
+                    todo.set_id,
+                    todo.complete,
+                )
+            })
+            .collect::<Vec<_>>();
+        created.sort_by_key(|(title, ..)| *title);
+        assert_eq!(
+            created,
+            [
+                ("Buy Soap", ListID(1), Some(SetID(1)), false),
+                ("Rinse Tub", ListID(1), Some(SetID(3)), true),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn uuids_of_other_users_are_skipped() {
+        let db = setup_test_db().await;
+        let uuid = Uuid::new_v4();
+        import_taskwarrior(
+            db.clone(),
+            UserID(1),
+            vec![task(uuid, "Dig", Some("Chores"))],
+        )
+        .await
+        .unwrap();
+
+        let other = Uuid::new_v4();
+        let summary = import_taskwarrior(
+            db.clone(),
+            UserID(2),
+            vec![
+                task(uuid, "Fill In", Some("Chores")),
+                task(other, "Plant", Some("Garden")),
+            ],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.skipped, [uuid]);
+        assert!(summary.updated.is_empty());
+        let planted = summary.created.iter().next().unwrap();
+        assert_eq!(
+            (summary.created.len(), planted.title.as_str()),
+            (1, "Plant")
+        );
+
+        let title: String = sqlx::query_scalar("SELECT title FROM Todos WHERE uuid = ?;")
+            .bind(uuid.to_string())
+            .fetch_one(&**db)
+            .await
+            .unwrap();
+        assert_eq!(title, "Dig");
+    }
+}
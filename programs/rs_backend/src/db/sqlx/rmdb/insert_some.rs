@@ -1,24 +1,45 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use actix_web::web::Data;
 use sqlx::{
     Error::{self as SQLXError, InvalidArgument},
-    Pool, Row, Sqlite,
+    Pool, Row, Sqlite, SqliteConnection,
 };
 
 use crate::{
     api::{
-        CreateListsRequest, CreateListsResponse, CreateSetsRequest, CreateSetsResponse,
-        CreateToDosRequest, CreateToDosResponse,
+        CreateChecklistItemsRequest, CreateChecklistItemsResponse, CreateListsRequest,
+        CreateListsResponse, CreateSetsRequest, CreateSetsResponse, CreateTagsRequest,
+        CreateTagsResponse, CreateToDosRequest, CreateToDosResponse,
     },
-    types::{List, Set, ToDo},
+    types::{ListID, SetID, ToDoID, UserID},
+};
+
+use super::{
+    access::{check_lists, check_tags, check_todos},
+    addresses::{reread_sets, reread_todos},
+    checklists::auto_complete,
+    dependencies::add_blockers,
+    links::with_links,
+    rows::{NOW, checklist_item_from_row, list_from_row, tag_from_row},
+    tagging::attach_tags,
 };
 
 pub async fn insert_lists(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    entries: CreateListsRequest,
+) -> Result<CreateListsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+    insert_lists_on(&mut db_conn, user_id, entries).await
+}
+
+pub async fn insert_lists_on(
+    db_conn: &mut SqliteConnection,
+    user_id: UserID,
     entries: CreateListsRequest,
 ) -> Result<CreateListsResponse, SQLXError> {
-    if entries.len() == 0 {
+    if entries.is_empty() {
         return Err(InvalidArgument(
             "Caller Provided no entries to the database".to_string(),
         ));
@@ -27,22 +48,20 @@ pub async fn insert_lists(
     let values = entries
         .iter()
         .fold(String::new(), |acc, ele| {
-            acc + &format!("('{}'), ", ele.title)
+            acc + &format!("({}, '{}'), ", user_id.key(), escape_text(&ele.title))
         })
         .trim_end_matches(", ")
         .to_string();
 
-    let query = format!("INSERT INTO Lists (title) VALUES {} RETURNING *;", values);
+    let query = format!(
+        "INSERT INTO Lists (owner_id, title) VALUES {} RETURNING *;",
+        values
+    );
 
-    let mut db_conn = db_conn_pool.acquire().await?;
     let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
     let mut lists = HashSet::new();
     for row in query_result {
-        let list = List {
-            id: row.get("id"),
-            title: row.get("title"),
-        };
-        lists.insert(list);
+        lists.insert(list_from_row(&row));
     }
 
     Ok(lists)
@@ -50,57 +69,151 @@ pub async fn insert_lists(
 
 pub async fn insert_sets(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    entries: CreateSetsRequest,
+) -> Result<CreateSetsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+    insert_sets_on(&mut db_conn, user_id, entries).await
+}
+
+pub async fn insert_sets_on(
+    db_conn: &mut SqliteConnection,
+    user_id: UserID,
     entries: CreateSetsRequest,
 ) -> Result<CreateSetsResponse, SQLXError> {
-    if entries.len() == 0 {
+    if entries.is_empty() {
         return Err(InvalidArgument(
             "Caller Provided no entries to the database".to_string(),
         ));
     }
 
+    check_lists(
+        &mut *db_conn,
+        user_id,
+        entries.iter().map(|ele| ele.list_id),
+    )
+    .await?;
+
+    // New sets go after the existing sets of their list.
+    let query = format!(
+        "SELECT list_id, MAX(position) AS position FROM Sets WHERE list_id IN ({}) GROUP BY list_id;",
+        entries
+            .iter()
+            .map(|ele| ele.list_id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    let mut positions: HashMap<ListID, f64> = HashMap::new();
+    for row in sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await? {
+        positions.insert(row.get("list_id"), row.get("position"));
+    }
+
     let values = entries
         .iter()
         .fold(String::new(), |acc, ele| {
-            acc + &format!("({}, '{}'), ", ele.list_id, ele.title)
+            let position = positions.entry(ele.list_id).or_default();
+            *position += 1.0;
+
+            let parent_id = match ele.parent_id {
+                Some(pid) => pid.key().to_string(),
+                None => "NULL".to_string(),
+            };
+
+            acc + &format!(
+                "({}, {}, '{}', {}), ",
+                ele.list_id.key(),
+                parent_id,
+                escape_text(&ele.title),
+                position
+            )
         })
         .trim_end_matches(", ")
         .to_string();
 
     let query = format!(
-        "INSERT INTO Sets (list_id, title) VALUES {} RETURNING *;",
+        "INSERT INTO Sets (list_id, parent_id, title, position) VALUES {} RETURNING id;",
         values
     );
 
-    let mut db_conn = db_conn_pool.acquire().await?;
-    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
-    let mut sets = HashSet::new();
-    for row in query_result {
-        let set = Set {
-            id: row.get("id"),
-            list_id: row.get("list_id"),
-            title: row.get("title"),
-        };
-        sets.insert(set);
-    }
+    let set_ids = sqlx::query(query.as_str())
+        .fetch_all(&mut *db_conn)
+        .await?
+        .iter()
+        .map(|row| row.get("id"))
+        .collect::<Vec<SetID>>();
 
-    Ok(sets)
+    Ok(reread_sets(&mut *db_conn, &set_ids)
+        .await?
+        .into_iter()
+        .collect())
 }
 
 pub async fn insert_todos(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     entries: CreateToDosRequest,
 ) -> Result<CreateToDosResponse, SQLXError> {
-    if entries.len() == 0 {
+    // To dos are written along with their tags and blockers, which must land together.
+    let mut transaction = db_conn_pool.begin().await?;
+    let todos = insert_todos_on(&mut transaction, user_id, entries).await?;
+    transaction.commit().await?;
+    Ok(todos)
+}
+
+pub async fn insert_todos_on(
+    db_conn: &mut SqliteConnection,
+    user_id: UserID,
+    entries: CreateToDosRequest,
+) -> Result<CreateToDosResponse, SQLXError> {
+    if entries.is_empty() {
         return Err(InvalidArgument(
             "Caller Provided no entries to the database".to_string(),
         ));
     }
 
+    check_lists(
+        &mut *db_conn,
+        user_id,
+        entries.iter().map(|ele| ele.list_id),
+    )
+    .await?;
+    check_tags(
+        &mut *db_conn,
+        user_id,
+        entries.iter().flat_map(|ele| ele.tags.iter().copied()),
+    )
+    .await?;
+    check_todos(
+        &mut *db_conn,
+        user_id,
+        entries
+            .iter()
+            .flat_map(|ele| ele.blocked_by.iter().copied()),
+    )
+    .await?;
+
+    // New to dos go after the existing to dos of their list or set.
+    let query = format!(
+        "SELECT list_id, set_id, MAX(position) AS position FROM Todos WHERE list_id IN ({}) GROUP BY list_id, set_id;",
+        entries
+            .iter()
+            .map(|ele| ele.list_id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    let mut positions: HashMap<(ListID, Option<SetID>), f64> = HashMap::new();
+    for row in sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await? {
+        positions.insert((row.get("list_id"), row.get("set_id")), row.get("position"));
+    }
+
     let values = entries
         .iter()
         .fold(String::new(), |acc, ele| {
+            let position = positions.entry((ele.list_id, ele.set_id)).or_default();
+            *position += 1.0;
+
             let set_id = match ele.set_id {
-                Some(sid) => sid.to_string(),
+                Some(sid) => sid.key().to_string(),
                 None => "NULL".to_string(),
             };
 
@@ -109,38 +222,161 @@ pub async fn insert_todos(
                 None => "NULL".to_string(),
             };
 
-            let complete = match ele.complete {
-                Some(complete) => complete.to_string(),
-                None => false.to_string(),
+            let complete = ele.complete.unwrap_or_default();
+            let completed_at = if complete { NOW } else { "NULL" };
+
+            let notes = match &ele.notes {
+                Some(notes) => format!("'{}'", escape_text(notes)),
+                None => "NULL".to_string(),
             };
 
             acc + &format!(
-                "({}, {}, '{}', {}, {}), ",
-                ele.list_id, set_id, ele.title, complete, due_date
+                "({}, {}, '{}', {}, {}, {}, {}, {}, {}, {}), ",
+                ele.list_id.key(),
+                set_id,
+                escape_text(&ele.title),
+                complete,
+                due_date,
+                position,
+                notes,
+                ele.priority.unwrap_or_default().level(),
+                completed_at,
+                ele.auto_complete.unwrap_or_default()
             )
         })
         .trim_end_matches(", ")
         .to_string();
 
     let query = format!(
-        "INSERT INTO Todos (list_id, set_id, title, complete, due_date) VALUES {} RETURNING *;",
+        "INSERT INTO Todos (list_id, set_id, title, complete, due_date, position, notes, priority, completed_at, auto_complete) VALUES {} RETURNING id;",
         values
     );
 
+    let todo_ids = sqlx::query(query.as_str())
+        .fetch_all(&mut *db_conn)
+        .await?
+        .iter()
+        .map(|row| row.get("id"))
+        .collect::<Vec<ToDoID>>();
+
+    // The rows got consecutive ids in the order of `entries`, and are read back in id order, which
+    // lines them back up.
+    let todos = reread_todos(&mut *db_conn, &todo_ids).await?;
+    for (todo, entry) in todos.iter().zip(&entries) {
+        attach_tags(&mut *db_conn, &[todo.id], &entry.tags).await?;
+        add_blockers(&mut *db_conn, &[todo.id], &entry.blocked_by).await?;
+    }
+
+    with_links(&mut *db_conn, todos).await
+}
+
+pub async fn insert_tags(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    entries: CreateTagsRequest,
+) -> Result<CreateTagsResponse, SQLXError> {
+    if entries.is_empty() {
+        return Err(InvalidArgument(
+            "Caller Provided no entries to the database".to_string(),
+        ));
+    }
+
     let mut db_conn = db_conn_pool.acquire().await?;
+
+    let values = entries
+        .iter()
+        .fold(String::new(), |acc, ele| {
+            acc + &format!("({}, '{}'), ", user_id.key(), escape_text(&ele.name))
+        })
+        .trim_end_matches(", ")
+        .to_string();
+
+    let query = format!(
+        "INSERT INTO Tags (owner_id, name) VALUES {} RETURNING *;",
+        values
+    );
+
     let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
-    let mut todos = HashSet::new();
+    let mut tags = HashSet::new();
     for row in query_result {
-        let todo = ToDo {
-            id: row.get("id"),
-            list_id: row.get("list_id"),
-            set_id: row.get("set_id"),
-            title: row.get("title"),
-            complete: row.get("complete"),
-            due_date: row.get("due_date"),
-        };
-        todos.insert(todo);
+        tags.insert(tag_from_row(&row));
     }
 
-    Ok(todos)
+    Ok(tags)
+}
+
+pub async fn insert_checklist_items(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    entries: CreateChecklistItemsRequest,
+) -> Result<CreateChecklistItemsResponse, SQLXError> {
+    if entries.is_empty() {
+        return Err(InvalidArgument(
+            "Caller Provided no entries to the database".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    check_todos(
+        &mut transaction,
+        user_id,
+        entries.iter().map(|ele| ele.todo_id),
+    )
+    .await?;
+
+    // New items go at the end of their to do's checklist.
+    let query = format!(
+        "SELECT todo_id, MAX(position) AS position FROM ChecklistItems WHERE todo_id IN ({}) GROUP BY todo_id;",
+        entries
+            .iter()
+            .map(|ele| ele.todo_id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    let mut positions: HashMap<ToDoID, f64> = HashMap::new();
+    for row in sqlx::query(query.as_str())
+        .fetch_all(&mut *transaction)
+        .await?
+    {
+        positions.insert(row.get("todo_id"), row.get("position"));
+    }
+
+    let values = entries
+        .iter()
+        .fold(String::new(), |acc, ele| {
+            let position = positions.entry(ele.todo_id).or_default();
+            *position += 1.0;
+
+            acc + &format!(
+                "({}, '{}', {}, {}), ",
+                ele.todo_id.key(),
+                escape_text(&ele.title),
+                ele.done.unwrap_or_default(),
+                position
+            )
+        })
+        .trim_end_matches(", ")
+        .to_string();
+
+    let query = format!(
+        "INSERT INTO ChecklistItems (todo_id, title, done, position) VALUES {} RETURNING *;",
+        values
+    );
+
+    let query_result = sqlx::query(query.as_str())
+        .fetch_all(&mut *transaction)
+        .await?;
+    let mut items = HashSet::new();
+    for row in query_result {
+        items.insert(checklist_item_from_row(&row));
+    }
+
+    auto_complete(&mut transaction, positions.into_keys()).await?;
+    transaction.commit().await?;
+    Ok(items)
+}
+
+/// Escapes single quotes so a title can be embedded in a SQL string literal.
+pub(super) fn escape_text(text: &str) -> String {
+    text.replace('\'', "''")
 }
@@ -0,0 +1,53 @@
+use std::collections::{BTreeSet, HashMap};
+
+use sqlx::{Error as SQLXError, Row, SqliteConnection};
+
+use crate::types::{TagID, ToDo, ToDoID};
+
+/// Fills in the tags and blockers of to dos read with [`super::rows::todo_from_row`], keeping
+/// their order.
+pub(super) async fn with_links<Out: FromIterator<ToDo>>(
+    db_conn: &mut SqliteConnection,
+    todos: impl IntoIterator<Item = ToDo>,
+) -> Result<Out, SQLXError> {
+    let mut todos: Vec<ToDo> = todos.into_iter().collect();
+    if todos.is_empty() {
+        return Ok(todos.into_iter().collect());
+    }
+
+    let todo_ids = todos
+        .iter()
+        .map(|todo| todo.id.key().to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let query = format!(
+        "SELECT todo_id, tag_id FROM TodoTags WHERE todo_id IN ({});",
+        todo_ids
+    );
+    let mut tags: HashMap<ToDoID, BTreeSet<TagID>> = HashMap::new();
+    for row in sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await? {
+        tags.entry(row.get("todo_id"))
+            .or_default()
+            .insert(row.get("tag_id"));
+    }
+
+    let query = format!(
+        "SELECT todo_id, blocker_id FROM TodoDependencies WHERE todo_id IN ({});",
+        todo_ids
+    );
+    let mut blockers: HashMap<ToDoID, BTreeSet<ToDoID>> = HashMap::new();
+    for row in sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await? {
+        blockers
+            .entry(row.get("todo_id"))
+            .or_default()
+            .insert(row.get("blocker_id"));
+    }
+
+    for todo in &mut todos {
+        todo.tags = tags.remove(&todo.id).unwrap_or_default();
+        todo.blocked_by = blockers.remove(&todo.id).unwrap_or_default();
+    }
+
+    Ok(todos.into_iter().collect())
+}
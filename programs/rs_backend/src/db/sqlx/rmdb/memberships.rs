@@ -0,0 +1,241 @@
+use std::collections::{BTreeSet, HashSet};
+
+use actix_web::web::Data;
+use sqlx::{
+    Error::{self as SQLXError, InvalidArgument},
+    Pool, Row, Sqlite,
+};
+
+use crate::{
+    api::{
+        CreateMembershipsRequest, CreateMembershipsResponse, DeleteMembershipsRequest,
+        DeleteMembershipsResponse, ReadMembershipsRequest, ReadMembershipsResponse,
+        ReadSharedListsResponse, UpdateMembershipsRequest, UpdateMembershipsResponse,
+    },
+    types::{MembershipID, Role, SharedList, UserID},
+};
+
+use super::{
+    access::{check_owned_lists, owned_lists, readable_lists},
+    rows::{list_from_row, membership_from_row},
+};
+
+/// Memberships along with the username of their user, for [`membership_from_row`].
+const MEMBERSHIPS: &str = "SELECT m.list_id, m.user_id, u.username, m.role, m.created_at \
+     FROM ListMembers m JOIN Users u ON u.id = m.user_id";
+
+/// Shares lists `user_id` owns. A list can't be shared with its owner, and sharing it with the
+/// same user twice is a duplicate; change the role with [`update_memberships`] instead. Unknown
+/// usernames are refused like the owner's, so sharing can't tell which usernames exist.
+pub async fn insert_memberships(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    entries: CreateMembershipsRequest,
+) -> Result<CreateMembershipsResponse, SQLXError> {
+    if entries.is_empty() {
+        return Err(InvalidArgument(
+            "Caller Provided no entries to the database".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    check_owned_lists(
+        &mut transaction,
+        user_id,
+        entries.iter().map(|entry| entry.list_id),
+    )
+    .await?;
+
+    let mut output = BTreeSet::new();
+    for entry in entries {
+        let member_id: UserID = sqlx::query("SELECT id FROM Users WHERE username = ? AND id != ?;")
+            .bind(entry.username.trim())
+            .bind(user_id)
+            .fetch_optional(&mut *transaction)
+            .await?
+            .ok_or_else(|| {
+                InvalidArgument(format!(
+                    "List {} can't be shared with that user",
+                    entry.list_id.public()
+                ))
+            })?
+            .get("id");
+
+        sqlx::query("INSERT INTO ListMembers (list_id, user_id, role) VALUES (?, ?, ?);")
+            .bind(entry.list_id)
+            .bind(member_id)
+            .bind(entry.role.name())
+            .execute(&mut *transaction)
+            .await?;
+
+        let row = sqlx::query(&format!(
+            "{} WHERE m.list_id = ? AND m.user_id = ?;",
+            MEMBERSHIPS
+        ))
+        .bind(entry.list_id)
+        .bind(member_id)
+        .fetch_one(&mut *transaction)
+        .await?;
+        output.insert(membership_from_row(&row));
+    }
+
+    transaction.commit().await?;
+    Ok(output)
+}
+
+/// The memberships of every list `user_id` can read, so members see who else a list is shared
+/// with.
+pub async fn query_all_memberships(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ReadMembershipsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "{} WHERE m.list_id IN ({});",
+        MEMBERSHIPS,
+        readable_lists(user_id)
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut memberships = BTreeSet::new();
+    for row in query_result {
+        memberships.insert(membership_from_row(&row));
+    }
+
+    Ok(memberships)
+}
+
+pub async fn query_memberships(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: ReadMembershipsRequest,
+) -> Result<ReadMembershipsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "{} WHERE m.list_id IN ({}) AND m.list_id IN ({});",
+        MEMBERSHIPS,
+        adds.into_iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        readable_lists(user_id)
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut memberships = BTreeSet::new();
+    for row in query_result {
+        memberships.insert(membership_from_row(&row));
+    }
+
+    Ok(memberships)
+}
+
+/// Changes the role of members of lists `user_id` owns.
+pub async fn update_memberships(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    mods: UpdateMembershipsRequest,
+) -> Result<UpdateMembershipsResponse, SQLXError> {
+    if mods.is_empty() {
+        return Err(InvalidArgument(
+            "Can't have zero modification when running update on ListMembers Table.".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    let mut output = BTreeSet::new();
+
+    let update = format!(
+        "UPDATE ListMembers SET role = ? WHERE list_id = ? AND user_id = ? AND list_id IN ({});",
+        owned_lists(user_id)
+    );
+    for mods in mods {
+        let updated = sqlx::query(update.as_str())
+            .bind(mods.role.name())
+            .bind(mods.list_id)
+            .bind(mods.user_id)
+            .execute(&mut *transaction)
+            .await?;
+        if updated.rows_affected() == 0 {
+            continue;
+        }
+
+        let row = sqlx::query(&format!(
+            "{} WHERE m.list_id = ? AND m.user_id = ?;",
+            MEMBERSHIPS
+        ))
+        .bind(mods.list_id)
+        .bind(mods.user_id)
+        .fetch_one(&mut *transaction)
+        .await?;
+        output.replace(membership_from_row(&row));
+    }
+
+    transaction.commit().await?;
+    Ok(output)
+}
+
+/// Ends memberships. Owners can remove anyone from their lists, and members can leave a list
+/// shared with them.
+pub async fn delete_memberships(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: DeleteMembershipsRequest,
+) -> Result<DeleteMembershipsResponse, SQLXError> {
+    if adds.is_empty() {
+        return Err(InvalidArgument(
+            "Caller Provided no entries to the database".to_string(),
+        ));
+    }
+
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "DELETE FROM ListMembers WHERE (list_id, user_id) IN (VALUES {}) AND (list_id IN ({}) OR user_id = {}) RETURNING list_id, user_id;",
+        adds.into_iter()
+            .map(|id| format!("({}, {})", id.list_id.key(), id.user_id.key()))
+            .collect::<Vec<String>>()
+            .join(", "),
+        owned_lists(user_id),
+        user_id.key()
+    );
+
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut deleted_ids = HashSet::new();
+    for row in query_result {
+        deleted_ids.insert(MembershipID {
+            list_id: row.get("list_id"),
+            user_id: row.get("user_id"),
+        });
+    }
+
+    Ok(deleted_ids)
+}
+
+/// The lists other users have shared with `user_id`.
+pub async fn query_shared_lists(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ReadSharedListsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query_result = sqlx::query(
+        "SELECT l.*, m.role FROM Lists l JOIN ListMembers m ON m.list_id = l.id WHERE m.user_id = ? ORDER BY l.id;",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *db_conn)
+    .await?;
+
+    let mut lists = Vec::new();
+    for row in query_result {
+        lists.push(SharedList {
+            list: list_from_row(&row),
+            role: Role::from_name(row.get("role")),
+        });
+    }
+
+    Ok(lists)
+}
@@ -0,0 +1,198 @@
+use sqlx::{
+    Error as SQLXError, Pool, Sqlite,
+    migrate::{MigrateError, Migrator},
+};
+
+/// The schema, one migration per change, on top of the tables `tables_v2.sql` made.
+static MIGRATOR: Migrator = sqlx::migrate!("../database/migrations");
+
+/// Brings the schema of the database up to date, starting from an empty database, one made from
+/// `tables_v2.sql`, or one migrated before.
+///
+/// Foreign keys are off while the migrations run, since rebuilding a table drops it, and
+/// dropping a table with foreign keys on deletes every row that refers to it. They're checked
+/// once the migrations are done instead.
+pub async fn migrate(db_conn_pool: &Pool<Sqlite>) -> Result<(), MigrateError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    sqlx::query("PRAGMA foreign_keys = OFF;")
+        .execute(&mut *db_conn)
+        .await?;
+    let migrated = MIGRATOR.run(&mut *db_conn).await;
+    let violations = sqlx::query("PRAGMA foreign_key_check;")
+        .fetch_all(&mut *db_conn)
+        .await?;
+    sqlx::query("PRAGMA foreign_keys = ON;")
+        .execute(&mut *db_conn)
+        .await?;
+
+    migrated?;
+    if !violations.is_empty() {
+        return Err(MigrateError::Execute(SQLXError::Protocol(format!(
+            "The migrated database has {} rows referring to rows that don't exist",
+            violations.len()
+        ))));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use sqlx::{Row, sqlite::SqlitePoolOptions};
+
+    use super::*;
+
+    async fn baseline_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect(":memory:")
+            .await
+            .unwrap();
+        sqlx::raw_sql(include_str!("../../../../../database/tables_v2.sql"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool
+    }
+
+    /// The tables, indexes and triggers of a database, without the bookkeeping of the migrator.
+    async fn schema(pool: &Pool<Sqlite>) -> Vec<(String, String)> {
+        sqlx::query(
+            "SELECT type, name FROM sqlite_master WHERE name NOT LIKE '_sqlx%' ORDER BY type, name;",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| (row.get("type"), row.get("name")))
+        .collect()
+    }
+
+    #[actix_web::test]
+    async fn databases_made_from_the_base_schema_keep_their_rows() {
+        let pool = baseline_db().await;
+        sqlx::raw_sql(
+            "INSERT INTO Lists (title) VALUES ('Chores'), ('Projects');
+             INSERT INTO Sets (list_id, title) VALUES (1, 'Clean Bath Room'), (2, 'Garden');
+             INSERT INTO Todos (list_id, set_id, title, complete) VALUES
+                (1, 1, 'Scrub Toilet Bowl', 1), (1, NULL, 'Sweep Floor', 0),
+                (1, 2, 'Plant Tulips', 0);",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        migrate(&pool).await.unwrap();
+        // Running them again finds nothing left to do.
+        migrate(&pool).await.unwrap();
+
+        let fresh = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect(":memory:")
+            .await
+            .unwrap();
+        migrate(&fresh).await.unwrap();
+        assert_eq!(schema(&pool).await, schema(&fresh).await);
+
+        let lists = sqlx::query("SELECT l.title, u.username FROM Lists l JOIN Users u ON u.id = l.owner_id ORDER BY l.id;")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        let lists: Vec<(String, String)> = lists
+            .iter()
+            .map(|row| (row.get("title"), row.get("username")))
+            .collect();
+        assert_eq!(
+            lists,
+            [
+                ("Chores".to_string(), "admin".to_string()),
+                ("Projects".to_string(), "admin".to_string())
+            ]
+        );
+
+        // The to do filed under a set of another list follows its set.
+        let todos = sqlx::query(
+            "SELECT title, list_id, tdid, completed_at IS NOT NULL AS completed FROM Todos ORDER BY id;",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        let todos: Vec<(String, i64, i64, bool)> = todos
+            .iter()
+            .map(|row| {
+                (
+                    row.get("title"),
+                    row.get("list_id"),
+                    row.get("tdid"),
+                    row.get("completed"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            todos,
+            [
+                ("Scrub Toilet Bowl".to_string(), 1, 1, true),
+                ("Sweep Floor".to_string(), 1, 2, false),
+                ("Plant Tulips".to_string(), 2, 1, false),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn rebuilding_tables_doesnt_cascade() {
+        let pool = baseline_db().await;
+        sqlx::query("PRAGMA foreign_keys = ON;")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::raw_sql(
+            "INSERT INTO Lists (title) VALUES ('Chores');
+             INSERT INTO Sets (list_id, title) VALUES (1, 'Clean Bath Room');
+             INSERT INTO Todos (list_id, set_id, title) VALUES (1, 1, 'Mop Floor');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        migrate(&pool).await.unwrap();
+
+        let count: i64 = sqlx::query("SELECT COUNT(*) AS count FROM Todos;")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .get("count");
+        assert_eq!(count, 1);
+        let foreign_keys: bool = sqlx::query("PRAGMA foreign_keys;")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .get(0);
+        assert!(foreign_keys);
+    }
+
+    #[actix_web::test]
+    async fn keys_of_deleted_rows_arent_reused() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect(":memory:")
+            .await
+            .unwrap();
+        migrate(&pool).await.unwrap();
+        sqlx::raw_sql(
+            "INSERT INTO Users (username, password_hash) VALUES ('alice', '');
+             INSERT INTO Lists (owner_id, title) VALUES (1, 'Chores'), (1, 'Projects');
+             DELETE FROM Lists WHERE id = 2;",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let id: i64 =
+            sqlx::query("INSERT INTO Lists (owner_id, title) VALUES (1, 'Hobbies') RETURNING id;")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+                .get("id");
+        assert_eq!(id, 3);
+    }
+}
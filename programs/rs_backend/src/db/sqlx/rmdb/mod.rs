@@ -1,11 +1,59 @@
+mod access;
+mod accounts;
+mod addresses;
+mod changes;
+mod checklists;
 mod delete_some;
+mod dependencies;
+mod export_all;
+mod export_csv;
+mod export_markdown;
+mod export_taskwarrior;
+mod find_by_title;
+mod ids;
+mod import_all;
+mod import_csv;
+mod import_markdown;
+mod import_taskwarrior;
 mod insert_some;
+mod links;
+mod memberships;
+mod migrate;
+mod move_some;
+mod nesting;
 mod query_all;
 mod query_some;
+mod query_stats;
+mod read_options;
+mod rows;
+mod share_links;
+mod tagging;
+#[cfg(test)]
+mod test_db;
 mod update_some;
 
+pub use accounts::*;
+pub use changes::*;
 pub use delete_some::*;
+pub use export_all::*;
+pub use export_csv::*;
+pub use export_markdown::*;
+pub use export_taskwarrior::*;
+pub use find_by_title::*;
+pub use ids::*;
+pub use import_all::*;
+pub use import_csv::*;
+pub use import_markdown::*;
+pub use import_taskwarrior::*;
 pub use insert_some::*;
+pub use memberships::*;
+pub use migrate::*;
+pub use move_some::*;
 pub use query_all::*;
 pub use query_some::*;
+pub use query_stats::*;
+pub use share_links::*;
 pub use update_some::*;
+
+#[cfg(test)]
+pub(crate) use test_db::setup_test_db;
@@ -0,0 +1,567 @@
+use std::collections::BTreeSet;
+
+use actix_web::web::Data;
+use sqlx::{
+    Error::{self as SQLXError, InvalidArgument},
+    Pool, Row, Sqlite, SqliteConnection,
+};
+
+use crate::{
+    api::{
+        MoveChecklistItemsRequest, MoveChecklistItemsResponse, MoveSetsRequest, MoveSetsResponse,
+        MoveToDosRequest, MoveToDosResponse,
+    },
+    types::{ListID, Placement, PublicId, SetID, ToDoID, UserID},
+};
+
+use super::{
+    access::{check_todos, writable_lists, writable_todos},
+    addresses::{reread_todos, unnumber_todos},
+    links::with_links,
+    rows::{NOW, checklist_item_from_row, set_from_row},
+};
+
+pub async fn move_sets(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    moves: MoveSetsRequest,
+) -> Result<MoveSetsResponse, SQLXError> {
+    if moves.is_empty() {
+        return Err(InvalidArgument(
+            "Can't have zero moves when running move on Sets Table.".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    let mut output = BTreeSet::new();
+
+    let owned_sets = format!(
+        "SELECT list_id FROM Sets WHERE id = ? AND list_id IN ({});",
+        writable_lists(user_id)
+    );
+    for mv in moves {
+        let list_id: ListID = sqlx::query(owned_sets.as_str())
+            .bind(mv.set_id)
+            .fetch_optional(&mut *transaction)
+            .await?
+            .ok_or_else(|| InvalidArgument(format!("Set {} doesn't exist", mv.set_id.public())))?
+            .get("list_id");
+
+        if let Placement::Before(anchor) | Placement::After(anchor) = mv.to {
+            let anchor_list_id: Option<ListID> = sqlx::query(owned_sets.as_str())
+                .bind(anchor)
+                .fetch_optional(&mut *transaction)
+                .await?
+                .map(|row| row.get("list_id"));
+            if anchor_list_id != Some(list_id) {
+                return Err(InvalidArgument(format!(
+                    "Set {} can only be placed next to another set of list {}",
+                    mv.set_id.public(),
+                    list_id.public()
+                )));
+            }
+        }
+
+        let scope = format!("list_id = {}", list_id.key());
+        let position = position_for(&mut transaction, "Sets", &scope, mv.set_id, mv.to).await?;
+
+        let row = sqlx::query(&format!(
+            "UPDATE Sets SET (position, updated_at) = (?, {}) WHERE id = ? RETURNING *;",
+            NOW
+        ))
+        .bind(position)
+        .bind(mv.set_id)
+        .fetch_one(&mut *transaction)
+        .await?;
+        output.replace(set_from_row(&row));
+    }
+
+    transaction.commit().await?;
+    Ok(output)
+}
+
+pub async fn move_todos(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    moves: MoveToDosRequest,
+) -> Result<MoveToDosResponse, SQLXError> {
+    if moves.is_empty() {
+        return Err(InvalidArgument(
+            "Can't have zero moves when running move on Todos Table.".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    let mut moved = Vec::new();
+
+    let owned_todos = format!(
+        "SELECT list_id, set_id FROM Todos WHERE id = ? AND list_id IN ({});",
+        writable_lists(user_id)
+    );
+    for mv in moves {
+        check_todos(&mut transaction, user_id, [mv.todo_id]).await?;
+
+        // A to do placed next to another one joins that one's list and set.
+        let container_of = match mv.to {
+            Placement::Before(anchor) | Placement::After(anchor) => anchor,
+            Placement::First | Placement::Last => mv.todo_id,
+        };
+        let row = sqlx::query(owned_todos.as_str())
+            .bind(container_of)
+            .fetch_optional(&mut *transaction)
+            .await?
+            .ok_or_else(|| {
+                InvalidArgument(format!("To do {} doesn't exist", container_of.public()))
+            })?;
+        let list_id: ListID = row.get("list_id");
+        let set_id: Option<SetID> = row.get("set_id");
+
+        let scope = match set_id {
+            Some(set_id) => format!("list_id = {} AND set_id = {}", list_id.key(), set_id.key()),
+            None => format!("list_id = {} AND set_id IS NULL", list_id.key()),
+        };
+        let position = position_for(&mut transaction, "Todos", &scope, mv.todo_id, mv.to).await?;
+        let moved_todo = format!("id = {}", mv.todo_id.key());
+        unnumber_todos(&mut transaction, &moved_todo, list_id).await?;
+
+        let row = sqlx::query(
+            &format!(
+                "UPDATE Todos SET (list_id, set_id, position, updated_at) = (?, ?, ?, {}) WHERE id = ? RETURNING id;",
+                NOW
+            ),
+        )
+        .bind(list_id)
+        .bind(set_id)
+        .bind(position)
+        .bind(mv.todo_id)
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or_else(|| InvalidArgument(format!("To do {} doesn't exist", mv.todo_id.public())))?;
+        moved.push(row.get("id"));
+    }
+
+    // To dos moved to another list were numbered again, which `RETURNING` doesn't show.
+    let moved = reread_todos(&mut transaction, &moved).await?;
+    let output = with_links(&mut transaction, moved).await?;
+    transaction.commit().await?;
+    Ok(output)
+}
+
+pub async fn move_checklist_items(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    moves: MoveChecklistItemsRequest,
+) -> Result<MoveChecklistItemsResponse, SQLXError> {
+    if moves.is_empty() {
+        return Err(InvalidArgument(
+            "Can't have zero moves when running move on ChecklistItems Table.".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    let mut output = BTreeSet::new();
+
+    let owned_items = format!(
+        "SELECT todo_id FROM ChecklistItems WHERE id = ? AND todo_id IN ({});",
+        writable_todos(user_id)
+    );
+    for mv in moves {
+        let todo_id: ToDoID = sqlx::query(owned_items.as_str())
+            .bind(mv.item_id)
+            .fetch_optional(&mut *transaction)
+            .await?
+            .ok_or_else(|| {
+                InvalidArgument(format!(
+                    "Checklist item {} doesn't exist",
+                    mv.item_id.public()
+                ))
+            })?
+            .get("todo_id");
+
+        let scope = format!("todo_id = {}", todo_id.key());
+        let position = position_for(
+            &mut transaction,
+            "ChecklistItems",
+            &scope,
+            mv.item_id,
+            mv.to,
+        )
+        .await?;
+
+        let row = sqlx::query("UPDATE ChecklistItems SET position = ? WHERE id = ? RETURNING *;")
+            .bind(position)
+            .bind(mv.item_id)
+            .fetch_one(&mut *transaction)
+            .await?;
+        output.replace(checklist_item_from_row(&row));
+    }
+
+    transaction.commit().await?;
+    Ok(output)
+}
+
+/// Finds a position for `moved` among the rows of `table` matching `scope`. Positions are
+/// fractional, so the new position lies between the neighbours' and only the moved row changes.
+/// When the neighbours are tied, or too close together to split, the scope is renumbered first.
+async fn position_for<I: PublicId>(
+    db_conn: &mut SqliteConnection,
+    table: &str,
+    scope: &str,
+    moved: I,
+    to: Placement<I>,
+) -> Result<f64, SQLXError> {
+    if let Placement::Before(anchor) | Placement::After(anchor) = to
+        && anchor == moved
+    {
+        return Err(InvalidArgument(format!(
+            "Can't place {} next to itself",
+            moved.public()
+        )));
+    }
+
+    let query = format!(
+        "SELECT COUNT(*) = COUNT(DISTINCT position) AS untied FROM {} WHERE {};",
+        table, scope
+    );
+    let untied: bool = sqlx::query(query.as_str())
+        .fetch_one(&mut *db_conn)
+        .await?
+        .get("untied");
+    if !untied {
+        renumber(db_conn, table, scope).await?;
+    }
+
+    if let Some(position) = try_position(db_conn, table, scope, moved, to).await? {
+        return Ok(position);
+    }
+
+    renumber(db_conn, table, scope).await?;
+    try_position(db_conn, table, scope, moved, to)
+        .await?
+        .ok_or_else(|| SQLXError::Protocol(format!("Couldn't find a position in {}", table)))
+}
+
+async fn try_position<I: PublicId>(
+    db_conn: &mut SqliteConnection,
+    table: &str,
+    scope: &str,
+    moved: I,
+    to: Placement<I>,
+) -> Result<Option<f64>, SQLXError> {
+    let others = format!("{} WHERE {} AND id != {}", table, scope, moved.key());
+
+    let (low, high) = match to {
+        Placement::First => (None, bound(db_conn, "MIN", &others, "").await?),
+        Placement::Last => (bound(db_conn, "MAX", &others, "").await?, None),
+        Placement::Before(anchor) => {
+            let anchor = anchor_position(db_conn, table, scope, anchor).await?;
+            let condition = format!("AND position < {}", anchor);
+            (
+                bound(db_conn, "MAX", &others, &condition).await?,
+                Some(anchor),
+            )
+        }
+        Placement::After(anchor) => {
+            let anchor = anchor_position(db_conn, table, scope, anchor).await?;
+            let condition = format!("AND position > {}", anchor);
+            (
+                Some(anchor),
+                bound(db_conn, "MIN", &others, &condition).await?,
+            )
+        }
+    };
+
+    Ok(match (low, high) {
+        (None, None) => Some(1.0),
+        (Some(low), None) => Some(low + 1.0),
+        (None, Some(high)) => Some(high - 1.0),
+        (Some(low), Some(high)) => {
+            let middle = low / 2.0 + high / 2.0;
+            (low < middle && middle < high).then_some(middle)
+        }
+    })
+}
+
+async fn bound(
+    db_conn: &mut SqliteConnection,
+    aggregate: &str,
+    rows: &str,
+    condition: &str,
+) -> Result<Option<f64>, SQLXError> {
+    let query = format!(
+        "SELECT {}(position) AS position FROM {} {};",
+        aggregate, rows, condition
+    );
+    Ok(sqlx::query(query.as_str())
+        .fetch_one(&mut *db_conn)
+        .await?
+        .get("position"))
+}
+
+async fn anchor_position<I: PublicId>(
+    db_conn: &mut SqliteConnection,
+    table: &str,
+    scope: &str,
+    anchor: I,
+) -> Result<f64, SQLXError> {
+    let query = format!(
+        "SELECT position FROM {} WHERE {} AND id = {};",
+        table,
+        scope,
+        anchor.key()
+    );
+    Ok(sqlx::query(query.as_str())
+        .fetch_optional(&mut *db_conn)
+        .await?
+        .ok_or_else(|| InvalidArgument(format!("{} doesn't exist", anchor.public())))?
+        .get("position"))
+}
+
+/// Spaces the positions of a scope one apart, keeping their current order.
+async fn renumber(
+    db_conn: &mut SqliteConnection,
+    table: &str,
+    scope: &str,
+) -> Result<(), SQLXError> {
+    let query = format!(
+        "UPDATE {table} SET position = ranked.position \
+         FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY position, id) AS position FROM {table} WHERE {scope}) AS ranked \
+         WHERE {table}.id = ranked.id;",
+    );
+    sqlx::query(query.as_str()).execute(&mut *db_conn).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::{ChecklistItemID, MoveChecklistItem, MoveSet, MoveToDo};
+
+    use super::super::test_db::setup_test_db;
+    use super::*;
+
+    /// The ids of the rows of `table` matching `scope`, in the order of their positions, and
+    /// whether those positions are all different.
+    async fn order(db: &Pool<Sqlite>, table: &str, scope: &str) -> (Vec<i64>, bool) {
+        let query = format!(
+            "SELECT id, position FROM {} WHERE {} ORDER BY position, id;",
+            table, scope
+        );
+        let rows = sqlx::query(query.as_str()).fetch_all(db).await.unwrap();
+        let positions = rows
+            .iter()
+            .map(|row| row.get::<f64, _>("position"))
+            .collect::<Vec<_>>();
+        (
+            rows.iter().map(|row| row.get("id")).collect(),
+            positions.windows(2).all(|pair| pair[0] < pair[1]),
+        )
+    }
+
+    /// Adds a list of bob's holding to do 5, and a to do 6 in set 2 of list 2.
+    async fn seed_others(db: &Pool<Sqlite>) {
+        sqlx::raw_sql(
+            "INSERT INTO Lists (owner_id, title) VALUES (2, 'Errands');
+             INSERT INTO Todos (list_id, set_id, title) VALUES (3, NULL, 'Buy Milk');
+             INSERT INTO Todos (list_id, set_id, title) VALUES (2, 2, 'Plant Tulips');",
+        )
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn sets_move_within_their_list() {
+        let db = setup_test_db().await;
+        sqlx::query("INSERT INTO Sets (list_id, title) VALUES (1, 'Kitchen');")
+            .execute(&**db)
+            .await
+            .unwrap();
+        let move_set = |set_id, to| move_sets(db.clone(), UserID(1), vec![MoveSet { set_id, to }]);
+
+        // Every set of list 1 starts out at the same position, so they're renumbered first.
+        move_set(SetID(4), Placement::First).await.unwrap();
+        assert_eq!(
+            order(&db, "Sets", "list_id = 1").await,
+            (vec![4, 1, 3], true)
+        );
+        move_set(SetID(4), Placement::Last).await.unwrap();
+        assert_eq!(
+            order(&db, "Sets", "list_id = 1").await,
+            (vec![1, 3, 4], true)
+        );
+        move_set(SetID(4), Placement::Before(SetID(3)))
+            .await
+            .unwrap();
+        assert_eq!(
+            order(&db, "Sets", "list_id = 1").await,
+            (vec![1, 4, 3], true)
+        );
+        move_set(SetID(1), Placement::After(SetID(3)))
+            .await
+            .unwrap();
+        assert_eq!(
+            order(&db, "Sets", "list_id = 1").await,
+            (vec![4, 3, 1], true)
+        );
+
+        assert!(
+            move_set(SetID(1), Placement::Before(SetID(2)))
+                .await
+                .is_err()
+        );
+        assert!(
+            move_set(SetID(1), Placement::After(SetID(1)))
+                .await
+                .is_err()
+        );
+        assert!(
+            move_sets(
+                db.clone(),
+                UserID(2),
+                vec![MoveSet {
+                    set_id: SetID(1),
+                    to: Placement::First
+                }]
+            )
+            .await
+            .is_err()
+        );
+        assert_eq!(
+            order(&db, "Sets", "list_id = 1").await,
+            (vec![4, 3, 1], true)
+        );
+    }
+
+    #[actix_web::test]
+    async fn todos_join_the_list_and_set_of_their_anchor() {
+        let db = setup_test_db().await;
+        seed_others(&db).await;
+        let move_todo =
+            |todo_id, to| move_todos(db.clone(), UserID(1), vec![MoveToDo { todo_id, to }]);
+
+        move_todo(ToDoID(2), Placement::First).await.unwrap();
+        assert_eq!(order(&db, "Todos", "set_id = 1").await, (vec![2, 1], true));
+        move_todo(ToDoID(2), Placement::Last).await.unwrap();
+        assert_eq!(order(&db, "Todos", "set_id = 1").await, (vec![1, 2], true));
+
+        // The loose to do and the one in the nested set join set 1.
+        move_todo(ToDoID(3), Placement::After(ToDoID(1)))
+            .await
+            .unwrap();
+        move_todo(ToDoID(4), Placement::Before(ToDoID(1)))
+            .await
+            .unwrap();
+        assert_eq!(
+            order(&db, "Todos", "set_id = 1").await,
+            (vec![4, 1, 3, 2], true)
+        );
+
+        // Placed next to a to do of another list, it moves to that list and is numbered there.
+        let moved = move_todo(ToDoID(1), Placement::Before(ToDoID(6)))
+            .await
+            .unwrap();
+        let moved = moved.iter().next().unwrap();
+        assert_eq!((moved.list_id, moved.set_id), (ListID(2), Some(SetID(2))));
+        assert_eq!(moved.tdid, 2);
+        assert_eq!(order(&db, "Todos", "set_id = 2").await, (vec![1, 6], true));
+
+        assert!(
+            move_todo(ToDoID(2), Placement::Before(ToDoID(2)))
+                .await
+                .is_err()
+        );
+        // Bob's list isn't one alice can write to, whether as the anchor or the moved to do.
+        assert!(
+            move_todo(ToDoID(2), Placement::After(ToDoID(5)))
+                .await
+                .is_err()
+        );
+        assert!(
+            move_todos(
+                db.clone(),
+                UserID(2),
+                vec![MoveToDo {
+                    todo_id: ToDoID(5),
+                    to: Placement::After(ToDoID(2))
+                }]
+            )
+            .await
+            .is_err()
+        );
+        assert_eq!(order(&db, "Todos", "list_id = 3").await, (vec![5], true));
+    }
+
+    #[actix_web::test]
+    async fn checklist_items_move_within_their_checklist() {
+        let db = setup_test_db().await;
+        sqlx::query(
+            "INSERT INTO ChecklistItems (todo_id, title) VALUES
+                (1, 'Brush'), (1, 'Rinse'), (1, 'Dry'), (2, 'Fill Bucket');",
+        )
+        .execute(&**db)
+        .await
+        .unwrap();
+        let move_item = |item_id, to| {
+            move_checklist_items(
+                db.clone(),
+                UserID(1),
+                vec![MoveChecklistItem { item_id, to }],
+            )
+        };
+
+        move_item(ChecklistItemID(3), Placement::First)
+            .await
+            .unwrap();
+        assert_eq!(
+            order(&db, "ChecklistItems", "todo_id = 1").await,
+            (vec![3, 1, 2], true)
+        );
+        move_item(ChecklistItemID(1), Placement::After(ChecklistItemID(2)))
+            .await
+            .unwrap();
+        assert_eq!(
+            order(&db, "ChecklistItems", "todo_id = 1").await,
+            (vec![3, 2, 1], true)
+        );
+        move_item(ChecklistItemID(1), Placement::Before(ChecklistItemID(3)))
+            .await
+            .unwrap();
+        move_item(ChecklistItemID(3), Placement::Last)
+            .await
+            .unwrap();
+        assert_eq!(
+            order(&db, "ChecklistItems", "todo_id = 1").await,
+            (vec![1, 2, 3], true)
+        );
+
+        assert!(
+            move_item(ChecklistItemID(1), Placement::Before(ChecklistItemID(4)))
+                .await
+                .is_err()
+        );
+        assert!(
+            move_item(ChecklistItemID(2), Placement::After(ChecklistItemID(2)))
+                .await
+                .is_err()
+        );
+        assert!(
+            move_checklist_items(
+                db.clone(),
+                UserID(2),
+                vec![MoveChecklistItem {
+                    item_id: ChecklistItemID(1),
+                    to: Placement::First
+                }]
+            )
+            .await
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn placements_are_tagged_like_targets() {
+        let placements: Vec<Placement<SetID>> =
+            serde_json::from_str(r#"[{"target": "first"}, {"target": "after", "id": 3}]"#).unwrap();
+        assert_eq!(placements, [Placement::First, Placement::After(SetID(3))]);
+    }
+}
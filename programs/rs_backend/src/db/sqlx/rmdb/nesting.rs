@@ -0,0 +1,48 @@
+use sqlx::{
+    Error::{self as SQLXError, InvalidArgument},
+    Row, SqliteConnection,
+};
+
+use crate::types::SetID;
+
+/// A query selecting the ids of the sets in `set_ids` (a comma separated list) and of every set
+/// nested inside them, for use in `id IN (...)`.
+pub(super) fn subtree_ids(set_ids: &str) -> String {
+    format!(
+        "WITH RECURSIVE subtree(id) AS (\
+            SELECT id FROM Sets WHERE id IN ({}) \
+            UNION SELECT Sets.id FROM Sets JOIN subtree ON Sets.parent_id = subtree.id\
+         ) SELECT id FROM subtree",
+        set_ids
+    )
+}
+
+/// Makes sure that nesting the sets matching `moved` (a `WHERE` condition on Sets) inside
+/// `parent_id` wouldn't make any of them its own ancestor.
+pub(super) async fn check_parent(
+    db_conn: &mut SqliteConnection,
+    moved: &str,
+    parent_id: SetID,
+) -> Result<(), SQLXError> {
+    let query = format!(
+        "WITH RECURSIVE ancestors(id, parent_id) AS (\
+            SELECT id, parent_id FROM Sets WHERE id = {} \
+            UNION SELECT Sets.id, Sets.parent_id FROM Sets JOIN ancestors ON Sets.id = ancestors.parent_id\
+         ) SELECT COUNT(*) AS cycles FROM ancestors WHERE id IN (SELECT id FROM Sets WHERE {});",
+        parent_id.key(),
+        moved
+    );
+    let cycles: i64 = sqlx::query(query.as_str())
+        .fetch_one(&mut *db_conn)
+        .await?
+        .get("cycles");
+
+    if cycles > 0 {
+        return Err(InvalidArgument(format!(
+            "Nesting sets under set {} would put a set inside itself",
+            parent_id.public()
+        )));
+    }
+
+    Ok(())
+}
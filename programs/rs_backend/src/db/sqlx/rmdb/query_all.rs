@@ -1,29 +1,39 @@
 use std::collections::BTreeSet;
 
 use actix_web::web::Data;
-use sqlx::{Error as SQLXError, Pool, Row, Sqlite};
+use sqlx::{Error as SQLXError, Pool, Sqlite};
 
 use crate::{
-    api::{ReadListsResponse, ReadSetsResponse, ReadToDosResponse},
-    types::{List, Set, ToDo},
+    api::{
+        ReadChecklistItemsResponse, ReadListsResponse, ReadSetsResponse, ReadTagsResponse,
+        ReadToDosResponse,
+    },
+    types::{ToDo, ToDoReadOptions, UserID},
+};
+
+use super::{
+    access::{readable_lists, readable_todos},
+    checklists::with_checklists,
+    links::with_links,
+    read_options::{todo_conditions, todo_order},
+    rows::{checklist_item_from_row, list_from_row, set_from_row, tag_from_row, todo_from_row},
 };
 
 pub async fn query_all_lists(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
 ) -> Result<ReadListsResponse, SQLXError> {
     let mut db_conn = db_conn_pool.acquire().await?;
 
-    let query_result = sqlx::query("SELECT * FROM lists")
-        .fetch_all(&mut *db_conn)
-        .await?;
+    let query = format!(
+        "SELECT * FROM lists WHERE id IN ({})",
+        readable_lists(user_id)
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
 
     let mut lists = BTreeSet::new();
     for row in query_result {
-        let list = List {
-            id: row.get("id"),
-            title: row.get("title"),
-        };
-        lists.insert(list);
+        lists.insert(list_from_row(&row));
     }
 
     Ok(lists)
@@ -31,21 +41,19 @@ pub async fn query_all_lists(
 
 pub async fn query_all_sets(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
 ) -> Result<ReadSetsResponse, SQLXError> {
     let mut db_conn = db_conn_pool.acquire().await?;
 
-    let query_result = sqlx::query("SELECT * FROM sets")
-        .fetch_all(&mut *db_conn)
-        .await?;
+    let query = format!(
+        "SELECT * FROM sets WHERE list_id IN ({}) ORDER BY list_id, position, id",
+        readable_lists(user_id)
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
 
-    let mut sets = BTreeSet::new();
+    let mut sets = Vec::new();
     for row in query_result {
-        let set = Set {
-            id: row.get("id"),
-            list_id: row.get("list_id"),
-            title: row.get("title"),
-        };
-        sets.insert(set);
+        sets.push(set_from_row(&row));
     }
 
     Ok(sets)
@@ -53,25 +61,67 @@ pub async fn query_all_sets(
 
 pub async fn query_all_todos(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    options: ToDoReadOptions,
 ) -> Result<ReadToDosResponse, SQLXError> {
     let mut db_conn = db_conn_pool.acquire().await?;
 
-    let query_result = sqlx::query("SELECT * FROM todos")
+    let query = format!(
+        "SELECT t.* FROM todos t LEFT JOIN sets s ON s.id = t.set_id WHERE t.list_id IN ({}) AND {} ORDER BY {}",
+        readable_lists(user_id),
+        todo_conditions(&options),
+        todo_order(&options)
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut todos = Vec::new();
+    for row in query_result {
+        todos.push(todo_from_row(&row));
+    }
+
+    let todos: Vec<ToDo> = with_links(&mut db_conn, todos).await?;
+    if options.checklist {
+        with_checklists(&mut db_conn, todos).await
+    } else {
+        Ok(todos)
+    }
+}
+
+pub async fn query_all_tags(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ReadTagsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query_result = sqlx::query("SELECT * FROM tags WHERE owner_id = ?")
+        .bind(user_id)
         .fetch_all(&mut *db_conn)
         .await?;
 
-    let mut todos = BTreeSet::new();
+    let mut tags = BTreeSet::new();
+    for row in query_result {
+        tags.insert(tag_from_row(&row));
+    }
+
+    Ok(tags)
+}
+
+pub async fn query_all_checklist_items(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ReadChecklistItemsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "SELECT * FROM ChecklistItems WHERE todo_id IN ({}) ORDER BY todo_id, position, id",
+        readable_todos(user_id)
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut items = Vec::new();
     for row in query_result {
-        let todo = ToDo {
-            id: row.get("id"),
-            list_id: row.get("list_id"),
-            set_id: row.get("set_id"),
-            title: row.get("title"),
-            complete: row.get("complete"),
-            due_date: row.get("due_date"),
-        };
-        todos.insert(todo);
+        items.push(checklist_item_from_row(&row));
     }
 
-    Ok(todos)
+    Ok(items)
 }
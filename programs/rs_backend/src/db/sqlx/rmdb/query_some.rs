@@ -1,39 +1,50 @@
 use std::collections::BTreeSet;
 
 use actix_web::web::Data;
-use sqlx::{Error as SQLXError, Pool, Row, Sqlite};
+use sqlx::{Error as SQLXError, Pool, Sqlite};
 
 use crate::{
     api::{
-        ReadListsRequest, ReadListsResponse, ReadSetsRequest, ReadSetsResponse, ReadToDosRequest,
+        ReadChecklistItemsRequest, ReadChecklistItemsResponse, ReadListsRequest, ReadListsResponse,
+        ReadSetsRequest, ReadSetsResponse, ReadTagsRequest, ReadTagsResponse, ReadToDosRequest,
         ReadToDosResponse,
     },
-    types::{List, Set, SetQueryTarget, ToDo, ToDoQueryTarget},
+    types::{
+        ChecklistItemQueryTarget, SetQueryTarget, ToDo, ToDoQueryTarget, ToDoReadOptions, UserID,
+    },
+};
+
+use super::{
+    access::{readable_lists, readable_todos},
+    addresses::{set_address_ids, todo_address_ids},
+    checklists::with_checklists,
+    links::with_links,
+    nesting::subtree_ids,
+    read_options::{todo_conditions, todo_order},
+    rows::{checklist_item_from_row, list_from_row, set_from_row, tag_from_row, todo_from_row},
 };
 
 pub async fn query_lists(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     adds: ReadListsRequest,
 ) -> Result<ReadListsResponse, SQLXError> {
     let mut db_conn = db_conn_pool.acquire().await?;
 
     let query = format!(
-        "SELECT * FROM Lists WHERE id IN ({});",
+        "SELECT * FROM Lists WHERE id IN ({}) AND id IN ({});",
         adds.into_iter()
-            .map(|id| id.to_string())
+            .map(|id| id.key().to_string())
             .collect::<Vec<String>>()
-            .join(", ")
+            .join(", "),
+        readable_lists(user_id)
     );
 
     let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
 
     let mut lists = BTreeSet::new();
     for row in query_result {
-        let list = List {
-            id: row.get("id"),
-            title: row.get("title"),
-        };
-        lists.insert(list);
+        lists.insert(list_from_row(&row));
     }
 
     Ok(lists)
@@ -41,49 +52,60 @@ pub async fn query_lists(
 
 pub async fn query_sets(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     adds: ReadSetsRequest,
 ) -> Result<ReadSetsResponse, SQLXError> {
     let mut db_conn = db_conn_pool.acquire().await?;
 
-    let (whole_list_ids, singular_ids) = {
-        let mut acc =
-            adds.into_iter()
-                .fold((String::new(), String::new()), |(mut wl, mut s), ele| {
-                    match ele {
-                        SetQueryTarget::List(id) => {
-                            wl.push_str(&id.to_string());
-                            wl.push_str(", ");
-                        }
-                        SetQueryTarget::Set(id) => {
-                            s.push_str(&id.to_string());
-                            s.push_str(", ");
-                        }
+    let (whole_list_ids, singular_ids, subtree_root_ids, addressed_ids) = {
+        let mut acc = adds.into_iter().fold(
+            (String::new(), String::new(), String::new(), String::new()),
+            |(mut wl, mut s, mut st, mut a), ele| {
+                match ele {
+                    SetQueryTarget::List(id) => {
+                        wl.push_str(&id.key().to_string());
+                        wl.push_str(", ");
+                    }
+                    SetQueryTarget::Set(id) => {
+                        s.push_str(&id.key().to_string());
+                        s.push_str(", ");
                     }
+                    SetQueryTarget::Subtree(id) => {
+                        st.push_str(&id.key().to_string());
+                        st.push_str(", ");
+                    }
+                    SetQueryTarget::Address(address) => {
+                        a.push_str(&set_address_ids(&address));
+                        a.push_str(" UNION ");
+                    }
+                }
 
-                    (wl, s)
-                });
+                (wl, s, st, a)
+            },
+        );
 
         acc.0 = acc.0.trim_end_matches(", ").to_string();
         acc.1 = acc.1.trim_end_matches(", ").to_string();
+        acc.2 = acc.2.trim_end_matches(", ").to_string();
+        acc.3 = acc.3.trim_end_matches(" UNION ").to_string();
 
         acc
     };
 
     let query = format!(
-        "SELECT * FROM Sets WHERE list_id IN ({}) OR id IN ({});",
-        whole_list_ids, singular_ids
+        "SELECT * FROM Sets WHERE (list_id IN ({}) OR id IN ({}) OR id IN ({}) OR id IN ({})) AND list_id IN ({}) ORDER BY list_id, position, id;",
+        whole_list_ids,
+        singular_ids,
+        subtree_ids(&subtree_root_ids),
+        addressed_ids,
+        readable_lists(user_id)
     );
 
     let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
 
-    let mut sets = BTreeSet::new();
+    let mut sets = Vec::new();
     for row in query_result {
-        let set = Set {
-            id: row.get("id"),
-            list_id: row.get("list_id"),
-            title: row.get("title"),
-        };
-        sets.insert(set);
+        sets.push(set_from_row(&row));
     }
 
     Ok(sets)
@@ -91,59 +113,162 @@ pub async fn query_sets(
 
 pub async fn query_todos(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     adds: ReadToDosRequest,
+    options: ToDoReadOptions,
 ) -> Result<ReadToDosResponse, SQLXError> {
     let mut db_conn = db_conn_pool.acquire().await?;
 
-    let (whole_list_ids, whole_set_ids, singular_ids) = {
+    let (whole_list_ids, whole_set_ids, singular_ids, subtree_root_ids, tag_ids, addressed_ids) = {
         let mut acc = adds.into_iter().fold(
-            (String::new(), String::new(), String::new()),
-            |(mut wl, mut ws, mut s), ele| {
+            (
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ),
+            |(mut wl, mut ws, mut s, mut st, mut t, mut a), ele| {
                 match ele {
                     ToDoQueryTarget::List(id) => {
-                        wl.push_str(&id.to_string());
+                        wl.push_str(&id.key().to_string());
                         wl.push_str(", ");
                     }
                     ToDoQueryTarget::Set(id) => {
-                        ws.push_str(&id.to_string());
+                        ws.push_str(&id.key().to_string());
                         ws.push_str(", ");
                     }
                     ToDoQueryTarget::ToDo(id) => {
-                        s.push_str(&id.to_string());
+                        s.push_str(&id.key().to_string());
                         s.push_str(", ");
                     }
+                    ToDoQueryTarget::Subtree(id) => {
+                        st.push_str(&id.key().to_string());
+                        st.push_str(", ");
+                    }
+                    ToDoQueryTarget::Tag(id) => {
+                        t.push_str(&id.key().to_string());
+                        t.push_str(", ");
+                    }
+                    ToDoQueryTarget::Address(address) => {
+                        a.push_str(&todo_address_ids(&address));
+                        a.push_str(" UNION ");
+                    }
                 }
 
-                (wl, ws, s)
+                (wl, ws, s, st, t, a)
             },
         );
 
         acc.0 = acc.0.trim_end_matches(", ").to_string();
         acc.1 = acc.1.trim_end_matches(", ").to_string();
         acc.2 = acc.2.trim_end_matches(", ").to_string();
+        acc.3 = acc.3.trim_end_matches(", ").to_string();
+        acc.4 = acc.4.trim_end_matches(", ").to_string();
+        acc.5 = acc.5.trim_end_matches(" UNION ").to_string();
 
         acc
     };
 
     let query: String = format!(
-        "SELECT * FROM todos WHERE list_id IN ({}) OR set_id IN ({}) OR id IN ({});",
-        whole_list_ids, whole_set_ids, singular_ids
+        "SELECT t.* FROM todos t LEFT JOIN sets s ON s.id = t.set_id WHERE (t.list_id IN ({}) OR t.set_id IN ({}) OR t.id IN ({}) OR t.set_id IN ({}) OR t.id IN (SELECT todo_id FROM TodoTags WHERE tag_id IN ({})) OR t.id IN ({})) AND t.list_id IN ({}) AND {} ORDER BY {};",
+        whole_list_ids,
+        whole_set_ids,
+        singular_ids,
+        subtree_ids(&subtree_root_ids),
+        tag_ids,
+        addressed_ids,
+        readable_lists(user_id),
+        todo_conditions(&options),
+        todo_order(&options)
+    );
+
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut todos = Vec::new();
+    for row in query_result {
+        todos.push(todo_from_row(&row));
+    }
+
+    let todos: Vec<ToDo> = with_links(&mut db_conn, todos).await?;
+    if options.checklist {
+        with_checklists(&mut db_conn, todos).await
+    } else {
+        Ok(todos)
+    }
+}
+
+pub async fn query_tags(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: ReadTagsRequest,
+) -> Result<ReadTagsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "SELECT * FROM Tags WHERE id IN ({}) AND owner_id = {};",
+        adds.into_iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        user_id.key()
+    );
+
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut tags = BTreeSet::new();
+    for row in query_result {
+        tags.insert(tag_from_row(&row));
+    }
+
+    Ok(tags)
+}
+
+pub async fn query_checklist_items(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: ReadChecklistItemsRequest,
+) -> Result<ReadChecklistItemsResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let (whole_todo_ids, singular_ids) = {
+        let mut acc =
+            adds.into_iter()
+                .fold((String::new(), String::new()), |(mut wt, mut s), ele| {
+                    match ele {
+                        ChecklistItemQueryTarget::ToDo(id) => {
+                            wt.push_str(&id.key().to_string());
+                            wt.push_str(", ");
+                        }
+                        ChecklistItemQueryTarget::Item(id) => {
+                            s.push_str(&id.key().to_string());
+                            s.push_str(", ");
+                        }
+                    }
+
+                    (wt, s)
+                });
+
+        acc.0 = acc.0.trim_end_matches(", ").to_string();
+        acc.1 = acc.1.trim_end_matches(", ").to_string();
+
+        acc
+    };
+
+    let query = format!(
+        "SELECT * FROM ChecklistItems WHERE (todo_id IN ({}) OR id IN ({})) AND todo_id IN ({}) ORDER BY todo_id, position, id;",
+        whole_todo_ids,
+        singular_ids,
+        readable_todos(user_id)
     );
 
     let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
 
-    let mut todos = BTreeSet::new();
+    let mut items = Vec::new();
     for row in query_result {
-        let todo = ToDo {
-            id: row.get("id"),
-            list_id: row.get("list_id"),
-            set_id: row.get("set_id"),
-            title: row.get("title"),
-            complete: row.get("complete"),
-            due_date: row.get("due_date"),
-        };
-        todos.insert(todo);
+        items.push(checklist_item_from_row(&row));
     }
 
-    Ok(todos)
+    Ok(items)
 }
@@ -0,0 +1,128 @@
+use actix_web::web::Data;
+use sqlx::{Error as SQLXError, Pool, Row, Sqlite, sqlite::SqliteRow};
+
+use crate::{
+    api::StatsResponse,
+    types::{ListStats, SetStats, Stats, ToDoStats, UserID},
+};
+
+use super::access::readable_lists;
+
+/// The aggregates behind [`ToDoStats`], over to dos joined as `t`. Rows without a to do (from a
+/// `LEFT JOIN` on an empty list or set) count for nothing.
+const TODO_AGGREGATES: &str = "\
+    COUNT(t.id) AS total, \
+    COALESCE(SUM(t.complete), 0) AS completed, \
+    COALESCE(SUM(NOT t.complete AND julianday(t.due_date) < julianday('now')), 0) AS overdue, \
+    strftime('%Y-%m-%dT%H:%M:%fZ', MIN(CASE WHEN NOT t.complete AND julianday(t.due_date) >= julianday('now') THEN julianday(t.due_date) END)) AS next_due_date";
+
+pub async fn query_stats(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<StatsResponse, SQLXError> {
+    // A transaction keeps the reads on the same snapshot of the database.
+    let mut transaction = db_conn_pool.begin().await?;
+
+    let query = format!(
+        "SELECT l.id AS list_id, {} FROM Lists l LEFT JOIN Todos t ON t.list_id = l.id WHERE l.id IN ({}) GROUP BY l.id ORDER BY l.id;",
+        TODO_AGGREGATES,
+        readable_lists(user_id)
+    );
+    let mut lists = Vec::new();
+    for row in sqlx::query(query.as_str())
+        .fetch_all(&mut *transaction)
+        .await?
+    {
+        lists.push(ListStats {
+            list_id: row.get("list_id"),
+            todos: todo_stats_from_row(&row),
+        });
+    }
+
+    // Every set is paired with itself and each set nested inside it, so its to dos roll up.
+    let query = format!(
+        "WITH RECURSIVE subtree(root_id, set_id) AS (\
+            SELECT id, id FROM Sets WHERE list_id IN ({}) \
+            UNION ALL SELECT subtree.root_id, Sets.id FROM Sets JOIN subtree ON Sets.parent_id = subtree.set_id\
+         ) SELECT s.id AS set_id, s.list_id, {} FROM subtree JOIN Sets s ON s.id = subtree.root_id \
+         LEFT JOIN Todos t ON t.set_id = subtree.set_id GROUP BY s.id ORDER BY s.list_id, s.position, s.id;",
+        readable_lists(user_id),
+        TODO_AGGREGATES
+    );
+    let mut sets = Vec::new();
+    for row in sqlx::query(query.as_str())
+        .fetch_all(&mut *transaction)
+        .await?
+    {
+        sets.push(SetStats {
+            set_id: row.get("set_id"),
+            list_id: row.get("list_id"),
+            todos: todo_stats_from_row(&row),
+        });
+    }
+
+    transaction.commit().await?;
+    Ok(Stats { lists, sets })
+}
+
+fn todo_stats_from_row(row: &SqliteRow) -> ToDoStats {
+    let total: i64 = row.get("total");
+    let completed: i64 = row.get("completed");
+
+    ToDoStats {
+        total,
+        completed,
+        overdue: row.get("overdue"),
+        completion: if total == 0 {
+            0.0
+        } else {
+            completed as f64 * 100.0 / total as f64
+        },
+        next_due_date: row.get("next_due_date"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::SetID;
+
+    use super::super::test_db::setup_test_db;
+
+    #[actix_web::test]
+    async fn sets_roll_up_their_nested_sets() {
+        let db = setup_test_db().await;
+        sqlx::raw_sql(
+            "UPDATE Todos SET complete = 1 WHERE id = 1;
+             UPDATE Todos SET due_date = '2000-01-01T00:00:00Z' WHERE id = 2;
+             UPDATE Todos SET due_date = '2999-01-01T00:00:00+00:00' WHERE id = 4;",
+        )
+        .execute(&**db)
+        .await
+        .unwrap();
+
+        let stats = query_stats(db, UserID(1)).await.unwrap();
+
+        assert_eq!(
+            stats
+                .lists
+                .iter()
+                .map(|l| l.todos.total)
+                .collect::<Vec<_>>(),
+            vec![4, 0]
+        );
+        let clean_bath_room = &stats.sets[0];
+        assert_eq!(clean_bath_room.set_id, SetID(1));
+        assert_eq!(
+            clean_bath_room.todos,
+            ToDoStats {
+                total: 3,
+                completed: 1,
+                overdue: 1,
+                completion: 100.0 / 3.0,
+                next_due_date: Some("2999-01-01T00:00:00Z".parse().unwrap()),
+            }
+        );
+        assert_eq!(stats.lists[1].todos.completion, 0.0);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::types::{SortOrder, ToDoReadOptions, ToDoSort};
+
+/// The filters of `options` as a `WHERE` condition on Todos aliased as `t`. Dates are compared
+/// with `julianday` since they aren't all stored with the same UTC offset notation.
+pub(super) fn todo_conditions(options: &ToDoReadOptions) -> String {
+    let mut conditions = vec!["1".to_string()];
+
+    if let Some(complete) = options.complete {
+        conditions.push(format!("t.complete = {}", complete));
+    }
+    if let Some(priority) = options.min_priority {
+        conditions.push(format!("t.priority >= {}", priority.level()));
+    }
+    if let Some(due_after) = options.due_after {
+        conditions.push(format!(
+            "julianday(t.due_date) > julianday('{}')",
+            due_after.to_rfc3339()
+        ));
+    }
+    if let Some(due_before) = options.due_before {
+        conditions.push(format!(
+            "julianday(t.due_date) < julianday('{}')",
+            due_before.to_rfc3339()
+        ));
+    }
+    if let Some(updated_after) = options.updated_after {
+        conditions.push(format!(
+            "julianday(t.updated_at) > julianday('{}')",
+            updated_after.to_rfc3339()
+        ));
+    }
+    if let Some(completed_after) = options.completed_after {
+        conditions.push(format!(
+            "julianday(t.completed_at) > julianday('{}')",
+            completed_after.to_rfc3339()
+        ));
+    }
+
+    if options.actionable {
+        conditions.push(
+            "NOT t.complete AND NOT EXISTS (SELECT 1 FROM TodoDependencies d JOIN Todos b ON b.id = d.blocker_id WHERE d.todo_id = t.id AND NOT b.complete)"
+                .to_string(),
+        );
+    }
+
+    conditions.join(" AND ")
+}
+
+/// The sort of `options` as an `ORDER BY` clause on Todos aliased as `t`, joined to their sets
+/// as `s`. To dos without a value to sort by go last.
+pub(super) fn todo_order(options: &ToDoReadOptions) -> String {
+    let direction = match options.order {
+        SortOrder::Ascending => "ASC",
+        SortOrder::Descending => "DESC",
+    };
+    let column = match options.sort {
+        ToDoSort::Position => {
+            return format!(
+                "t.list_id {direction}, s.position {direction}, t.set_id {direction}, t.position {direction}, t.id {direction}"
+            );
+        }
+        ToDoSort::Title => "t.title",
+        ToDoSort::DueDate => "julianday(t.due_date)",
+        ToDoSort::Priority => "t.priority",
+        ToDoSort::CreatedAt => "julianday(t.created_at)",
+        ToDoSort::UpdatedAt => "julianday(t.updated_at)",
+        ToDoSort::CompletedAt => "julianday(t.completed_at)",
+    };
+
+    format!("{column} {direction} NULLS LAST, t.id {direction}")
+}
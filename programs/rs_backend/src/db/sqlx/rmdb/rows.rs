@@ -0,0 +1,107 @@
+use std::collections::BTreeSet;
+
+use sqlx::{Row, sqlite::SqliteRow};
+use uuid::Uuid;
+
+use crate::types::{
+    ChecklistItem, List, Membership, Priority, Role, Set, ShareLink, Tag, ToDo, User,
+};
+
+/// The current time, in the form the timestamp columns are stored in.
+pub(super) const NOW: &str = "strftime('%Y-%m-%dT%H:%M:%fZ', 'now')";
+
+pub(super) fn checklist_item_from_row(row: &SqliteRow) -> ChecklistItem {
+    ChecklistItem {
+        id: row.get("id"),
+        todo_id: row.get("todo_id"),
+        title: row.get("title"),
+        done: row.get("done"),
+        position: row.get("position"),
+    }
+}
+
+pub(super) fn list_from_row(row: &SqliteRow) -> List {
+    List {
+        id: row.get("id"),
+        owner_id: row.get("owner_id"),
+        title: row.get("title"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Reads a membership joined with its user, see [`super::memberships`].
+pub(super) fn membership_from_row(row: &SqliteRow) -> Membership {
+    Membership {
+        list_id: row.get("list_id"),
+        user_id: row.get("user_id"),
+        username: row.get("username"),
+        role: Role::from_name(row.get("role")),
+        created_at: row.get("created_at"),
+    }
+}
+
+pub(super) fn set_from_row(row: &SqliteRow) -> Set {
+    Set {
+        id: row.get("id"),
+        list_id: row.get("list_id"),
+        parent_id: row.get("parent_id"),
+        sid: row.get("sid"),
+        title: row.get("title"),
+        position: row.get("position"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+pub(super) fn share_link_from_row(row: &SqliteRow) -> ShareLink {
+    ShareLink {
+        id: row.get("id"),
+        list_id: row.get("list_id"),
+        token: None,
+        created_at: row.get("created_at"),
+        expires_at: row.get("expires_at"),
+    }
+}
+
+pub(super) fn tag_from_row(row: &SqliteRow) -> Tag {
+    Tag {
+        id: row.get("id"),
+        name: row.get("name"),
+    }
+}
+
+/// Reads a to do without its tags and blockers, which live in their own tables. See
+/// [`super::links::with_links`].
+pub(super) fn todo_from_row(row: &SqliteRow) -> ToDo {
+    ToDo {
+        id: row.get("id"),
+        list_id: row.get("list_id"),
+        set_id: row.get("set_id"),
+        tdid: row.get("tdid"),
+        title: row.get("title"),
+        complete: row.get("complete"),
+        due_date: row.get("due_date"),
+        uuid: row
+            .get::<Option<String>, _>("uuid")
+            .and_then(|uuid| Uuid::parse_str(&uuid).ok()),
+        position: row.get("position"),
+        tags: BTreeSet::new(),
+        blocked_by: BTreeSet::new(),
+        notes: row.get("notes"),
+        priority: Priority::from_level(row.get("priority")),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        completed_at: row.get("completed_at"),
+        auto_complete: row.get("auto_complete"),
+        checklist: None,
+    }
+}
+
+pub(super) fn user_from_row(row: &SqliteRow) -> User {
+    User {
+        id: row.get("id"),
+        username: row.get("username"),
+        created_at: row.get("created_at"),
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::{BTreeSet, HashSet};
+
+use actix_web::web::Data;
+use chrono::Utc;
+use sqlx::{
+    Error::{self as SQLXError, InvalidArgument},
+    Pool, Row, Sqlite,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::{
+        CreateShareLinksRequest, CreateShareLinksResponse, DeleteShareLinksRequest,
+        DeleteShareLinksResponse, ReadShareLinksRequest, ReadShareLinksResponse,
+    },
+    types::{ShareLink, SharedListDocument, ToDo, ToDoReadOptions, UserID},
+};
+
+use super::{
+    access::{check_owned_lists, owned_lists},
+    accounts::token_hash,
+    links::with_links,
+    read_options::todo_order,
+    rows::{list_from_row, set_from_row, share_link_from_row, todo_from_row},
+};
+
+/// Share links whose `expires_at` hasn't passed, as a condition on ShareLinks.
+const UNEXPIRED: &str = "(expires_at IS NULL OR julianday(expires_at) > julianday('now'))";
+
+/// Creates share links for lists `user_id` owns. Tokens are random rather than signed: they're
+/// just as hard to guess, and revoking one only takes deleting its row. Only their hash is stored,
+/// so this is the one response that carries the token.
+pub async fn insert_share_links(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    entries: CreateShareLinksRequest,
+) -> Result<CreateShareLinksResponse, SQLXError> {
+    if entries.is_empty() {
+        return Err(InvalidArgument(
+            "Caller Provided no entries to the database".to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+    if let Some(entry) = entries
+        .iter()
+        .find(|entry| entry.expires_at.is_some_and(|expires_at| expires_at <= now))
+    {
+        return Err(InvalidArgument(format!(
+            "A share link for list {} can't expire in the past",
+            entry.list_id.public()
+        )));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    check_owned_lists(
+        &mut transaction,
+        user_id,
+        entries.iter().map(|entry| entry.list_id),
+    )
+    .await?;
+
+    let mut output = HashSet::new();
+    for entry in entries {
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let row = sqlx::query(
+            "INSERT INTO ShareLinks (list_id, token_hash, expires_at) VALUES (?, ?, ?) RETURNING *;",
+        )
+        .bind(entry.list_id)
+        .bind(token_hash(&token))
+        .bind(entry.expires_at)
+        .fetch_one(&mut *transaction)
+        .await?;
+        output.insert(ShareLink {
+            token: Some(token),
+            ..share_link_from_row(&row)
+        });
+    }
+
+    transaction.commit().await?;
+    Ok(output)
+}
+
+/// The share links of every list `user_id` owns, expired ones included.
+pub async fn query_all_share_links(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+) -> Result<ReadShareLinksResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "SELECT * FROM ShareLinks WHERE list_id IN ({});",
+        owned_lists(user_id)
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut links = BTreeSet::new();
+    for row in query_result {
+        links.insert(share_link_from_row(&row));
+    }
+
+    Ok(links)
+}
+
+/// The share links of the given lists `user_id` owns.
+pub async fn query_share_links(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: ReadShareLinksRequest,
+) -> Result<ReadShareLinksResponse, SQLXError> {
+    let mut db_conn = db_conn_pool.acquire().await?;
+
+    let query = format!(
+        "SELECT * FROM ShareLinks WHERE list_id IN ({}) AND list_id IN ({});",
+        adds.into_iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        owned_lists(user_id)
+    );
+    let query_result = sqlx::query(query.as_str()).fetch_all(&mut *db_conn).await?;
+
+    let mut links = BTreeSet::new();
+    for row in query_result {
+        links.insert(share_link_from_row(&row));
+    }
+
+    Ok(links)
+}
+
+/// Revokes share links of lists `user_id` owns. Expired links are cleaned up along the way.
+pub async fn delete_share_links(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    adds: DeleteShareLinksRequest,
+) -> Result<DeleteShareLinksResponse, SQLXError> {
+    if adds.is_empty() {
+        return Err(InvalidArgument(
+            "Caller Provided no entries to the database".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+
+    let query = format!(
+        "DELETE FROM ShareLinks WHERE id IN ({}) AND list_id IN ({}) RETURNING id;",
+        adds.into_iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        owned_lists(user_id)
+    );
+    let query_result = sqlx::query(query.as_str())
+        .fetch_all(&mut *transaction)
+        .await?;
+
+    let mut deleted_ids = HashSet::new();
+    for row in query_result {
+        deleted_ids.insert(row.get("id"));
+    }
+
+    sqlx::query(&format!("DELETE FROM ShareLinks WHERE NOT {};", UNEXPIRED))
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+    Ok(deleted_ids)
+}
+
+/// The list a share link points to, with its sets and to dos. Returns `None` when the token is
+/// unknown, revoked or expired.
+pub async fn query_shared_document(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    token: String,
+) -> Result<Option<SharedListDocument>, SQLXError> {
+    // A transaction keeps the reads on the same snapshot of the database.
+    let mut transaction = db_conn_pool.begin().await?;
+
+    let Some(row) = sqlx::query(&format!(
+        "SELECT * FROM Lists WHERE id = (SELECT list_id FROM ShareLinks WHERE token_hash = ? AND {});",
+        UNEXPIRED
+    ))
+    .bind(token_hash(&token))
+    .fetch_optional(&mut *transaction)
+    .await?
+    else {
+        return Ok(None);
+    };
+    let list = list_from_row(&row);
+
+    let mut sets = Vec::new();
+    for row in sqlx::query("SELECT * FROM Sets WHERE list_id = ? ORDER BY position, id;")
+        .bind(list.id)
+        .fetch_all(&mut *transaction)
+        .await?
+    {
+        sets.push(set_from_row(&row));
+    }
+
+    let query = format!(
+        "SELECT t.* FROM Todos t LEFT JOIN Sets s ON s.id = t.set_id WHERE t.list_id = ? ORDER BY {};",
+        todo_order(&ToDoReadOptions::default())
+    );
+    let mut todos = Vec::new();
+    for row in sqlx::query(query.as_str())
+        .bind(list.id)
+        .fetch_all(&mut *transaction)
+        .await?
+    {
+        todos.push(todo_from_row(&row));
+    }
+    let todos: Vec<ToDo> = with_links(&mut transaction, todos).await?;
+
+    transaction.commit().await?;
+    Ok(Some(SharedListDocument { list, sets, todos }))
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::types::{CreateShareLink, ListID};
+
+    use super::super::test_db::setup_test_db;
+
+    #[actix_web::test]
+    async fn share_links_stop_working_once_expired_or_revoked() {
+        let db = setup_test_db().await;
+        let link_to = |list_id| {
+            vec![CreateShareLink {
+                list_id,
+                expires_at: Some(Utc::now() + Duration::days(1)),
+            }]
+        };
+
+        let not_owner = insert_share_links(db.clone(), UserID(2), link_to(ListID(1))).await;
+        assert!(not_owner.is_err());
+
+        let link = insert_share_links(db.clone(), UserID(1), link_to(ListID(1)))
+            .await
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let document = query_shared_document(db.clone(), link.token.clone().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.list.id, ListID(1));
+        assert_eq!(document.sets.len(), 2);
+        assert_eq!(document.todos.len(), 4);
+
+        // The token isn't kept, so it can't be read back.
+        let listed = query_all_share_links(db.clone(), UserID(1)).await.unwrap();
+        assert_eq!(
+            listed.iter().map(|link| &link.token).collect::<Vec<_>>(),
+            [&None]
+        );
+
+        sqlx::query("UPDATE ShareLinks SET expires_at = '2000-01-01T00:00:00Z';")
+            .execute(&**db)
+            .await
+            .unwrap();
+        let expired = query_shared_document(db.clone(), link.token.clone().unwrap())
+            .await
+            .unwrap();
+        assert!(expired.is_none());
+
+        let link = insert_share_links(db.clone(), UserID(1), link_to(ListID(1)))
+            .await
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let revoked = delete_share_links(db.clone(), UserID(1), HashSet::from([link.id]))
+            .await
+            .unwrap();
+        assert_eq!(revoked, HashSet::from([link.id]));
+        assert!(
+            query_shared_document(db, link.token.unwrap())
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
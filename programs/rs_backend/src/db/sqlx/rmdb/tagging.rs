@@ -0,0 +1,58 @@
+use std::collections::BTreeSet;
+
+use sqlx::{Error as SQLXError, SqliteConnection};
+
+use crate::types::{TagID, ToDoID};
+
+/// Tags every one of `todo_ids` with every one of `tag_ids`. Tags a to do already carries are
+/// left alone.
+pub(super) async fn attach_tags(
+    db_conn: &mut SqliteConnection,
+    todo_ids: &[ToDoID],
+    tag_ids: &BTreeSet<TagID>,
+) -> Result<(), SQLXError> {
+    let links = todo_ids
+        .iter()
+        .flat_map(|todo_id| {
+            tag_ids
+                .iter()
+                .map(move |tag_id| format!("({}, {})", todo_id.key(), tag_id.key()))
+        })
+        .collect::<Vec<String>>();
+    if links.is_empty() {
+        return Ok(());
+    }
+
+    let query = format!(
+        "INSERT OR IGNORE INTO TodoTags (todo_id, tag_id) VALUES {};",
+        links.join(", ")
+    );
+    sqlx::query(query.as_str()).execute(&mut *db_conn).await?;
+    Ok(())
+}
+
+pub(super) async fn detach_tags(
+    db_conn: &mut SqliteConnection,
+    todo_ids: &[ToDoID],
+    tag_ids: &BTreeSet<TagID>,
+) -> Result<(), SQLXError> {
+    if todo_ids.is_empty() || tag_ids.is_empty() {
+        return Ok(());
+    }
+
+    let query = format!(
+        "DELETE FROM TodoTags WHERE todo_id IN ({}) AND tag_id IN ({});",
+        todo_ids
+            .iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        tag_ids
+            .iter()
+            .map(|id| id.key().to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    sqlx::query(query.as_str()).execute(&mut *db_conn).await?;
+    Ok(())
+}
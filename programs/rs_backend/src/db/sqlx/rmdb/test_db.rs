@@ -0,0 +1,58 @@
+use std::collections::BTreeSet;
+
+use actix_web::web::Data;
+use sqlx::{Pool, Sqlite, sqlite::SqlitePoolOptions};
+
+use crate::types::{ToDoQueryTarget, UpdateToDo};
+
+use super::migrate;
+
+/// An in-memory database with the migrated schema and a small seed:
+///
+/// - user 1 "alice", who owns everything below, and user 2 "bob", who owns nothing
+/// - list 1 "Chores" with set 1 "Clean Bath Room" and set 3 "Clean Tub" nested inside it
+/// - list 2 "Projects" with set 2 "Garden"
+/// - to dos 1 and 2 in set 1, to do 3 loose in list 1 and to do 4 in set 3
+///
+/// A single connection keeps every query on the same database.
+pub(crate) async fn setup_test_db() -> Data<Pool<Sqlite>> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(":memory:")
+        .await
+        .expect("Failed to create test database");
+    migrate(&pool).await.expect("Failed to create tables");
+    sqlx::raw_sql(
+        "INSERT INTO Users (username, password_hash) VALUES ('alice', ''), ('bob', '');
+         INSERT INTO Lists (owner_id, title) VALUES (1, 'Chores'), (1, 'Projects');
+         INSERT INTO Sets (list_id, title) VALUES (1, 'Clean Bath Room'), (2, 'Garden');
+         INSERT INTO Sets (list_id, parent_id, title) VALUES (1, 1, 'Clean Tub');
+         INSERT INTO Todos (list_id, set_id, title) VALUES
+            (1, 1, 'Scrub Toilet Bowl'), (1, 1, 'Mop Floor'), (1, NULL, 'Sweep Floor'),
+            (1, 3, 'Scrub Tub');",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to seed tables");
+    Data::new(pool)
+}
+
+/// An update of the to dos at `target` that changes nothing, to fill in with struct update
+/// syntax.
+pub(crate) fn update_todo(target: ToDoQueryTarget) -> UpdateToDo {
+    UpdateToDo {
+        target,
+        set_id: None,
+        list_id: None,
+        title: None,
+        complete: None,
+        due_date: None,
+        notes: None,
+        priority: None,
+        auto_complete: None,
+        attach_tags: BTreeSet::new(),
+        detach_tags: BTreeSet::new(),
+        add_blockers: BTreeSet::new(),
+        remove_blockers: BTreeSet::new(),
+    }
+}
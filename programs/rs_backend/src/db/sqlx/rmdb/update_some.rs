@@ -5,14 +5,33 @@ use sqlx::{Error as SQLXError, Pool, Row, Sqlite};
 
 use crate::{
     api::{
-        UpdateListsRequest, UpdateListsResponse, UpdateSetsRequest, UpdateSetsResponse,
-        UpdateToDoResponse, UpdateToDosRequest,
+        UpdateChecklistItemsRequest, UpdateChecklistItemsResponse, UpdateListsRequest,
+        UpdateListsResponse, UpdateSetsRequest, UpdateSetsResponse, UpdateTagsRequest,
+        UpdateTagsResponse, UpdateToDoResponse, UpdateToDosRequest,
     },
-    types::{List, Set, SetQueryTarget, ToDo, ToDoQueryTarget},
+    types::{SetID, SetQueryTarget, ToDoID, ToDoQueryTarget, UserID},
+};
+
+use super::{
+    access::{check_lists, check_tags, check_todos, writable_lists, writable_todos},
+    addresses::{
+        pin_rows, reread_sets, reread_todos, set_address_ids, todo_address_ids, unnumber_sets,
+        unnumber_todos,
+    },
+    checklists::auto_complete,
+    dependencies::{add_blockers, remove_blockers},
+    insert_some::escape_text,
+    links::with_links,
+    nesting::{check_parent, subtree_ids},
+    rows::{
+        NOW, checklist_item_from_row, list_from_row, set_from_row, tag_from_row, todo_from_row,
+    },
+    tagging::{attach_tags, detach_tags},
 };
 
 pub async fn update_lists(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     mods: UpdateListsRequest,
 ) -> Result<UpdateListsResponse, SQLXError> {
     if mods.is_empty() {
@@ -25,11 +44,16 @@ pub async fn update_lists(
     let mut output = BTreeSet::new();
 
     for update in mods {
-        let updates;
-        let address;
-
-        updates = format!("title = '{}'", update.title);
-        address = format!("id = {}", update.list_id);
+        let updates = format!(
+            "title = '{}', updated_at = {}",
+            escape_text(&update.title),
+            NOW
+        );
+        let address = format!(
+            "id = {} AND owner_id = {}",
+            update.list_id.key(),
+            user_id.key()
+        );
         let query = format!(
             "UPDATE Lists SET {} WHERE {} RETURNING * ;",
             updates, address
@@ -40,10 +64,7 @@ pub async fn update_lists(
             .await?;
 
         for row in query_result {
-            output.replace(List {
-                id: row.get("id"),
-                title: row.get("title"),
-            });
+            output.replace(list_from_row(&row));
         }
     }
 
@@ -53,6 +74,7 @@ pub async fn update_lists(
 
 pub async fn update_sets(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     mods: UpdateSetsRequest,
 ) -> Result<UpdateSetsResponse, SQLXError> {
     if mods.is_empty() {
@@ -66,25 +88,48 @@ pub async fn update_sets(
     let mut output = BTreeSet::new();
 
     for update in mods {
+        check_lists(&mut transaction, user_id, update.list_id).await?;
+
         let mut updates = String::new();
         match update.list_id {
-            Some(id) => updates.push_str(format!("{}, ", id).as_str()),
+            Some(id) => updates.push_str(format!("{}, ", id.key()).as_str()),
             None => updates.push_str("list_id, "),
         }
+        match update.parent_id {
+            Some(Some(id)) => updates.push_str(format!("{}, ", id.key()).as_str()),
+            Some(None) => updates.push_str("NULL, "),
+            None => updates.push_str("parent_id, "),
+        }
         match update.title {
-            Some(title) => updates.push_str(format!("'{}', ", title).as_str()),
+            Some(title) => updates.push_str(format!("'{}', ", escape_text(&title)).as_str()),
             None => updates.push_str("title, "),
         }
-        updates = updates.trim_end_matches(", ").to_string();
+        updates.push_str(NOW);
 
         let mut target = String::new();
         match update.target {
-            SetQueryTarget::List(id) => target.push_str(format!("list_id = {}", id).as_str()),
-            SetQueryTarget::Set(id) => target.push_str(format!("id = {}", id).as_str()),
+            SetQueryTarget::List(id) => target.push_str(format!("list_id = {}", id.key()).as_str()),
+            SetQueryTarget::Set(id) => target.push_str(format!("id = {}", id.key()).as_str()),
+            SetQueryTarget::Subtree(id) => {
+                target.push_str(format!("id IN ({})", subtree_ids(&id.key().to_string())).as_str())
+            }
+            SetQueryTarget::Address(address) => {
+                target.push_str(format!("id IN ({})", set_address_ids(&address)).as_str())
+            }
+        }
+        target.push_str(format!(" AND list_id IN ({})", writable_lists(user_id)).as_str());
+
+        if let Some(list_id) = update.list_id {
+            target = pin_rows(&mut transaction, "Sets", &target).await?;
+            unnumber_sets(&mut transaction, &target, list_id).await?;
+        }
+
+        if let Some(Some(parent_id)) = update.parent_id {
+            check_parent(&mut transaction, &target, parent_id).await?;
         }
 
         let query = format!(
-            "UPDATE Sets SET (list_id, title) = ({}) WHERE {} RETURNING * ;",
+            "UPDATE Sets SET (list_id, parent_id, title, updated_at) = ({}) WHERE {} RETURNING * ;",
             updates, target
         );
 
@@ -92,12 +137,18 @@ pub async fn update_sets(
             .fetch_all(&mut *transaction)
             .await?;
 
-        for row in query_result {
-            output.replace(Set {
-                id: row.get("id"),
-                list_id: row.get("list_id"),
-                title: row.get("title"),
-            });
+        // Sets moved to another list were numbered again, which `RETURNING` doesn't show.
+        let sets = if update.list_id.is_some() {
+            let set_ids = query_result
+                .iter()
+                .map(|row| row.get("id"))
+                .collect::<Vec<SetID>>();
+            reread_sets(&mut transaction, &set_ids).await?
+        } else {
+            query_result.iter().map(set_from_row).collect()
+        };
+        for set in sets {
+            output.replace(set);
         }
     }
 
@@ -107,6 +158,7 @@ pub async fn update_sets(
 
 pub async fn update_todos(
     db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
     mods: UpdateToDosRequest,
 ) -> Result<UpdateToDoResponse, SQLXError> {
     if mods.is_empty() {
@@ -118,19 +170,34 @@ pub async fn update_todos(
     let mut transaction = db_conn_pool.begin().await?;
 
     let mut output = BTreeSet::new();
+    let mut armed = BTreeSet::new();
 
     for update in mods {
+        check_lists(&mut transaction, user_id, update.list_id).await?;
+        check_tags(
+            &mut transaction,
+            user_id,
+            update.attach_tags.iter().copied(),
+        )
+        .await?;
+        check_todos(
+            &mut transaction,
+            user_id,
+            update.add_blockers.iter().copied(),
+        )
+        .await?;
+
         let mut updates: String = String::new();
         match update.list_id {
-            Some(id) => updates.push_str(format!("{}, ", id).as_str()),
+            Some(id) => updates.push_str(format!("{}, ", id.key()).as_str()),
             None => updates.push_str("list_id, "),
         }
         match update.set_id {
-            Some(id) => updates.push_str(format!("{}, ", id).as_str()),
+            Some(id) => updates.push_str(format!("{}, ", id.key()).as_str()),
             None => updates.push_str("set_id, "),
         }
         match update.title {
-            Some(t) => updates.push_str(format!("'{}', ", t).as_str()),
+            Some(t) => updates.push_str(format!("'{}', ", escape_text(&t)).as_str()),
             None => updates.push_str("title, "),
         }
         match update.complete {
@@ -141,17 +208,58 @@ pub async fn update_todos(
             Some(d) => updates.push_str(format!("'{}', ", d.to_rfc3339()).as_str()),
             None => updates.push_str("due_date, "),
         }
-        updates = updates.trim_end_matches(", ").to_string();
+        match update.notes {
+            Some(Some(n)) => updates.push_str(format!("'{}', ", escape_text(&n)).as_str()),
+            Some(None) => updates.push_str("NULL, "),
+            None => updates.push_str("notes, "),
+        }
+        match update.priority {
+            Some(p) => updates.push_str(format!("{}, ", p.level()).as_str()),
+            None => updates.push_str("priority, "),
+        }
+        match update.auto_complete {
+            Some(a) => updates.push_str(format!("{}, ", a).as_str()),
+            None => updates.push_str("auto_complete, "),
+        }
+        // Completing a to do that's already complete keeps its completion time.
+        match update.complete {
+            Some(true) => updates.push_str(
+                format!("CASE WHEN complete THEN completed_at ELSE {} END, ", NOW).as_str(),
+            ),
+            Some(false) => updates.push_str("NULL, "),
+            None => updates.push_str("completed_at, "),
+        }
+        updates.push_str(NOW);
 
         let mut target = String::new();
         match update.target {
-            ToDoQueryTarget::List(id) => target.push_str(format!("list_id = {}", id).as_str()),
-            ToDoQueryTarget::Set(id) => target.push_str(format!("set_id = {}", id).as_str()),
-            ToDoQueryTarget::ToDo(id) => target.push_str(format!("id = {}", id).as_str()),
+            ToDoQueryTarget::List(id) => {
+                target.push_str(format!("list_id = {}", id.key()).as_str())
+            }
+            ToDoQueryTarget::Set(id) => target.push_str(format!("set_id = {}", id.key()).as_str()),
+            ToDoQueryTarget::ToDo(id) => target.push_str(format!("id = {}", id.key()).as_str()),
+            ToDoQueryTarget::Subtree(id) => target
+                .push_str(format!("set_id IN ({})", subtree_ids(&id.key().to_string())).as_str()),
+            ToDoQueryTarget::Tag(id) => target.push_str(
+                format!(
+                    "id IN (SELECT todo_id FROM TodoTags WHERE tag_id = {})",
+                    id.key()
+                )
+                .as_str(),
+            ),
+            ToDoQueryTarget::Address(address) => {
+                target.push_str(format!("id IN ({})", todo_address_ids(&address)).as_str())
+            }
+        }
+        target.push_str(format!(" AND list_id IN ({})", writable_lists(user_id)).as_str());
+
+        if let Some(list_id) = update.list_id {
+            target = pin_rows(&mut transaction, "Todos", &target).await?;
+            unnumber_todos(&mut transaction, &target, list_id).await?;
         }
 
         let query = format!(
-            "UPDATE Todos SET (list_id, set_id, title, complete, due_date) = ({}) WHERE {} RETURNING * ;",
+            "UPDATE Todos SET (list_id, set_id, title, complete, due_date, notes, priority, auto_complete, completed_at, updated_at) = ({}) WHERE {} RETURNING * ;",
             updates, target
         );
 
@@ -159,19 +267,397 @@ pub async fn update_todos(
             .fetch_all(&mut *transaction)
             .await?;
 
-        for row in query_result {
-            output.replace(ToDo {
-                id: row.get("id"),
-                list_id: row.get("list_id"),
-                set_id: row.get("set_id"),
-                complete: row.get("complete"),
-                due_date: row.get("due_date"),
-                title: row.get("title"),
-            });
+        let todo_ids = query_result
+            .iter()
+            .map(|row| row.get("id"))
+            .collect::<Vec<ToDoID>>();
+        attach_tags(&mut transaction, &todo_ids, &update.attach_tags).await?;
+        detach_tags(&mut transaction, &todo_ids, &update.detach_tags).await?;
+        add_blockers(&mut transaction, &todo_ids, &update.add_blockers).await?;
+        remove_blockers(&mut transaction, &todo_ids, &update.remove_blockers).await?;
+        if update.auto_complete == Some(true) {
+            armed.extend(todo_ids.iter().copied());
+        }
+
+        let todos = if update.list_id.is_some() {
+            reread_todos(&mut transaction, &todo_ids).await?
+        } else {
+            query_result.iter().map(todo_from_row).collect()
+        };
+        for todo in todos {
+            output.replace(todo);
+        }
+    }
+
+    // Turning auto completion on completes the to dos whose checklist is already done.
+    if !armed.is_empty() {
+        auto_complete(&mut transaction, armed.iter().copied()).await?;
+        let query = format!(
+            "SELECT * FROM Todos WHERE id IN ({});",
+            armed
+                .iter()
+                .map(|id| id.key().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        for row in sqlx::query(query.as_str())
+            .fetch_all(&mut *transaction)
+            .await?
+        {
+            output.replace(todo_from_row(&row));
         }
     }
 
+    let output = with_links(&mut transaction, output).await?;
     transaction.commit().await?;
 
     Ok(output)
 }
+
+pub async fn update_tags(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    mods: UpdateTagsRequest,
+) -> Result<UpdateTagsResponse, SQLXError> {
+    if mods.is_empty() {
+        return Err(SQLXError::InvalidArgument(
+            "Can't have zero modification when running update on Tags Table.".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    let mut output = BTreeSet::new();
+
+    for update in mods {
+        let row =
+            sqlx::query("UPDATE Tags SET name = ? WHERE id = ? AND owner_id = ? RETURNING *;")
+                .bind(&update.name)
+                .bind(update.tag_id)
+                .bind(user_id)
+                .fetch_optional(&mut *transaction)
+                .await?;
+
+        if let Some(row) = row {
+            output.replace(tag_from_row(&row));
+        }
+    }
+
+    transaction.commit().await?;
+    Ok(output)
+}
+
+pub async fn update_checklist_items(
+    db_conn_pool: Data<Pool<Sqlite>>,
+    user_id: UserID,
+    mods: UpdateChecklistItemsRequest,
+) -> Result<UpdateChecklistItemsResponse, SQLXError> {
+    if mods.is_empty() {
+        return Err(SQLXError::InvalidArgument(
+            "Can't have zero modification when running update on ChecklistItems Table.".to_string(),
+        ));
+    }
+
+    let mut transaction = db_conn_pool.begin().await?;
+    let mut output = BTreeSet::new();
+
+    for update in mods {
+        let query = format!(
+            "UPDATE ChecklistItems SET (title, done) = (COALESCE(?, title), COALESCE(?, done)) WHERE id = ? AND todo_id IN ({}) RETURNING *;",
+            writable_todos(user_id)
+        );
+        let row = sqlx::query(query.as_str())
+            .bind(&update.title)
+            .bind(update.done)
+            .bind(update.item_id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        if let Some(row) = row {
+            output.replace(checklist_item_from_row(&row));
+        }
+    }
+
+    auto_complete(&mut transaction, output.iter().map(|item| item.todo_id)).await?;
+    transaction.commit().await?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use sqlx::Row;
+
+    use super::*;
+    use crate::types::{
+        ChecklistItemID, ListID, SetID, TagID, UpdateChecklistItem, UpdateSet, UpdateToDo,
+    };
+
+    use super::super::test_db::{setup_test_db, update_todo};
+
+    #[actix_web::test]
+    async fn moving_a_set_moves_its_subsets_and_todos() {
+        let db = setup_test_db().await;
+
+        update_sets(
+            db.clone(),
+            UserID(1),
+            vec![UpdateSet {
+                target: SetQueryTarget::Set(SetID(1)),
+                list_id: Some(ListID(2)),
+                parent_id: None,
+                title: None,
+            }],
+        )
+        .await
+        .unwrap();
+
+        let list_ids: Vec<i64> = sqlx::query("SELECT list_id FROM Todos ORDER BY id;")
+            .fetch_all(&**db)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get("list_id"))
+            .collect();
+        assert_eq!(list_ids, vec![2, 2, 1, 2]);
+    }
+
+    #[actix_web::test]
+    async fn sets_cant_be_nested_inside_themselves() {
+        let db = setup_test_db().await;
+        let nest = |target, parent_id| UpdateSet {
+            target,
+            list_id: None,
+            parent_id: Some(parent_id),
+            title: None,
+        };
+
+        let into_itself = update_sets(
+            db.clone(),
+            UserID(1),
+            vec![nest(SetQueryTarget::Set(SetID(1)), Some(SetID(1)))],
+        )
+        .await;
+        assert!(matches!(into_itself, Err(SQLXError::InvalidArgument(_))));
+        let into_child = update_sets(
+            db.clone(),
+            UserID(1),
+            vec![nest(SetQueryTarget::Set(SetID(1)), Some(SetID(3)))],
+        )
+        .await;
+        assert!(matches!(into_child, Err(SQLXError::InvalidArgument(_))));
+
+        let other_list = update_sets(
+            db.clone(),
+            UserID(1),
+            vec![nest(SetQueryTarget::Set(SetID(3)), Some(SetID(2)))],
+        )
+        .await;
+        assert!(matches!(other_list, Err(SQLXError::Database(e)) if e.is_foreign_key_violation()));
+
+        let unnested = update_sets(
+            db.clone(),
+            UserID(1),
+            vec![nest(SetQueryTarget::Set(SetID(3)), None)],
+        )
+        .await;
+        assert_eq!(unnested.unwrap().first().unwrap().parent_id, None);
+    }
+
+    #[actix_web::test]
+    async fn subtree_targets_reach_nested_sets() {
+        let db = setup_test_db().await;
+
+        let completed = update_todos(
+            db.clone(),
+            UserID(1),
+            vec![UpdateToDo {
+                complete: Some(true),
+                ..update_todo(ToDoQueryTarget::Subtree(SetID(1)))
+            }],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            completed.iter().map(|todo| todo.id).collect::<Vec<_>>(),
+            vec![ToDoID(1), ToDoID(2), ToDoID(4)]
+        );
+    }
+
+    #[actix_web::test]
+    async fn todo_set_must_belong_to_its_list() {
+        let db = setup_test_db().await;
+        let update = |list_id, set_id| UpdateToDo {
+            set_id,
+            list_id,
+            ..update_todo(ToDoQueryTarget::ToDo(ToDoID(3)))
+        };
+
+        let foreign_set =
+            update_todos(db.clone(), UserID(1), vec![update(None, Some(SetID(2)))]).await;
+        assert!(matches!(foreign_set, Err(SQLXError::Database(e)) if e.is_foreign_key_violation()));
+
+        let set_left_behind =
+            update_todos(db.clone(), UserID(1), vec![update(Some(ListID(2)), None)]).await;
+        assert!(set_left_behind.is_ok());
+        let moved = update_todos(
+            db.clone(),
+            UserID(1),
+            vec![update(Some(ListID(1)), Some(SetID(1)))],
+        )
+        .await;
+        assert!(moved.is_ok());
+
+        let stuck = update_todos(
+            db.clone(),
+            UserID(1),
+            vec![UpdateToDo {
+                target: ToDoQueryTarget::ToDo(ToDoID(1)),
+                ..update(Some(ListID(2)), None)
+            }],
+        )
+        .await;
+        assert!(matches!(stuck, Err(SQLXError::Database(e)) if e.is_foreign_key_violation()));
+    }
+
+    #[actix_web::test]
+    async fn tags_are_attached_detached_and_targeted() {
+        let db = setup_test_db().await;
+        sqlx::query("INSERT INTO Tags (owner_id, name) VALUES (1, 'errand');")
+            .execute(&**db)
+            .await
+            .unwrap();
+        let update = |target, attach_tags, detach_tags| UpdateToDo {
+            attach_tags,
+            detach_tags,
+            ..update_todo(target)
+        };
+        let errand = BTreeSet::from([TagID(1)]);
+
+        let tagged = update_todos(
+            db.clone(),
+            UserID(1),
+            vec![
+                update(
+                    ToDoQueryTarget::ToDo(ToDoID(1)),
+                    errand.clone(),
+                    BTreeSet::new(),
+                ),
+                update(
+                    ToDoQueryTarget::ToDo(ToDoID(3)),
+                    errand.clone(),
+                    BTreeSet::new(),
+                ),
+            ],
+        )
+        .await
+        .unwrap();
+        assert!(tagged.iter().all(|todo| todo.tags == errand));
+
+        let untagged = update_todos(
+            db.clone(),
+            UserID(1),
+            vec![update(
+                ToDoQueryTarget::Tag(TagID(1)),
+                BTreeSet::new(),
+                errand.clone(),
+            )],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            untagged.iter().map(|todo| todo.id).collect::<Vec<_>>(),
+            vec![ToDoID(1), ToDoID(3)]
+        );
+        assert!(untagged.iter().all(|todo| todo.tags.is_empty()));
+
+        let unknown_tag = update_todos(
+            db.clone(),
+            UserID(1),
+            vec![update(
+                ToDoQueryTarget::ToDo(ToDoID(1)),
+                BTreeSet::from([TagID(2)]),
+                BTreeSet::new(),
+            )],
+        )
+        .await;
+        assert!(matches!(unknown_tag, Err(SQLXError::InvalidArgument(_))));
+    }
+
+    #[actix_web::test]
+    async fn completion_time_follows_complete() {
+        let db = setup_test_db().await;
+        let complete = |complete| {
+            vec![UpdateToDo {
+                complete: Some(complete),
+                ..update_todo(ToDoQueryTarget::ToDo(ToDoID(1)))
+            }]
+        };
+        let completed_at = |todos: UpdateToDoResponse| todos.first().unwrap().completed_at;
+
+        let first = completed_at(
+            update_todos(db.clone(), UserID(1), complete(true))
+                .await
+                .unwrap(),
+        );
+        assert!(first.is_some());
+
+        let again = completed_at(
+            update_todos(db.clone(), UserID(1), complete(true))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(again, first);
+
+        let reopened = completed_at(
+            update_todos(db.clone(), UserID(1), complete(false))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(reopened, None);
+    }
+
+    #[actix_web::test]
+    async fn checklists_auto_complete_their_todo() {
+        let db = setup_test_db().await;
+        sqlx::raw_sql(
+            "UPDATE Todos SET auto_complete = 1 WHERE id = 1;
+             INSERT INTO ChecklistItems (todo_id, title, position) VALUES
+                (1, 'Bleach', 1), (1, 'Brush', 2), (2, 'Bucket', 1);",
+        )
+        .execute(&**db)
+        .await
+        .unwrap();
+        let tick = |item_id| UpdateChecklistItem {
+            item_id,
+            title: None,
+            done: Some(true),
+        };
+        let complete = |todo_id: ToDoID| {
+            let db = db.clone();
+            async move {
+                sqlx::query("SELECT complete FROM Todos WHERE id = ?;")
+                    .bind(todo_id)
+                    .fetch_one(&**db)
+                    .await
+                    .unwrap()
+                    .get::<bool, _>("complete")
+            }
+        };
+
+        update_checklist_items(db.clone(), UserID(1), vec![tick(ChecklistItemID(1))])
+            .await
+            .unwrap();
+        assert!(!complete(ToDoID(1)).await);
+
+        update_checklist_items(
+            db.clone(),
+            UserID(1),
+            vec![tick(ChecklistItemID(2)), tick(ChecklistItemID(3))],
+        )
+        .await
+        .unwrap();
+        assert!(complete(ToDoID(1)).await);
+        // To do 2 didn't ask to be auto completed.
+        assert!(!complete(ToDoID(2)).await);
+    }
+}
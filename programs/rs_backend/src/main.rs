@@ -1,5 +1,7 @@
-use actix_web::{App, HttpServer, web::Data};
+use actix_web::{App, HttpServer, middleware::from_fn, web::Data};
 use sqlx::SqlitePool;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 mod api;
 mod db;
@@ -14,21 +16,88 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
+    if let Err(e) = db::sqlx::migrate(&pool).await {
+        panic!("Failed to migrate the database: {}", e);
+    }
+
+    match db::sqlx::load_public_id_key(&pool).await {
+        Ok(key) => types::init_public_ids(key),
+        Err(e) => panic!("Failed to load the public id key: {}", e),
+    }
+
+    let rate_limit_config = match api::RateLimitConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => panic!("Failed to read the rate limits: {}", e),
+    };
+    // Budgets are shared by every worker, so the limiter is made once, outside the factory.
+    let rate_limiter = Data::new(api::RateLimiter::new(rate_limit_config));
+    let schema = Data::new(api::new_schema());
+    let openapi = api::ApiDoc::openapi();
+
     HttpServer::new(move || {
-        App::new()
+        let app = App::new()
             .app_data(Data::new(pool.clone()))
+            .app_data(rate_limiter.clone())
+            .app_data(schema.clone())
+            // The last middleware wrapped runs first, so requests are authenticated before
+            // they're rate limited by user. Failed authentications have a budget of their own,
+            // which authentication checks first.
+            .wrap(from_fn(api::rate_limit))
+            .wrap(from_fn(api::authenticate))
+            .service(api::create_user)
+            .service(api::create_session)
+            .service(api::delete_current_session)
+            .service(api::read_current_user)
             .service(api::create_lists)
             .service(api::create_sets)
             .service(api::create_to_dos)
+            .service(api::create_tags)
+            .service(api::create_checklist_items)
             .service(api::read_lists)
             .service(api::read_sets)
             .service(api::read_to_dos)
+            .service(api::read_tags)
+            .service(api::read_checklist_items)
             .service(api::update_lists)
             .service(api::update_sets)
             .service(api::update_to_dos)
+            .service(api::update_tags)
+            .service(api::update_checklist_items)
+            .service(api::move_sets)
+            .service(api::move_to_dos)
+            .service(api::move_checklist_items)
             .service(api::delete_lists)
             .service(api::delete_sets)
             .service(api::delete_to_dos)
+            .service(api::delete_tags)
+            .service(api::delete_checklist_items)
+            .service(api::create_memberships)
+            .service(api::read_memberships)
+            .service(api::update_memberships)
+            .service(api::delete_memberships)
+            .service(api::read_shared_lists)
+            .service(api::create_share_links)
+            .service(api::read_share_links)
+            .service(api::delete_share_links)
+            .service(api::read_shared_list)
+            .service(api::read_stats)
+            .service(api::export_database)
+            .service(api::import_database)
+            .service(api::export_to_dos_csv)
+            .service(api::import_to_dos_csv)
+            .service(api::export_markdown)
+            .service(api::import_markdown_lists)
+            .service(api::export_taskwarrior)
+            .service(api::import_taskwarrior)
+            .service(api::graphql)
+            .service(api::json_rpc)
+            .service(SwaggerUi::new("/api/docs/{_:.*}").url("/api/openapi.json", openapi.clone()));
+
+        if cfg!(debug_assertions) {
+            app.service(api::graphql_playground)
+        } else {
+            app
+        }
     })
     .bind(("127.0.0.1", 8080))?
     .run()
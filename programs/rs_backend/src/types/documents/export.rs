@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use len_trait::{Empty, Len};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::types::{List, ListID, Set, SetID, Tag, TagID, ToDo, ToDoID};
+
+/// Version of the export document this server writes and accepts.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// A snapshot of every list, set, to do and tag in the database. To dos carry their checklists.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct ExportDocument {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub lists: Vec<List>,
+    pub sets: Vec<Set>,
+    pub todos: Vec<ToDo>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+}
+
+impl Empty for ExportDocument {
+    fn is_empty(&self) -> bool {
+        self.lists.is_empty()
+            && self.sets.is_empty()
+            && self.todos.is_empty()
+            && self.tags.is_empty()
+    }
+}
+
+impl Len for ExportDocument {
+    fn len(&self) -> usize {
+        self.lists.len() + self.sets.len() + self.todos.len() + self.tags.len()
+    }
+}
+
+/// How an import treats the data already in the database.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Keep existing data and add the imported entities next to it.
+    #[default]
+    #[serde(rename = "merge")]
+    Merge,
+    /// Delete all existing data before adding the imported entities.
+    #[serde(rename = "replace")]
+    Replace,
+}
+
+#[derive(Serialize, Deserialize, IntoParams, Debug, Default)]
+#[into_params(parameter_in = Query)]
+pub struct ImportOptions {
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+/// Maps the ids found in the imported document to the ids they were stored under.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default)]
+pub struct ImportSummary {
+    pub lists: HashMap<ListID, ListID>,
+    pub sets: HashMap<SetID, SetID>,
+    pub todos: HashMap<ToDoID, ToDoID>,
+    #[serde(default)]
+    pub tags: HashMap<TagID, TagID>,
+}
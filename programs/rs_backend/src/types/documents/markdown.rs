@@ -0,0 +1,378 @@
+use std::{
+    collections::HashSet,
+    fmt::{Display, Write},
+};
+
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::types::{List, Set, ToDo};
+
+/// Lists, sets and to dos written as a Markdown checklist:
+///
+/// ```markdown
+/// # Chores
+///
+/// - [ ] Sweep Floor (due: 2025-01-01)
+///
+/// ## Clean Bath Room
+///
+/// - [x] Scrub Toilet Bowl (due: today)
+///
+/// ### Clean Bath Tub
+///
+/// - [ ] Scrub Tiles
+/// ```
+///
+/// `#` headings open a list, `##` headings open a set within it, and every further `#` opens a
+/// set nested in the set above it. Checklist items belong to the closest heading above them. Any
+/// other line is treated as a note and skipped.
+#[derive(Debug, Default, PartialEq)]
+pub struct MarkdownDocument {
+    pub lists: Vec<MarkdownList>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct MarkdownList {
+    pub title: String,
+    pub todos: Vec<MarkdownToDo>,
+    pub sets: Vec<MarkdownSet>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct MarkdownSet {
+    pub title: String,
+    pub todos: Vec<MarkdownToDo>,
+    pub sets: Vec<MarkdownSet>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MarkdownToDo {
+    pub title: String,
+    pub complete: bool,
+    pub due_date: Option<DateTime<Utc>>,
+}
+
+/// Everything created by a Markdown import.
+#[derive(Serialize, ToSchema, Debug, Default)]
+pub struct MarkdownImportSummary {
+    pub lists: HashSet<List>,
+    pub sets: HashSet<Set>,
+    pub todos: HashSet<ToDo>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MarkdownError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for MarkdownError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl MarkdownDocument {
+    /// Parses a checklist. Relative due hints (`today`, `tomorrow`, `yesterday`) are resolved
+    /// against `today`.
+    pub fn parse(text: &str, today: NaiveDate) -> Result<Self, MarkdownError> {
+        let mut document = MarkdownDocument::default();
+        // How deep the set of the last set heading is nested, 0 for a `##` set and `None` right
+        // after a list heading.
+        let mut depth: Option<usize> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: &str| MarkdownError {
+                line: line_number,
+                message: message.to_string(),
+            };
+            let line = line.trim();
+
+            if let Some((1, title)) = heading(line) {
+                document.lists.push(MarkdownList {
+                    title: title.to_string(),
+                    ..Default::default()
+                });
+                depth = None;
+            } else if let Some((level, title)) = heading(line) {
+                let Some(list) = document.lists.last_mut() else {
+                    return Err(error("Set heading appears before any list heading"));
+                };
+                let set_depth = level - 2;
+                let siblings = match set_depth.checked_sub(1) {
+                    None => &mut list.sets,
+                    Some(parent_depth) if depth.is_some_and(|depth| parent_depth <= depth) => {
+                        &mut last_set(&mut list.sets, parent_depth).sets
+                    }
+                    Some(_) => {
+                        return Err(error(
+                            "Set heading is nested deeper than the heading above it",
+                        ));
+                    }
+                };
+                siblings.push(MarkdownSet {
+                    title: title.to_string(),
+                    ..Default::default()
+                });
+                depth = Some(set_depth);
+            } else if let Some(item) = line
+                .strip_prefix("- [")
+                .or_else(|| line.strip_prefix("* ["))
+            {
+                let Some(list) = document.lists.last_mut() else {
+                    return Err(error("Checklist item appears before any list heading"));
+                };
+                let todo = parse_item(item, today).map_err(|message| error(&message))?;
+                match depth {
+                    Some(depth) => last_set(&mut list.sets, depth).todos.push(todo),
+                    None => list.todos.push(todo),
+                }
+            }
+        }
+
+        Ok(document)
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        for list in &self.lists {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "# {}", list.title);
+            render_items(&mut out, &list.todos);
+
+            for set in &list.sets {
+                render_set(&mut out, set, 2);
+            }
+        }
+
+        out
+    }
+}
+
+/// Splits a heading line into its level (the number of `#`s) and its title.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let title = line[level..].strip_prefix(' ')?;
+    (level > 0).then(|| (level, title.trim()))
+}
+
+/// The last set `depth` levels down, following the last set at every level.
+///
+/// Only called with depths of sets that were already opened, so there is always one.
+fn last_set(sets: &mut [MarkdownSet], depth: usize) -> &mut MarkdownSet {
+    let set = sets.last_mut().expect("the set above was opened");
+    match depth {
+        0 => set,
+        _ => last_set(&mut set.sets, depth - 1),
+    }
+}
+
+/// Parses what follows the `- [` of a checklist item.
+fn parse_item(item: &str, today: NaiveDate) -> Result<MarkdownToDo, String> {
+    let (complete, rest) = match item.split_once(']') {
+        Some((" ", rest)) => (false, rest),
+        Some(("x" | "X", rest)) => (true, rest),
+        _ => return Err("Checklist items must start with '- [ ]' or '- [x]'".to_string()),
+    };
+    let rest = rest.trim();
+
+    let (title, due_date) = match rest.rfind("(due:") {
+        Some(start) if rest.ends_with(')') => {
+            let hint = rest[start + "(due:".len()..rest.len() - 1].trim();
+            (rest[..start].trim(), Some(parse_due(hint, today)?))
+        }
+        _ => (rest, None),
+    };
+
+    if title.is_empty() {
+        return Err("Checklist item is missing a title".to_string());
+    }
+
+    Ok(MarkdownToDo {
+        title: title.to_string(),
+        complete,
+        due_date,
+    })
+}
+
+fn parse_due(hint: &str, today: NaiveDate) -> Result<DateTime<Utc>, String> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(hint) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+
+    let date = match hint.to_lowercase().as_str() {
+        "today" => Some(today),
+        "tomorrow" => today.checked_add_days(Days::new(1)),
+        "yesterday" => today.checked_sub_days(Days::new(1)),
+        _ => NaiveDate::parse_from_str(hint, "%Y-%m-%d").ok(),
+    };
+
+    match date {
+        Some(date) => Ok(date.and_time(NaiveTime::MIN).and_utc()),
+        None => Err(format!("Can't read the due date '{}'", hint)),
+    }
+}
+
+fn render_set(out: &mut String, set: &MarkdownSet, level: usize) {
+    let _ = writeln!(out, "\n{} {}", "#".repeat(level), set.title);
+    render_items(out, &set.todos);
+
+    for child in &set.sets {
+        render_set(out, child, level + 1);
+    }
+}
+
+fn render_items(out: &mut String, todos: &[MarkdownToDo]) {
+    if todos.is_empty() {
+        return;
+    }
+
+    out.push('\n');
+    for todo in todos {
+        let check = if todo.complete { "x" } else { " " };
+        let _ = write!(out, "- [{}] {}", check, todo.title);
+        if let Some(due_date) = todo.due_date {
+            if due_date.time() == NaiveTime::MIN {
+                let _ = write!(out, " (due: {})", due_date.format("%Y-%m-%d"));
+            } else {
+                let _ = write!(out, " (due: {})", due_date.to_rfc3339());
+            }
+        }
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 14).unwrap()
+    }
+
+    fn midnight(year: i32, month: u32, day: u32) -> Option<DateTime<Utc>> {
+        Some(
+            NaiveDate::from_ymd_opt(year, month, day)
+                .unwrap()
+                .and_time(NaiveTime::MIN)
+                .and_utc(),
+        )
+    }
+
+    #[test]
+    fn parses_lists_sets_and_items() {
+        let text = "# Chores\n\
+            Some notes about chores.\n\
+            - [ ] Sweep Floor\n\
+            \n\
+            ## Clean Bath Room\n\
+            - [x] Scrub Toilet Bowl (due: today)\n\
+            \u{20}\u{20}* [X] Mop Floor (due: 2025-01-02)\n\
+            ### Clean Bath Tub\n\
+            - [ ] Scrub Tiles\n\
+            # Community\n\
+            - [ ] Go to Church (due: 2025-03-16T10:30:00Z)\n";
+
+        let document = MarkdownDocument::parse(text, today()).unwrap();
+
+        assert_eq!(
+            document,
+            MarkdownDocument {
+                lists: vec![
+                    MarkdownList {
+                        title: "Chores".to_string(),
+                        todos: vec![MarkdownToDo {
+                            title: "Sweep Floor".to_string(),
+                            complete: false,
+                            due_date: None,
+                        }],
+                        sets: vec![MarkdownSet {
+                            title: "Clean Bath Room".to_string(),
+                            todos: vec![
+                                MarkdownToDo {
+                                    title: "Scrub Toilet Bowl".to_string(),
+                                    complete: true,
+                                    due_date: midnight(2025, 3, 14),
+                                },
+                                MarkdownToDo {
+                                    title: "Mop Floor".to_string(),
+                                    complete: true,
+                                    due_date: midnight(2025, 1, 2),
+                                },
+                            ],
+                            sets: vec![MarkdownSet {
+                                title: "Clean Bath Tub".to_string(),
+                                todos: vec![MarkdownToDo {
+                                    title: "Scrub Tiles".to_string(),
+                                    complete: false,
+                                    due_date: None,
+                                }],
+                                sets: vec![],
+                            }],
+                        }],
+                    },
+                    MarkdownList {
+                        title: "Community".to_string(),
+                        todos: vec![MarkdownToDo {
+                            title: "Go to Church".to_string(),
+                            complete: false,
+                            due_date: Some("2025-03-16T10:30:00Z".parse().unwrap()),
+                        }],
+                        sets: vec![],
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_misplaced_and_malformed_lines() {
+        let orphan = MarkdownDocument::parse("- [ ] Sweep Floor\n", today());
+        assert_eq!(orphan.unwrap_err().line, 1);
+
+        let bad_check = MarkdownDocument::parse("# Chores\n- [?] Sweep Floor\n", today());
+        assert_eq!(bad_check.unwrap_err().line, 2);
+
+        let bad_due = MarkdownDocument::parse("# Chores\n\n- [ ] Mop (due: someday)\n", today());
+        assert_eq!(bad_due.unwrap_err().line, 3);
+
+        let skipped_set = MarkdownDocument::parse("# Chores\n## Kitchen\n#### Sink\n", today());
+        assert_eq!(skipped_set.unwrap_err().line, 3);
+
+        let orphan_set = MarkdownDocument::parse("# Chores\n### Sink\n", today());
+        assert_eq!(orphan_set.unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn render_round_trips_through_parse() {
+        let text = "# Chores\n\
+            \n\
+            - [ ] Sweep Floor (due: 2025-01-02)\n\
+            \n\
+            ## Clean Bath Room\n\
+            \n\
+            - [x] Scrub Toilet Bowl (due: 2025-03-16T10:30:00+00:00)\n\
+            \n\
+            ### Clean Bath Tub\n\
+            \n\
+            - [ ] Scrub Tiles\n\
+            \n\
+            #### Clean Drain\n\
+            \n\
+            ### Clean Sink\n\
+            \n\
+            ## Clean Pet\n\
+            \n\
+            # Projects\n";
+
+        let document = MarkdownDocument::parse(text, today()).unwrap();
+
+        assert_eq!(document.render(), text);
+    }
+}
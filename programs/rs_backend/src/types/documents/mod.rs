@@ -0,0 +1,14 @@
+mod export;
+mod markdown;
+pub mod protobuf;
+mod share;
+mod spreadsheet;
+mod stats;
+mod taskwarrior;
+
+pub use export::*;
+pub use markdown::*;
+pub use share::*;
+pub use spreadsheet::*;
+pub use stats::*;
+pub use taskwarrior::*;
@@ -0,0 +1,677 @@
+use std::collections::{BTreeSet, HashSet};
+
+use chrono::{DateTime, Utc};
+use prost_types::Timestamp;
+
+use crate::types::{
+    CreateList, CreateSet, CreateToDo, ListID, SetID, SetQueryTarget, ToDoID, ToDoQueryTarget,
+    UpdateList, UpdateSet, UpdateToDo,
+};
+
+/// The messages of `schema.proto`, generated by the build script. Their `lid`, `sid` and `tdid`
+/// fields hold the same public ids as JSON bodies do, except for the `sid` and `tdid` of
+/// addresses, which are numbers within a list. Fields a message has no room for take the defaults
+/// a JSON body leaving them out would get.
+#[allow(dead_code)] // No route takes the bare id messages.
+mod messages {
+    include!(concat!(env!("OUT_DIR"), "/todoapi.rs"));
+}
+
+pub use messages::*;
+
+fn parse_id<ID>(parse: fn(&str) -> Option<ID>, field: &str, id: &str) -> Result<ID, String> {
+    parse(id).ok_or_else(|| format!("Invalid {}: {:?}", field, id))
+}
+
+/// Reads the number of a set or to do within its list, which addresses hold instead of an id.
+fn parse_number(field: &str, number: &str) -> Result<i64, String> {
+    number
+        .parse()
+        .ok()
+        .filter(|number| *number > 0)
+        .ok_or_else(|| format!("Invalid {}: {:?}", field, number))
+}
+
+fn timestamp(date: DateTime<Utc>) -> Timestamp {
+    Timestamp {
+        seconds: date.timestamp(),
+        nanos: date.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn date_time(timestamp: Timestamp) -> Result<DateTime<Utc>, String> {
+    u32::try_from(timestamp.nanos)
+        .ok()
+        .and_then(|nanos| DateTime::from_timestamp(timestamp.seconds, nanos))
+        .ok_or_else(|| format!("Invalid timestamp: {}", timestamp))
+}
+
+fn collect<M, T: TryFrom<M, Error = String>, C: FromIterator<T>>(
+    messages: Vec<M>,
+) -> Result<C, String> {
+    messages.into_iter().map(T::try_from).collect()
+}
+
+// ENTITIES
+
+impl From<crate::types::List> for List {
+    fn from(list: crate::types::List) -> Self {
+        List {
+            lid: list.id.public(),
+            title: list.title,
+            sets: Vec::new(),
+            todos: Vec::new(),
+        }
+    }
+}
+
+impl TryFrom<List> for crate::types::List {
+    type Error = String;
+
+    fn try_from(list: List) -> Result<Self, String> {
+        Ok(crate::types::List {
+            id: parse_id(ListID::parse, "lid", &list.lid)?,
+            owner_id: Default::default(),
+            title: list.title,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+    }
+}
+
+impl From<crate::types::Set> for Set {
+    fn from(set: crate::types::Set) -> Self {
+        Set {
+            lid: set.list_id.public(),
+            sid: set.id.public(),
+            title: set.title,
+            todos: Vec::new(),
+            number: set.sid,
+        }
+    }
+}
+
+impl TryFrom<Set> for crate::types::Set {
+    type Error = String;
+
+    fn try_from(set: Set) -> Result<Self, String> {
+        Ok(crate::types::Set {
+            id: parse_id(SetID::parse, "sid", &set.sid)?,
+            list_id: parse_id(ListID::parse, "lid", &set.lid)?,
+            parent_id: None,
+            sid: set.number,
+            title: set.title,
+            position: Default::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+    }
+}
+
+impl From<crate::types::ToDo> for ToDo {
+    fn from(todo: crate::types::ToDo) -> Self {
+        ToDo {
+            lid: todo.list_id.public(),
+            sid: todo.set_id.map(SetID::public),
+            tdid: todo.id.public(),
+            title: todo.title,
+            complete: todo.complete,
+            due_date: todo.due_date.map(timestamp),
+            number: todo.tdid,
+        }
+    }
+}
+
+impl TryFrom<ToDo> for crate::types::ToDo {
+    type Error = String;
+
+    fn try_from(todo: ToDo) -> Result<Self, String> {
+        Ok(crate::types::ToDo {
+            id: parse_id(ToDoID::parse, "tdid", &todo.tdid)?,
+            set_id: todo
+                .sid
+                .map(|sid| parse_id(SetID::parse, "sid", &sid))
+                .transpose()?,
+            list_id: parse_id(ListID::parse, "lid", &todo.lid)?,
+            tdid: todo.number,
+            title: todo.title,
+            complete: todo.complete,
+            due_date: todo.due_date.map(date_time).transpose()?,
+            uuid: None,
+            position: Default::default(),
+            tags: BTreeSet::new(),
+            blocked_by: BTreeSet::new(),
+            notes: None,
+            priority: Default::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            completed_at: None,
+            auto_complete: false,
+            checklist: None,
+        })
+    }
+}
+
+// CREATE
+
+impl From<CreateList> for ListCreateData {
+    fn from(list: CreateList) -> Self {
+        ListCreateData { title: list.title }
+    }
+}
+
+impl From<ListCreateData> for CreateList {
+    fn from(list: ListCreateData) -> Self {
+        CreateList { title: list.title }
+    }
+}
+
+impl From<CreateSet> for SetCreateData {
+    fn from(set: CreateSet) -> Self {
+        SetCreateData {
+            title: set.title,
+            lid: set.list_id.public(),
+        }
+    }
+}
+
+impl TryFrom<SetCreateData> for CreateSet {
+    type Error = String;
+
+    fn try_from(set: SetCreateData) -> Result<Self, String> {
+        Ok(CreateSet {
+            list_id: parse_id(ListID::parse, "lid", &set.lid)?,
+            parent_id: None,
+            title: set.title,
+        })
+    }
+}
+
+impl From<CreateToDo> for ToDoCreateData {
+    fn from(todo: CreateToDo) -> Self {
+        ToDoCreateData {
+            title: todo.title,
+            due_date: todo.due_date.map(timestamp),
+            lid: todo.list_id.public(),
+            sid: todo.set_id.map(SetID::public),
+        }
+    }
+}
+
+impl TryFrom<ToDoCreateData> for CreateToDo {
+    type Error = String;
+
+    fn try_from(todo: ToDoCreateData) -> Result<Self, String> {
+        Ok(CreateToDo {
+            list_id: parse_id(ListID::parse, "lid", &todo.lid)?,
+            set_id: todo
+                .sid
+                .map(|sid| parse_id(SetID::parse, "sid", &sid))
+                .transpose()?,
+            title: todo.title,
+            complete: None,
+            due_date: todo.due_date.map(date_time).transpose()?,
+            tags: BTreeSet::new(),
+            blocked_by: BTreeSet::new(),
+            notes: None,
+            priority: None,
+            auto_complete: None,
+        })
+    }
+}
+
+impl TryFrom<CreateListsRequest> for Vec<CreateList> {
+    type Error = String;
+
+    fn try_from(req: CreateListsRequest) -> Result<Self, String> {
+        Ok(req.lists.into_iter().map(CreateList::from).collect())
+    }
+}
+
+impl TryFrom<CreateSetsRequest> for Vec<CreateSet> {
+    type Error = String;
+
+    fn try_from(req: CreateSetsRequest) -> Result<Self, String> {
+        collect(req.sets)
+    }
+}
+
+impl TryFrom<CreateToDosRequest> for Vec<CreateToDo> {
+    type Error = String;
+
+    fn try_from(req: CreateToDosRequest) -> Result<Self, String> {
+        collect(req.todos)
+    }
+}
+
+impl From<HashSet<crate::types::List>> for CreateListsResponse {
+    fn from(_: HashSet<crate::types::List>) -> Self {
+        CreateListsResponse { success: true }
+    }
+}
+
+impl From<HashSet<crate::types::Set>> for CreateSetsResponse {
+    fn from(_: HashSet<crate::types::Set>) -> Self {
+        CreateSetsResponse { success: true }
+    }
+}
+
+impl From<HashSet<crate::types::ToDo>> for CreateToDosResponse {
+    fn from(_: HashSet<crate::types::ToDo>) -> Self {
+        CreateToDosResponse { success: true }
+    }
+}
+
+// READ
+
+/// Addresses name sets by their list and their number within it, like the `address` targets of
+/// JSON bodies.
+impl TryFrom<SetAddress> for SetQueryTarget {
+    type Error = String;
+
+    fn try_from(address: SetAddress) -> Result<Self, String> {
+        let address = match address.address {
+            Some(set_address::Address::WholeList(list)) => {
+                crate::types::SetAddress::WholeList(parse_id(ListID::parse, "lid", &list.lid)?)
+            }
+            Some(set_address::Address::Singular(set)) => crate::types::SetAddress::Singular(
+                parse_id(ListID::parse, "lid", &set.lid)?,
+                parse_number("sid", &set.sid)?,
+            ),
+            None => return Err("Empty set address".to_string()),
+        };
+        Ok(SetQueryTarget::Address(address))
+    }
+}
+
+/// Like sets, to dos are addressed by their list, their set's number if they're in one, and their
+/// own number.
+impl TryFrom<ToDoAddress> for ToDoQueryTarget {
+    type Error = String;
+
+    fn try_from(address: ToDoAddress) -> Result<Self, String> {
+        let address = match address.address {
+            Some(to_do_address::Address::WholeList(list)) => {
+                crate::types::ToDoAddress::WholeList(parse_id(ListID::parse, "lid", &list.lid)?)
+            }
+            Some(to_do_address::Address::WholeSet(set)) => crate::types::ToDoAddress::WholeSet(
+                parse_id(ListID::parse, "lid", &set.lid)?,
+                parse_number("sid", &set.sid)?,
+            ),
+            Some(to_do_address::Address::Singular(todo)) => crate::types::ToDoAddress::Singular(
+                parse_id(ListID::parse, "lid", &todo.lid)?,
+                todo.sid.map(|sid| parse_number("sid", &sid)).transpose()?,
+                parse_number("tdid", &todo.tdid)?,
+            ),
+            None => return Err("Empty to do address".to_string()),
+        };
+        Ok(ToDoQueryTarget::Address(address))
+    }
+}
+
+impl TryFrom<ReadListsRequest> for HashSet<ListID> {
+    type Error = String;
+
+    fn try_from(req: ReadListsRequest) -> Result<Self, String> {
+        req.lids
+            .iter()
+            .map(|lid| parse_id(ListID::parse, "lid", lid))
+            .collect()
+    }
+}
+
+impl TryFrom<ReadSetsRequest> for HashSet<SetQueryTarget> {
+    type Error = String;
+
+    fn try_from(req: ReadSetsRequest) -> Result<Self, String> {
+        collect(req.addresses)
+    }
+}
+
+impl TryFrom<ReadToDosRequest> for HashSet<ToDoQueryTarget> {
+    type Error = String;
+
+    fn try_from(req: ReadToDosRequest) -> Result<Self, String> {
+        collect(req.addresses)
+    }
+}
+
+impl From<BTreeSet<crate::types::List>> for ReadListsResponse {
+    fn from(lists: BTreeSet<crate::types::List>) -> Self {
+        ReadListsResponse {
+            lists: lists.into_iter().map(List::from).collect(),
+        }
+    }
+}
+
+impl From<Vec<crate::types::Set>> for ReadSetsResponse {
+    fn from(sets: Vec<crate::types::Set>) -> Self {
+        ReadSetsResponse {
+            sets: sets.into_iter().map(Set::from).collect(),
+        }
+    }
+}
+
+impl From<Vec<crate::types::ToDo>> for ReadToDosResponse {
+    fn from(todos: Vec<crate::types::ToDo>) -> Self {
+        ReadToDosResponse {
+            todos: todos.into_iter().map(ToDo::from).collect(),
+        }
+    }
+}
+
+// UPDATE
+
+impl TryFrom<ListChangeData> for UpdateList {
+    type Error = String;
+
+    fn try_from(list: ListChangeData) -> Result<Self, String> {
+        Ok(UpdateList {
+            list_id: parse_id(ListID::parse, "lid", &list.lid)?,
+            title: list.title,
+        })
+    }
+}
+
+impl TryFrom<SetChangeData> for UpdateSet {
+    type Error = String;
+
+    fn try_from(set: SetChangeData) -> Result<Self, String> {
+        Ok(UpdateSet {
+            target: SetQueryTarget::Set(parse_id(SetID::parse, "sid", &set.sid)?),
+            list_id: None,
+            parent_id: None,
+            title: Some(set.title),
+        })
+    }
+}
+
+/// The change data says where the to do is, not where it goes, so it's never moved. A missing
+/// `due_date` keeps the current one.
+impl TryFrom<ToDoChangeData> for UpdateToDo {
+    type Error = String;
+
+    fn try_from(todo: ToDoChangeData) -> Result<Self, String> {
+        Ok(UpdateToDo {
+            target: ToDoQueryTarget::ToDo(parse_id(ToDoID::parse, "tdid", &todo.tdid)?),
+            set_id: None,
+            list_id: None,
+            title: Some(todo.title),
+            complete: Some(todo.complete),
+            due_date: todo.due_date.map(date_time).transpose()?,
+            notes: None,
+            priority: None,
+            auto_complete: None,
+            attach_tags: BTreeSet::new(),
+            detach_tags: BTreeSet::new(),
+            add_blockers: BTreeSet::new(),
+            remove_blockers: BTreeSet::new(),
+        })
+    }
+}
+
+impl TryFrom<UpdateListsRequest> for Vec<UpdateList> {
+    type Error = String;
+
+    fn try_from(req: UpdateListsRequest) -> Result<Self, String> {
+        collect(req.lists)
+    }
+}
+
+impl TryFrom<UpdateSetsRequest> for Vec<UpdateSet> {
+    type Error = String;
+
+    fn try_from(req: UpdateSetsRequest) -> Result<Self, String> {
+        collect(req.sets)
+    }
+}
+
+impl TryFrom<UpdateToDosRequest> for Vec<UpdateToDo> {
+    type Error = String;
+
+    fn try_from(req: UpdateToDosRequest) -> Result<Self, String> {
+        collect(req.todos)
+    }
+}
+
+impl From<BTreeSet<crate::types::List>> for UpdateListsResponse {
+    fn from(_: BTreeSet<crate::types::List>) -> Self {
+        UpdateListsResponse { success: true }
+    }
+}
+
+impl From<BTreeSet<crate::types::Set>> for UpdateSetsResponse {
+    fn from(_: BTreeSet<crate::types::Set>) -> Self {
+        UpdateSetsResponse { success: true }
+    }
+}
+
+impl From<BTreeSet<crate::types::ToDo>> for UpdateToDosResponse {
+    fn from(_: BTreeSet<crate::types::ToDo>) -> Self {
+        UpdateToDosResponse { success: true }
+    }
+}
+
+// DELETE
+
+impl TryFrom<DeleteListsRequest> for HashSet<ListID> {
+    type Error = String;
+
+    fn try_from(req: DeleteListsRequest) -> Result<Self, String> {
+        req.lids
+            .iter()
+            .map(|lid| parse_id(ListID::parse, "lid", lid))
+            .collect()
+    }
+}
+
+impl TryFrom<DeleteSetsRequest> for HashSet<SetQueryTarget> {
+    type Error = String;
+
+    fn try_from(req: DeleteSetsRequest) -> Result<Self, String> {
+        collect(req.addresses)
+    }
+}
+
+impl TryFrom<DeleteToDosRequest> for HashSet<ToDoQueryTarget> {
+    type Error = String;
+
+    fn try_from(req: DeleteToDosRequest) -> Result<Self, String> {
+        collect(req.addresses)
+    }
+}
+
+impl From<HashSet<ListID>> for DeleteListsResponse {
+    fn from(_: HashSet<ListID>) -> Self {
+        DeleteListsResponse { success: true }
+    }
+}
+
+impl From<HashSet<SetID>> for DeleteSetsResponse {
+    fn from(_: HashSet<SetID>) -> Self {
+        DeleteSetsResponse { success: true }
+    }
+}
+
+impl From<HashSet<ToDoID>> for DeleteToDosResponse {
+    fn from(_: HashSet<ToDoID>) -> Self {
+        DeleteToDosResponse { success: true }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn due_date() -> DateTime<Utc> {
+        "2025-01-02T09:30:00.123456789Z".parse().unwrap()
+    }
+
+    #[test]
+    fn timestamps_round_trip() {
+        let ts = timestamp(due_date());
+        assert_eq!(ts.seconds, 1_735_810_200);
+        assert_eq!(ts.nanos, 123_456_789);
+        assert_eq!(date_time(ts), Ok(due_date()));
+
+        let before_epoch: DateTime<Utc> = "1969-12-31T23:59:59.5Z".parse().unwrap();
+        assert_eq!(date_time(timestamp(before_epoch)), Ok(before_epoch));
+
+        assert!(
+            date_time(Timestamp {
+                seconds: 0,
+                nanos: -1
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn entities_round_trip() {
+        let list = crate::types::List::try_from(List::from(crate::types::List {
+            id: ListID(3),
+            owner_id: Default::default(),
+            title: "Chores".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }))
+        .unwrap();
+        assert_eq!((list.id, list.title.as_str()), (ListID(3), "Chores"));
+
+        let message = Set {
+            lid: ListID(3).public(),
+            sid: SetID(5).public(),
+            title: "Bath Room".to_string(),
+            todos: Vec::new(),
+            number: 2,
+        };
+        let set = crate::types::Set::try_from(message.clone()).unwrap();
+        assert_eq!((set.id, set.list_id, set.sid), (SetID(5), ListID(3), 2));
+        assert_eq!(Set::from(set), message);
+
+        for (sid, due_date) in [(Some(SetID(5)), Some(due_date())), (None, None)] {
+            let message = ToDo {
+                lid: ListID(3).public(),
+                sid: sid.map(SetID::public),
+                tdid: ToDoID(8).public(),
+                title: "Scrub Toilet Bowl".to_string(),
+                complete: true,
+                due_date: due_date.map(timestamp),
+                number: 4,
+            };
+            let todo = crate::types::ToDo::try_from(message.clone()).unwrap();
+            assert_eq!(
+                (todo.id, todo.set_id, todo.list_id),
+                (ToDoID(8), sid, ListID(3))
+            );
+            assert_eq!(todo.due_date, due_date);
+            assert_eq!(todo.tdid, 4);
+            assert!(todo.complete);
+            assert_eq!(ToDo::from(todo), message);
+        }
+    }
+
+    #[test]
+    fn create_data_round_trips() {
+        let message = ToDoCreateData {
+            title: "Sweep Floor".to_string(),
+            due_date: Some(timestamp(due_date())),
+            lid: ListID(3).public(),
+            sid: Some(SetID(5).public()),
+        };
+        let todo = CreateToDo::try_from(message.clone()).unwrap();
+        assert_eq!(todo.due_date, Some(due_date()));
+        assert_eq!((todo.list_id, todo.set_id), (ListID(3), Some(SetID(5))));
+        assert_eq!(ToDoCreateData::from(todo), message);
+
+        let message = SetCreateData {
+            title: "Kitchen".to_string(),
+            lid: ListID(3).public(),
+        };
+        assert_eq!(
+            SetCreateData::from(CreateSet::try_from(message.clone()).unwrap()),
+            message
+        );
+
+        // Plain row ids are read too, like they are in JSON.
+        let todo = CreateToDo::try_from(ToDoCreateData {
+            lid: "3".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!((todo.list_id, todo.due_date), (ListID(3), None));
+    }
+
+    #[test]
+    fn requests_convert_to_targets() {
+        let targets = HashSet::<ToDoQueryTarget>::try_from(ReadToDosRequest {
+            addresses: vec![
+                ToDoAddress {
+                    address: Some(to_do_address::Address::WholeSet(to_do_address::WholeSet {
+                        lid: ListID(3).public(),
+                        sid: "2".to_string(),
+                    })),
+                },
+                ToDoAddress {
+                    address: Some(to_do_address::Address::Singular(
+                        to_do_address::SingularToDo {
+                            lid: ListID(3).public(),
+                            sid: None,
+                            tdid: "4".to_string(),
+                        },
+                    )),
+                },
+            ],
+        })
+        .unwrap();
+        assert_eq!(
+            targets,
+            HashSet::from([
+                ToDoQueryTarget::Address(crate::types::ToDoAddress::WholeSet(ListID(3), 2)),
+                ToDoQueryTarget::Address(crate::types::ToDoAddress::Singular(ListID(3), None, 4)),
+            ])
+        );
+
+        // The list has to be a list's id, and the numbers have to be numbers.
+        let singular = |lid: String, sid: &str| SetAddress {
+            address: Some(set_address::Address::Singular(set_address::SingularSet {
+                lid,
+                sid: sid.to_string(),
+            })),
+        };
+        assert_eq!(
+            SetQueryTarget::try_from(singular(ListID(3).public(), "2")),
+            Ok(SetQueryTarget::Address(crate::types::SetAddress::Singular(
+                ListID(3),
+                2
+            )))
+        );
+        assert!(SetQueryTarget::try_from(singular(SetID(3).public(), "2")).is_err());
+        assert!(SetQueryTarget::try_from(singular(String::new(), "2")).is_err());
+        assert!(
+            SetQueryTarget::try_from(singular(ListID(3).public(), &SetID(5).public())).is_err()
+        );
+        assert!(SetQueryTarget::try_from(singular(ListID(3).public(), "0")).is_err());
+
+        let update = UpdateToDo::try_from(ToDoChangeData {
+            lid: ListID(3).public(),
+            sid: None,
+            tdid: ToDoID(8).public(),
+            title: "Mop Floor".to_string(),
+            due_date: Some(timestamp(due_date())),
+            complete: true,
+        })
+        .unwrap();
+        assert_eq!(update.target, ToDoQueryTarget::ToDo(ToDoID(8)));
+        assert_eq!(update.due_date, Some(due_date()));
+        assert_eq!(update.complete, Some(true));
+
+        assert!(
+            HashSet::<ListID>::try_from(DeleteListsRequest {
+                lids: vec![SetID(5).public()],
+            })
+            .is_err()
+        );
+        assert!(SetQueryTarget::try_from(SetAddress { address: None }).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{List, Set, ToDo};
+
+/// What a share link shows: one list with its sets and to dos, in their user-defined order.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct SharedListDocument {
+    pub list: List,
+    pub sets: Vec<Set>,
+    pub todos: Vec<ToDo>,
+}
@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{ListID, Priority, SetID, ToDo, ToDoID};
+
+/// A to do as written to a CSV export, with its list and set titles resolved.
+#[derive(Serialize, Debug)]
+pub struct CsvToDoRecord {
+    pub id: ToDoID,
+    pub list_id: ListID,
+    pub list: String,
+    pub set_id: Option<SetID>,
+    pub set: Option<String>,
+    pub title: String,
+    pub complete: bool,
+    pub due_date: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// A to do as read from a CSV import. Lists and sets are addressed by title, and any other
+/// columns (like the ids in an export) are ignored.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct CsvToDoRow {
+    pub list: String,
+    #[serde(default)]
+    pub set: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub complete: Option<bool>,
+    #[serde(default)]
+    pub due_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+pub struct CsvRowError {
+    pub line: u64,
+    pub error: String,
+}
+
+/// The rows of a CSV import that parsed, and the ones that didn't.
+#[derive(Debug, Default)]
+pub struct CsvImport {
+    pub rows: Vec<CsvToDoRow>,
+    pub errors: Vec<CsvRowError>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct CsvImportReport {
+    pub created: HashSet<ToDo>,
+    pub errors: Vec<CsvRowError>,
+}
+
+impl CsvImport {
+    /// Parses a CSV document with a header row. Only a missing or unreadable header fails the
+    /// whole document; every other problem is reported against the row it happened on.
+    pub fn parse(body: &[u8]) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(body);
+        let headers = reader.headers()?.clone();
+
+        let mut import = CsvImport::default();
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(err) => {
+                    import.errors.push(CsvRowError {
+                        line: err.position().map(|pos| pos.line()).unwrap_or_default(),
+                        error: err.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let line = record.position().map(|pos| pos.line()).unwrap_or_default();
+
+            match record.deserialize::<CsvToDoRow>(Some(&headers)) {
+                Ok(row) if row.list.is_empty() => import.errors.push(CsvRowError {
+                    line,
+                    error: "Missing list title".to_string(),
+                }),
+                Ok(row) if row.title.is_empty() => import.errors.push(CsvRowError {
+                    line,
+                    error: "Missing to do title".to_string(),
+                }),
+                Ok(mut row) => {
+                    row.set = row.set.filter(|set| !set.is_empty());
+                    row.notes = row.notes.filter(|notes| !notes.is_empty());
+                    import.rows.push(row);
+                }
+                Err(err) => import.errors.push(CsvRowError {
+                    line,
+                    error: err.to_string(),
+                }),
+            }
+        }
+
+        Ok(import)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty() && self.errors.is_empty()
+    }
+}
+
+pub fn write_csv_records(records: &[CsvToDoRecord]) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer.serialize(record)?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|err| csv::Error::from(err.into_error()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_rows_and_reports_bad_ones() {
+        let body = b"list,set,title,complete,due_date,priority\n\
+            Chores,Clean Bath Room,Scrub Toilet Bowl,false,2025-01-01T00:00:00Z,high\n\
+            Chores,,Sweep Floor,,,\n\
+            Chores,,,true,,\n\
+            Chores,,Mop Floor,maybe,,\n";
+
+        let import = CsvImport::parse(body).unwrap();
+
+        assert_eq!(
+            import.rows,
+            vec![
+                CsvToDoRow {
+                    list: "Chores".to_string(),
+                    set: Some("Clean Bath Room".to_string()),
+                    title: "Scrub Toilet Bowl".to_string(),
+                    complete: Some(false),
+                    due_date: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+                    priority: Some(Priority::High),
+                    notes: None,
+                },
+                CsvToDoRow {
+                    list: "Chores".to_string(),
+                    set: None,
+                    title: "Sweep Floor".to_string(),
+                    complete: None,
+                    due_date: None,
+                    priority: None,
+                    notes: None,
+                },
+            ]
+        );
+        assert_eq!(
+            import.errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+    }
+
+    #[test]
+    fn export_round_trips_through_import() {
+        let records = vec![CsvToDoRecord {
+            id: ToDoID(7),
+            list_id: ListID(1),
+            list: "Community".to_string(),
+            set_id: None,
+            set: None,
+            title: "Go to Church".to_string(),
+            complete: true,
+            due_date: None,
+            priority: Priority::Low,
+            notes: Some("Bring the hymnal, it's *Advent*".to_string()),
+            created_at: "2025-01-01T09:00:00Z".parse().unwrap(),
+            updated_at: "2025-01-05T11:00:00Z".parse().unwrap(),
+            completed_at: Some("2025-01-05T11:00:00Z".parse().unwrap()),
+        }];
+
+        let body = write_csv_records(&records).unwrap();
+        let import = CsvImport::parse(&body).unwrap();
+
+        assert!(import.errors.is_empty());
+        assert_eq!(
+            import.rows,
+            vec![CsvToDoRow {
+                list: "Community".to_string(),
+                set: None,
+                title: "Go to Church".to_string(),
+                complete: Some(true),
+                due_date: None,
+                priority: Some(Priority::Low),
+                notes: Some("Bring the hymnal, it's *Advent*".to_string()),
+            }]
+        );
+    }
+}
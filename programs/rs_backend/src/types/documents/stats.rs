@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{ListID, SetID};
+
+/// Progress of every list and set, for the dashboard.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default)]
+pub struct Stats {
+    pub lists: Vec<ListStats>,
+    /// A set's numbers include the to dos of the sets nested inside it.
+    pub sets: Vec<SetStats>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, PartialEq)]
+pub struct ListStats {
+    pub list_id: ListID,
+    #[serde(flatten)]
+    pub todos: ToDoStats,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, PartialEq)]
+pub struct SetStats {
+    pub set_id: SetID,
+    pub list_id: ListID,
+    #[serde(flatten)]
+    pub todos: ToDoStats,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default, PartialEq)]
+pub struct ToDoStats {
+    pub total: i64,
+    pub completed: i64,
+    /// Incomplete to dos whose due date has passed.
+    pub overdue: i64,
+    /// The share of completed to dos, from 0 to 100. Empty lists and sets are at 0.
+    pub completion: f64,
+    /// The earliest due date of an incomplete to do that isn't overdue yet.
+    pub next_due_date: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,293 @@
+use std::{borrow::Cow, collections::HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::types::{Priority, ToDo};
+
+/// The list used for tasks that don't have a project.
+pub const TASKWARRIOR_DEFAULT_LIST: &str = "Inbox";
+
+/// A task in the format read and written by `task import` and `task export`. Fields this server
+/// has no use for (urgency, tags, annotations, ...) are ignored on import.
+#[derive(Serialize, Deserialize, ToSchema, Debug, PartialEq)]
+pub struct TaskwarriorTask {
+    pub uuid: Uuid,
+    pub description: String,
+    pub status: TaskwarriorStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskwarriorPriority>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "taskwarrior_date"
+    )]
+    #[schema(value_type = Option<String>, example = "20250101T120000Z")]
+    pub entry: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "taskwarrior_date"
+    )]
+    #[schema(value_type = Option<String>, example = "20250101T120000Z")]
+    pub due: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "taskwarrior_date"
+    )]
+    #[schema(value_type = Option<String>, example = "20250101T120000Z")]
+    pub end: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskwarriorStatus {
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "waiting")]
+    Waiting,
+    #[serde(rename = "completed")]
+    Completed,
+    #[serde(rename = "deleted")]
+    Deleted,
+    #[serde(rename = "recurring")]
+    Recurring,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskwarriorPriority {
+    #[serde(rename = "L")]
+    Low,
+    #[serde(rename = "M")]
+    Medium,
+    #[serde(rename = "H")]
+    High,
+}
+
+impl From<TaskwarriorPriority> for Priority {
+    fn from(priority: TaskwarriorPriority) -> Self {
+        match priority {
+            TaskwarriorPriority::Low => Priority::Low,
+            TaskwarriorPriority::Medium => Priority::Medium,
+            TaskwarriorPriority::High => Priority::High,
+        }
+    }
+}
+
+impl TaskwarriorPriority {
+    /// Taskwarrior has no "none" priority, a task without one just leaves the field out.
+    pub fn from_priority(priority: Priority) -> Option<Self> {
+        match priority {
+            Priority::None => None,
+            Priority::Low => Some(TaskwarriorPriority::Low),
+            Priority::Medium => Some(TaskwarriorPriority::Medium),
+            Priority::High => Some(TaskwarriorPriority::High),
+        }
+    }
+}
+
+impl TaskwarriorTask {
+    /// The project of a to do in the list `list`, and the set `set` if it's in one, like
+    /// `Chores.Bathroom`. Dots and backslashes in the titles are escaped with a backslash, so a
+    /// dot of a title doesn't read back as the start of a set.
+    pub fn project(list: &str, set: Option<&str>) -> String {
+        let escape = |title: &str| title.replace('\\', "\\\\").replace('.', "\\.");
+        match set {
+            Some(set) => format!("{}.{}", escape(list), escape(set)),
+            None => escape(list),
+        }
+    }
+
+    /// Splits a project like `Chores.Bathroom` into the list "Chores" and the set "Bathroom".
+    /// Anything after the first dot that isn't escaped names the set.
+    pub fn list_and_set(&self) -> (Cow<'_, str>, Option<Cow<'_, str>>) {
+        let project = match self.project.as_deref() {
+            None | Some("") => return (Cow::Borrowed(TASKWARRIOR_DEFAULT_LIST), None),
+            Some(project) => project,
+        };
+
+        let mut escaped = false;
+        for (i, c) in project.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '.' => {
+                    let set = Some(&project[i + 1..]).filter(|set| !set.is_empty());
+                    return (unescape(&project[..i]), set.map(unescape));
+                }
+                _ => {}
+            }
+        }
+        (unescape(project), None)
+    }
+
+    pub fn complete(&self) -> bool {
+        self.status == TaskwarriorStatus::Completed
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority.map(Priority::from).unwrap_or_default()
+    }
+
+    /// Deleted tasks and recurrence templates don't map to a to do.
+    pub fn is_importable(&self) -> bool {
+        !matches!(
+            self.status,
+            TaskwarriorStatus::Deleted | TaskwarriorStatus::Recurring
+        )
+    }
+}
+
+fn unescape(title: &str) -> Cow<'_, str> {
+    if !title.contains('\\') {
+        return Cow::Borrowed(title);
+    }
+    let mut unescaped = String::with_capacity(title.len());
+    let mut chars = title.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+#[derive(Serialize, ToSchema, Debug, Default)]
+pub struct TaskwarriorImportSummary {
+    pub created: HashSet<ToDo>,
+    pub updated: HashSet<ToDo>,
+    pub skipped: Vec<Uuid>,
+}
+
+/// Taskwarrior writes dates as `20250101T120000Z`. RFC 3339 is accepted on import as well.
+mod taskwarrior_date {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    const FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+    pub fn serialize<S: Serializer>(
+        date: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => serializer.serialize_str(&date.format(FORMAT).to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        let Some(text) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+
+        if let Ok(date) = NaiveDateTime::parse_from_str(&text, FORMAT) {
+            return Ok(Some(date.and_utc()));
+        }
+
+        match DateTime::parse_from_rfc3339(&text) {
+            Ok(date) => Ok(Some(date.with_timezone(&Utc))),
+            Err(_) => Err(D::Error::custom(format!(
+                "invalid Taskwarrior date '{}'",
+                text
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_task_export() {
+        let export = r#"[
+            {"id":1,"description":"Scrub Toilet Bowl","due":"20250102T000000Z","entry":"20250101T120000Z","modified":"20250101T120000Z","priority":"H","project":"Chores.Bath Room","status":"pending","uuid":"5b3c4ec5-5a8d-4bd7-9d5d-1a5b4ac1e0f2","urgency":8.2},
+            {"id":0,"description":"Go to Church","end":"20250103T100000Z","entry":"20250101T120000Z","status":"completed","uuid":"0a0f5c16-2a7c-4b77-8d6e-b3b1ff1ee1a1"}
+        ]"#;
+
+        let tasks: Vec<TaskwarriorTask> = serde_json::from_str(export).unwrap();
+
+        assert_eq!(
+            tasks[0].list_and_set(),
+            ("Chores".into(), Some("Bath Room".into()))
+        );
+        assert_eq!(tasks[0].due, Some("2025-01-02T00:00:00Z".parse().unwrap()));
+        assert!(!tasks[0].complete());
+        assert_eq!(tasks[0].priority(), Priority::High);
+        assert_eq!(tasks[1].priority(), Priority::None);
+        assert_eq!(
+            tasks[1].list_and_set(),
+            (TASKWARRIOR_DEFAULT_LIST.into(), None)
+        );
+        assert!(tasks[1].complete());
+    }
+
+    #[test]
+    fn dots_in_titles_survive_the_project() {
+        let task = |project: String| TaskwarriorTask {
+            uuid: Uuid::nil(),
+            description: "Sweep Floor".to_string(),
+            status: TaskwarriorStatus::Pending,
+            project: Some(project),
+            priority: None,
+            entry: None,
+            due: None,
+            end: None,
+        };
+
+        for (list, set) in [
+            ("Chores", Some("Bath Room")),
+            ("v1.2 Release", Some("Docs.rs")),
+            ("C:\\Temp.", None),
+            ("Chores", None),
+        ] {
+            let project = TaskwarriorTask::project(list, set);
+            assert_eq!(
+                task(project).list_and_set(),
+                (list.into(), set.map(Cow::from))
+            );
+        }
+        assert_eq!(
+            TaskwarriorTask::project("v1.2 Release", Some("Docs")),
+            "v1\\.2 Release.Docs"
+        );
+        // Only the first dot that isn't escaped ends the list.
+        assert_eq!(
+            task("Chores.Bath.Room".to_string()).list_and_set(),
+            ("Chores".into(), Some("Bath.Room".into()))
+        );
+    }
+
+    #[test]
+    fn writes_task_export() {
+        let task = TaskwarriorTask {
+            uuid: Uuid::nil(),
+            description: "Sweep Floor".to_string(),
+            status: TaskwarriorStatus::Pending,
+            project: Some("Chores".to_string()),
+            priority: Some(TaskwarriorPriority::Low),
+            entry: None,
+            due: Some("2025-01-02T09:30:00Z".parse().unwrap()),
+            end: None,
+        };
+
+        let json = serde_json::to_string(&task).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"uuid":"00000000-0000-0000-0000-000000000000","description":"Sweep Floor","status":"pending","project":"Chores","priority":"L","due":"20250102T093000Z"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<TaskwarriorTask>(&json).unwrap(),
+            task
+        );
+    }
+}
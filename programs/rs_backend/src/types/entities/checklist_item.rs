@@ -0,0 +1,46 @@
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{ChecklistItemID, ToDoID};
+
+/// A step of a to do's checklist.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ChecklistItem {
+    pub id: ChecklistItemID,
+    pub todo_id: ToDoID,
+    pub title: String,
+    pub done: bool,
+    #[serde(default)]
+    pub position: f64,
+}
+
+impl PartialEq for ChecklistItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for ChecklistItem {}
+
+impl PartialOrd for ChecklistItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ChecklistItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl Hash for ChecklistItem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
@@ -3,14 +3,23 @@ use std::{
     hash::{Hash, Hasher},
 };
 
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::types::ListID;
+use crate::types::{ListID, UserID};
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 pub struct List {
     pub id: ListID,
+    /// Ignored on import, where lists go to the importing user.
+    #[serde(default)]
+    pub owner_id: UserID,
     pub title: String,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
 }
 
 impl PartialEq for List {
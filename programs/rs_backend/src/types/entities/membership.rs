@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{List, ListID, UserID};
+
+/// What a member can do with a list shared with them.
+#[derive(
+    Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+pub enum Role {
+    /// Can read the list, its sets, to dos and checklist items.
+    #[serde(rename = "viewer")]
+    Viewer,
+    /// Can also create, change, move and delete its sets, to dos and checklist items.
+    #[serde(rename = "editor")]
+    Editor,
+}
+
+impl Role {
+    /// The name this role is stored as.
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+        }
+    }
+
+    /// Reads a stored role. The table only allows known names, anything else reads as a viewer.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "editor" => Role::Editor,
+            _ => Role::Viewer,
+        }
+    }
+}
+
+/// A membership is keyed by its list and user.
+#[derive(
+    Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+pub struct MembershipID {
+    pub list_id: ListID,
+    pub user_id: UserID,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Membership {
+    pub list_id: ListID,
+    pub user_id: UserID,
+    pub username: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A list someone else owns, along with the role it's shared with.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct SharedList {
+    #[serde(flatten)]
+    pub list: List,
+    pub role: Role,
+}
@@ -1,11 +1,17 @@
+mod checklist_item;
 mod list;
+mod membership;
 mod set;
+mod share_link;
+mod tag;
 mod todo;
+mod user;
 
+pub use checklist_item::*;
 pub use list::*;
+pub use membership::*;
 pub use set::*;
+pub use share_link::*;
+pub use tag::*;
 pub use todo::*;
-
-pub type ListID = i32;
-pub type SetID = i32;
-pub type ToDoID = i32;
+pub use user::*;
@@ -3,15 +3,29 @@ use std::{
     hash::{Hash, Hasher},
 };
 
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::types::{ListID, SetID};
+use crate::types::{ListID, SetID, SetNumber};
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 pub struct Set {
     pub id: SetID,
     pub list_id: ListID,
+    #[serde(default)]
+    pub parent_id: Option<SetID>,
+    /// Ignored on import, where sets are numbered again in their new list.
+    #[serde(default)]
+    #[schema(value_type = i64)]
+    pub sid: SetNumber,
     pub title: String,
+    #[serde(default)]
+    pub position: f64,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
 }
 
 impl PartialEq for Set {
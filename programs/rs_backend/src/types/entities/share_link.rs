@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{ListID, ShareLinkID};
+
+/// A read-only link to a list, served at `/share/{token}` to anyone who has it.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShareLink {
+    pub id: ShareLinkID,
+    pub list_id: ListID,
+    /// Only returned when the link is created, as the server keeps just its hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
@@ -1,21 +1,88 @@
 use std::{
     cmp::Ordering,
+    collections::BTreeSet,
     hash::{Hash, Hasher},
 };
 
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
 
-use crate::types::{ListID, SetID, ToDoID};
+use crate::types::{ChecklistItem, ListID, SetID, TagID, ToDoID, ToDoNumber};
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 pub struct ToDo {
     pub id: ToDoID,
     pub set_id: Option<SetID>,
     pub list_id: ListID,
+    /// Ignored on import, where to dos are numbered again in their new list.
+    #[serde(default)]
+    #[schema(value_type = i64)]
+    pub tdid: ToDoNumber,
     pub title: String,
     pub complete: bool,
     pub due_date: Option<DateTime<Utc>>,
+    /// The to do's identity in Taskwarrior, which it keeps through exports and imports.
+    #[serde(default)]
+    pub uuid: Option<Uuid>,
+    #[serde(default)]
+    pub position: f64,
+    #[serde(default)]
+    pub tags: BTreeSet<TagID>,
+    /// The to dos that have to be complete before this one can be worked on.
+    #[serde(default)]
+    pub blocked_by: BTreeSet<ToDoID>,
+    /// Free-form Markdown.
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Whether the to do completes itself once every item of its checklist is done.
+    #[serde(default)]
+    pub auto_complete: bool,
+    /// Only filled in when a read asks for checklists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checklist: Option<Vec<ChecklistItem>>,
+}
+
+/// How urgent a to do is. Priorities are ordered, so `high` sorts after `low`.
+#[derive(
+    Serialize, Deserialize, ToSchema, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub enum Priority {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "low")]
+    Low,
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "high")]
+    High,
+}
+
+impl Priority {
+    /// The number this priority is stored as.
+    pub fn level(self) -> i64 {
+        self as i64
+    }
+
+    /// Reads a stored priority. Levels out of range are clamped.
+    pub fn from_level(level: i64) -> Self {
+        match level {
+            ..=0 => Priority::None,
+            1 => Priority::Low,
+            2 => Priority::Medium,
+            3.. => Priority::High,
+        }
+    }
 }
 
 impl PartialEq for ToDo {
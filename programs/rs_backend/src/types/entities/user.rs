@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::UserID;
+
+/// An account. The password hash never leaves the database.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub id: UserID,
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A login. The token is sent back as the `session` cookie or as an `Authorization: Bearer`
+/// header on every other request.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct Session {
+    pub token: String,
+    pub user: User,
+    pub expires_at: DateTime<Utc>,
+}
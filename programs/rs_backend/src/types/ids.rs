@@ -0,0 +1,250 @@
+use std::{fmt, sync::OnceLock};
+
+use blake2::{Blake2b512, Digest};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, Visitor},
+};
+
+/// The key public ids are scrambled with. It's made once per database and loaded at startup, see
+/// [`init_public_ids`].
+pub type PublicIdKey = [u8; 32];
+
+static PUBLIC_ID_KEY: OnceLock<PublicIdKey> = OnceLock::new();
+
+const FEISTEL_ROUNDS: u8 = 8;
+
+/// Crockford's base 32, which leaves out letters that read like digits.
+const ALPHABET: &[u8; 32] = b"0123456789abcdefghjkmnpqrstvwxyz";
+
+/// Sets the key public ids are made with. Only the first call has an effect.
+pub fn init_public_ids(key: PublicIdKey) {
+    let _ = PUBLIC_ID_KEY.set(key);
+}
+
+fn public_id_key() -> &'static PublicIdKey {
+    PUBLIC_ID_KEY.get_or_init(|| {
+        if cfg!(test) {
+            [0; 32]
+        } else {
+            panic!("Public ids were used before their key was loaded")
+        }
+    })
+}
+
+/// The round function: a slice of a keyed hash of one half of the block. The prefix is mixed in,
+/// so the same row id gives unrelated public ids in different tables.
+fn round(prefix: &str, round: u8, half: u32) -> u32 {
+    let mut hasher = Blake2b512::new();
+    hasher.update(public_id_key());
+    hasher.update(prefix.as_bytes());
+    hasher.update([round]);
+    hasher.update(half.to_le_bytes());
+    let digest = hasher.finalize();
+    u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]])
+}
+
+/// Scrambles a row id with a Feistel network. It's a permutation of the 64 bit numbers, so every
+/// row id has exactly one public id and the other way around.
+fn scramble(prefix: &str, key: i64) -> u64 {
+    let block = key as u64;
+    let (mut left, mut right) = ((block >> 32) as u32, block as u32);
+    for i in 0..FEISTEL_ROUNDS {
+        (left, right) = (right, left ^ round(prefix, i, right));
+    }
+    (u64::from(left) << 32) | u64::from(right)
+}
+
+fn unscramble(prefix: &str, block: u64) -> i64 {
+    let (mut left, mut right) = ((block >> 32) as u32, block as u32);
+    for i in (0..FEISTEL_ROUNDS).rev() {
+        (left, right) = (right ^ round(prefix, i, left), left);
+    }
+    ((u64::from(left) << 32) | u64::from(right)) as i64
+}
+
+fn encode(prefix: &str, key: i64) -> String {
+    let block = scramble(prefix, key);
+    let mut id = String::with_capacity(prefix.len() + 14);
+    id.push_str(prefix);
+    id.push('_');
+    // 13 characters of 5 bits cover all 64, the first one only holding the top 4.
+    for i in (0..13).rev() {
+        id.push(ALPHABET[((block >> (i * 5)) & 0x1f) as usize] as char);
+    }
+    id
+}
+
+fn decode(prefix: &str, id: &str) -> Option<i64> {
+    let digits = id.strip_prefix(prefix)?.strip_prefix('_')?;
+    if digits.len() != 13 {
+        return None;
+    }
+
+    let mut block: u64 = 0;
+    for (i, digit) in digits.bytes().enumerate() {
+        let value = match digit.to_ascii_lowercase() {
+            b'o' => 0,
+            b'i' | b'l' => 1,
+            digit => ALPHABET.iter().position(|&c| c == digit)? as u64,
+        };
+        if i == 0 && value > 0xf {
+            return None;
+        }
+        block = (block << 5) | value;
+    }
+    Some(unscramble(prefix, block))
+}
+
+/// What every kind of public id has in common, for code that handles ids of any table.
+pub trait PublicId: Copy + Ord {
+    fn from_key(key: i64) -> Self;
+    fn key(self) -> i64;
+    fn public(self) -> String;
+}
+
+macro_rules! public_id {
+    ($(#[$doc:meta])* $name:ident, $prefix:literal, $noun:literal) => {
+        $(#[$doc])*
+        ///
+        /// Internally it's the row id, which never leaves the server: it's written out as an
+        /// opaque string and read back from one. Plain integer row ids are still read for now, so
+        /// clients written against them keep working while they move over.
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(pub i64);
+
+        impl $name {
+            /// The prefix the public form of these ids starts with.
+            pub const PREFIX: &'static str = $prefix;
+
+            /// The row id, for use in queries.
+            pub fn key(self) -> i64 {
+                self.0
+            }
+
+            /// The form the id is shown to clients in.
+            pub fn public(self) -> String {
+                encode(Self::PREFIX, self.0)
+            }
+
+            /// Reads the public form of an id, or a plain row id.
+            pub fn parse(id: &str) -> Option<Self> {
+                match id.parse::<i64>() {
+                    Ok(key) => Some($name(key)),
+                    Err(_) => decode(Self::PREFIX, id).map($name),
+                }
+            }
+        }
+
+        impl PublicId for $name {
+            fn from_key(key: i64) -> Self {
+                $name(key)
+            }
+
+            fn key(self) -> i64 {
+                $name::key(self)
+            }
+
+            fn public(self) -> String {
+                $name::public(self)
+            }
+        }
+
+        impl utoipa::PartialSchema for $name {
+            fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+                utoipa::openapi::ObjectBuilder::new()
+                    .schema_type(utoipa::openapi::schema::Type::String)
+                    .description(Some(concat!(
+                        "The public id of ", $noun, ", like \"", $prefix, "_…\". ",
+                        "Plain integer row ids are read too."
+                    )))
+                    .into()
+            }
+        }
+
+        impl utoipa::ToSchema for $name {}
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.public())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct IdVisitor;
+
+                impl Visitor<'_> for IdVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        write!(f, "{} id like \"{}_…\"", $noun, $prefix)
+                    }
+
+                    fn visit_i64<E: de::Error>(self, key: i64) -> Result<$name, E> {
+                        Ok($name(key))
+                    }
+
+                    fn visit_u64<E: de::Error>(self, key: u64) -> Result<$name, E> {
+                        i64::try_from(key)
+                            .map($name)
+                            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(key), &self))
+                    }
+
+                    fn visit_str<E: de::Error>(self, id: &str) -> Result<$name, E> {
+                        $name::parse(id).ok_or_else(|| E::invalid_value(de::Unexpected::Str(id), &self))
+                    }
+                }
+
+                deserializer.deserialize_any(IdVisitor)
+            }
+        }
+    };
+}
+
+/// A set's number within its list, the SID of the API design. Unlike a [`SetID`], it's only unique
+/// inside the list.
+pub type SetNumber = i64;
+
+/// A to do's number within its list, the TDID of the API design. Numbers are handed out per list,
+/// so they're unique within every set too, and a to do keeps its number when it changes sets.
+pub type ToDoNumber = i64;
+
+public_id!(ChecklistItemID, "item", "a checklist item");
+public_id!(ListID, "list", "a list");
+public_id!(SetID, "set", "a set");
+public_id!(ShareLinkID, "link", "a share link");
+public_id!(TagID, "tag", "a tag");
+public_id!(ToDoID, "todo", "a to do");
+public_id!(UserID, "user", "a user");
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn public_ids_round_trip() {
+        for key in [0, 1, 2, 41, i64::from(i32::MAX), -1, i64::MAX] {
+            let public = ListID(key).public();
+            assert!(public.starts_with("list_"));
+            assert_eq!(ListID::parse(&public), Some(ListID(key)));
+            assert_eq!(
+                ListID::parse(&public.to_uppercase().replace("LIST", "list")),
+                Some(ListID(key))
+            );
+        }
+
+        assert_ne!(ListID(1).public()[5..], SetID(1).public()[4..]);
+        assert_eq!(SetID::parse(&ListID(1).public()), None);
+        assert_eq!(ListID::parse("7"), Some(ListID(7)));
+
+        let ids: ListID = serde_json::from_str("[3]")
+            .map(|ids: Vec<ListID>| ids[0])
+            .unwrap();
+        assert_eq!(ids, ListID(3));
+        assert_eq!(
+            serde_json::to_string(&ListID(3)).unwrap(),
+            format!("\"{}\"", ListID(3).public())
+        );
+    }
+}
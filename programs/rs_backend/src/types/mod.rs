@@ -1,5 +1,9 @@
+mod documents;
 mod entities;
+mod ids;
 mod queries;
 
+pub use documents::*;
 pub use entities::*;
+pub use ids::*;
 pub use queries::*;
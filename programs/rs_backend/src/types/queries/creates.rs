@@ -1,24 +1,81 @@
+use std::collections::BTreeSet;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{ListID, Priority, Role, SetID, TagID, ToDoID};
 
-use crate::types::{ListID, SetID};
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct CreateChecklistItem {
+    pub todo_id: ToDoID,
+    pub title: String,
+    pub done: Option<bool>,
+}
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
 pub struct CreateList {
     pub title: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Shares a list with another user, who is picked by username.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct CreateMembership {
+    pub list_id: ListID,
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
 pub struct CreateSet {
     pub list_id: ListID,
+    /// The set to nest this one in, which must belong to the same list.
+    #[serde(default)]
+    pub parent_id: Option<SetID>,
     pub title: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct CreateShareLink {
+    pub list_id: ListID,
+    /// When the link stops working. Links without one work until they're revoked.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct CreateTag {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
 pub struct CreateToDo {
     pub list_id: ListID,
     pub set_id: Option<SetID>,
     pub title: String,
     pub complete: Option<bool>,
     pub due_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: BTreeSet<TagID>,
+    #[serde(default)]
+    pub blocked_by: BTreeSet<ToDoID>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub auto_complete: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct CreateUser {
+    pub username: String,
+    pub password: String,
+}
+
+/// Logs in.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct CreateSession {
+    pub username: String,
+    pub password: String,
 }
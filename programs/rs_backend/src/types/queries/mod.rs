@@ -1,7 +1,11 @@
 mod creates;
+mod moves;
+mod reads;
 mod targets;
 mod updates;
 
 pub use creates::*;
+pub use moves::*;
+pub use reads::*;
 pub use targets::*;
 pub use updates::*;
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{ChecklistItemID, SetID, ToDoID};
+
+/// Where a moved set, to do or checklist item ends up, relative to its siblings.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "target", content = "id")]
+pub enum Placement<ID> {
+    #[serde(rename = "first")]
+    First,
+    #[serde(rename = "last")]
+    Last,
+    #[serde(rename = "before")]
+    Before(ID),
+    #[serde(rename = "after")]
+    After(ID),
+}
+
+/// Moves a set within its list. `before`/`after` must name a set of the same list.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct MoveSet {
+    pub set_id: SetID,
+    pub to: Placement<SetID>,
+}
+
+/// Moves a to do within its list or set. `before`/`after` may name a to do in another list or
+/// set, in which case the moved to do joins that list or set.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct MoveToDo {
+    pub todo_id: ToDoID,
+    pub to: Placement<ToDoID>,
+}
+
+/// Moves a checklist item within its to do's checklist. `before`/`after` must name an item of
+/// the same to do.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct MoveChecklistItem {
+    pub item_id: ChecklistItemID,
+    pub to: Placement<ChecklistItemID>,
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::types::Priority;
+
+/// Sorting and filtering for to do reads, taken from the query string, e.g.
+/// `/api/to_dos?sort=due_date&complete=false&min_priority=medium`. Filters narrow down whatever
+/// the targets in the body select.
+#[derive(Serialize, Deserialize, IntoParams, Debug, Default, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct ToDoReadOptions {
+    #[serde(default)]
+    pub sort: ToDoSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub complete: Option<bool>,
+    pub min_priority: Option<Priority>,
+    pub due_after: Option<DateTime<Utc>>,
+    pub due_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub completed_after: Option<DateTime<Utc>>,
+    /// Keeps only incomplete to dos whose blockers are all complete.
+    #[serde(default)]
+    pub actionable: bool,
+    /// Embeds each to do's checklist items in the response.
+    #[serde(default)]
+    pub checklist: bool,
+}
+
+/// The query parameters of [`ToDoReadOptions`], which the to do reads take next to their targets.
+pub const TO_DO_READ_OPTIONS: &[&str] = &[
+    "sort",
+    "order",
+    "complete",
+    "min_priority",
+    "due_after",
+    "due_before",
+    "updated_after",
+    "completed_after",
+    "actionable",
+    "checklist",
+];
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ToDoSort {
+    /// The user-defined order, grouped by list and set.
+    #[default]
+    #[serde(rename = "position")]
+    Position,
+    #[serde(rename = "title")]
+    Title,
+    #[serde(rename = "due_date")]
+    DueDate,
+    #[serde(rename = "priority")]
+    Priority,
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "updated_at")]
+    UpdatedAt,
+    #[serde(rename = "completed_at")]
+    CompletedAt,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    #[serde(rename = "asc")]
+    Ascending,
+    #[serde(rename = "desc")]
+    Descending,
+}
@@ -1,17 +1,37 @@
 use serde::{Deserialize, Serialize};
+use utoipa::{
+    PartialSchema, ToSchema,
+    openapi::{
+        AllOfBuilder, ArrayBuilder, ObjectBuilder, OneOfBuilder, RefOr, Schema, schema::ArrayItems,
+    },
+};
 
-use crate::types::{ListID, SetID, ToDoID};
+use crate::types::{ChecklistItemID, ListID, SetID, SetNumber, TagID, ToDoID, ToDoNumber};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, PartialEq, Eq, Hash)]
 #[serde(tag = "target", content = "id")]
 pub enum SetQueryTarget {
     #[serde(rename = "list")]
     List(ListID),
     #[serde(rename = "set")]
     Set(SetID),
+    /// A set along with every set nested inside it, however deep.
+    #[serde(rename = "subtree")]
+    Subtree(SetID),
+    #[serde(rename = "address")]
+    Address(SetAddress),
 }
 
+/// Sets by where they sit rather than by id, as the API design addresses them.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum SetAddress {
+    #[serde(rename = "whole_list")]
+    WholeList(ListID),
+    #[serde(rename = "singular")]
+    Singular(ListID, SetNumber),
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, PartialEq, Eq, Hash)]
 #[serde(tag = "target", content = "id")]
 pub enum ToDoQueryTarget {
     #[serde(rename = "list")]
@@ -20,4 +40,96 @@ pub enum ToDoQueryTarget {
     Set(SetID),
     #[serde(rename = "todo")]
     ToDo(ToDoID),
+    /// The to dos of a set and of every set nested inside it, however deep.
+    #[serde(rename = "subtree")]
+    Subtree(SetID),
+    /// Every to do carrying a tag, whatever list it's in.
+    #[serde(rename = "tag")]
+    Tag(TagID),
+    #[serde(rename = "address")]
+    Address(ToDoAddress),
+}
+
+/// To dos by where they sit rather than by id, as the API design addresses them.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum ToDoAddress {
+    #[serde(rename = "whole_list")]
+    WholeList(ListID),
+    #[serde(rename = "whole_set")]
+    WholeSet(ListID, SetNumber),
+    /// A to do outside of any set has no set number.
+    #[serde(rename = "singular")]
+    Singular(ListID, Option<SetNumber>, ToDoNumber),
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, PartialEq, Eq, Hash)]
+#[serde(tag = "target", content = "id")]
+pub enum ChecklistItemQueryTarget {
+    #[serde(rename = "todo")]
+    ToDo(ToDoID),
+    #[serde(rename = "item")]
+    Item(ChecklistItemID),
+}
+
+// The derive can't describe variants holding more than one value, so the schemas of addresses
+// are written out: each variant is an object with one key, holding an id or a tuple.
+
+impl PartialSchema for SetAddress {
+    fn schema() -> RefOr<Schema> {
+        OneOfBuilder::new()
+            .item(variant("whole_list", ListID::schema()))
+            .item(variant(
+                "singular",
+                tuple([ListID::schema(), SetNumber::schema()]),
+            ))
+            .description(Some(
+                "Sets by where they sit rather than by id, as the API design addresses them.",
+            ))
+            .into()
+    }
+}
+
+impl ToSchema for SetAddress {}
+
+impl PartialSchema for ToDoAddress {
+    fn schema() -> RefOr<Schema> {
+        OneOfBuilder::new()
+            .item(variant("whole_list", ListID::schema()))
+            .item(variant(
+                "whole_set",
+                tuple([ListID::schema(), SetNumber::schema()]),
+            ))
+            .item(variant(
+                "singular",
+                tuple([
+                    ListID::schema(),
+                    Option::<SetNumber>::schema(),
+                    ToDoNumber::schema(),
+                ]),
+            ))
+            .description(Some(
+                "To dos by where they sit rather than by id, as the API design addresses them. A \
+                to do outside of any set has no set number.",
+            ))
+            .into()
+    }
+}
+
+impl ToSchema for ToDoAddress {}
+
+fn variant(name: &str, value: impl Into<RefOr<Schema>>) -> ObjectBuilder {
+    ObjectBuilder::new().property(name, value).required(name)
+}
+
+fn tuple<const N: usize>(items: [RefOr<Schema>; N]) -> RefOr<Schema> {
+    let items = items.map(|item| match item {
+        RefOr::T(schema) => schema,
+        RefOr::Ref(reference) => AllOfBuilder::new().item(reference).into(),
+    });
+    ArrayBuilder::new()
+        .items(ArrayItems::False)
+        .prefix_items(items)
+        .min_items(Some(N))
+        .max_items(Some(N))
+        .into()
 }
@@ -1,22 +1,56 @@
+use std::collections::BTreeSet;
+
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::{
+    ChecklistItemID, ListID, Priority, Role, SetID, SetQueryTarget, TagID, ToDoID, ToDoQueryTarget,
+    UserID,
+};
 
-use crate::types::{ListID, SetID, SetQueryTarget, ToDoQueryTarget};
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct UpdateChecklistItem {
+    pub item_id: ChecklistItemID,
+    pub title: Option<String>,
+    pub done: Option<bool>,
+}
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
 pub struct UpdateList {
     pub list_id: ListID,
     pub title: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct UpdateMembership {
+    pub list_id: ListID,
+    pub user_id: UserID,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
 pub struct UpdateSet {
     pub target: SetQueryTarget,
     pub list_id: Option<ListID>,
+    /// `null` moves the set to the top level of its list, while leaving the field out keeps
+    /// the current parent.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parent_id: Option<Option<SetID>>,
     pub title: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct UpdateTag {
+    pub tag_id: TagID,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
 pub struct UpdateToDo {
     pub target: ToDoQueryTarget,
     pub set_id: Option<SetID>,
@@ -24,4 +58,30 @@ pub struct UpdateToDo {
     pub title: Option<String>,
     pub complete: Option<bool>,
     pub due_date: Option<DateTime<Utc>>,
+    /// `null` clears the notes, while leaving the field out keeps them.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub notes: Option<Option<String>>,
+    pub priority: Option<Priority>,
+    pub auto_complete: Option<bool>,
+    #[serde(default)]
+    pub attach_tags: BTreeSet<TagID>,
+    #[serde(default)]
+    pub detach_tags: BTreeSet<TagID>,
+    #[serde(default)]
+    pub add_blockers: BTreeSet<ToDoID>,
+    #[serde(default)]
+    pub remove_blockers: BTreeSet<ToDoID>,
+}
+
+/// Tells a field that was set to `null` apart from one that was left out.
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }